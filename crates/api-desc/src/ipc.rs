@@ -0,0 +1,110 @@
+// Copyright 2023 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::*;
+
+pub(crate) fn new() -> Item {
+    let docs = docs! {
+        /// Message passing between applets.
+        ///
+        /// Applets are addressed by name. An applet only receives messages from the applets it
+        /// allows in its `wasefire-ipc` custom section (one applet name per line).
+    };
+    let name = "ipc".into();
+    let items = vec![
+        item! {
+            /// Describes errors passing messages.
+            enum Error {
+                /// A function pre-condition was broken.
+                InvalidArgument = 0,
+
+                /// The receiving applet is not loaded.
+                NotFound = 1,
+
+                /// The receiving applet doesn't allow messages from the sending applet.
+                NotAllowed = 2,
+
+                /// The mailbox of the receiving applet is full.
+                NoCapacity = 3,
+            }
+        },
+        item! {
+            /// Sends a message to an applet.
+            ///
+            /// The message is queued in the mailbox of the receiving applet, which is notified if
+            /// it registered a callback.
+            fn send "is" {
+                /// Name of the receiving applet.
+                applet_ptr: *const u8,
+
+                /// Length of the name of the receiving applet.
+                applet_len: usize,
+
+                /// Address of the message.
+                ptr: *const u8,
+
+                /// Length of the message.
+                ///
+                /// Messages can't be empty. The platform may limit the length of messages.
+                len: usize,
+            } -> {
+                /// Zero for success. Otherwise complement of error number.
+                res: isize,
+            }
+        },
+        item! {
+            /// Receives the oldest message from the mailbox, if any.
+            fn receive "ir" {
+                /// Where to write the name of the sending applet, if a message is received.
+                ///
+                /// The (inner) pointer will be allocated by the callee and must be freed by the
+                /// caller. It is thus owned by the caller when the function returns.
+                applet_ptr: *mut *mut u8,
+
+                /// Where to write the length of the name of the sending applet.
+                applet_len: *mut usize,
+
+                /// Where to write the message, if a message is received.
+                ///
+                /// The (inner) pointer will be allocated by the callee and must be freed by the
+                /// caller. It is thus owned by the caller when the function returns.
+                ptr: *mut *mut u8,
+
+                /// Where to write the length of the message.
+                len: *mut usize,
+            } -> {
+                /// One if received. Zero if the mailbox is empty. Otherwise complement of error
+                /// number.
+                res: isize,
+            }
+        },
+        item! {
+            /// Registers a callback when the mailbox is not empty.
+            ///
+            /// It is possible that the callback is spuriously called.
+            fn register "ie" {
+                /// Function pointer of the closure to call on messages.
+                handler_func: fn { data: *const u8 },
+
+                /// Opaque data of the closure to call on messages.
+                handler_data: *const u8,
+            } -> {}
+        },
+        item! {
+            /// Unregisters the callback.
+            fn unregister "id" {} -> {}
+        },
+    ];
+    Item::Mod(Mod { docs, name, items })
+}
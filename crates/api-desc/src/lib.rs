@@ -24,6 +24,7 @@ mod clock;
 mod crypto;
 mod debug;
 mod id;
+mod ipc;
 mod led;
 mod macros;
 mod rng;
@@ -44,6 +45,7 @@ impl Default for Api {
             clock::new(),
             crypto::new(),
             debug::new(),
+            ipc::new(),
             led::new(),
             rng::new(),
             scheduling::new(),
@@ -411,6 +413,7 @@ impl Mod {
         let mut inner = Vec::new();
         let mut merge = Vec::new();
         let mut descriptor = Vec::new();
+        let mut path = Vec::new();
         let mut iter = Vec::new();
         let mut id = Vec::new();
         let mut erase = Vec::new();
@@ -429,6 +432,7 @@ impl Mod {
                     merge.push(quote!(#pat(_) => Api::#name_camel(T::merge(erased)),));
                     descriptor
                         .push(quote!(#pat(_) => <#name::Sig as crate::Signature>::descriptor(),));
+                    path.push(quote!(#pat(_) => output.push(stringify!(#name)),));
                     iter.push(quote!(output.push(wrap(Api::#name_camel(#name::Sig)));));
                     id.push(quote!(#pat(_) => Api::#name_camel(#name::Sig),));
                     erase.push(quote!(#pat(x) => <T as crate::Dispatch>::erase(x),));
@@ -443,6 +447,7 @@ impl Mod {
                     inner.push(quote!(#name::Api<T>));
                     merge.push(quote!(#pat Api::#name_camel(x.merge(erased)),));
                     descriptor.push(quote!(#pat x.descriptor(),));
+                    path.push(quote!(#pat { output.push(stringify!(#name)); x.path(output) }));
                     iter.push(quote! {
                         #name::Api::<crate::Id>::iter(output, |x| wrap(Api::#name_camel(x)));
                     });
@@ -474,6 +479,10 @@ impl Mod {
                 pub fn descriptor(&self) -> crate::Descriptor {
                     match self { #(#descriptor)* }
                 }
+                /// Appends the path of the function (its modules and its name) to the output.
+                pub fn path(&self, output: &mut alloc::vec::Vec<&'static str>) {
+                    match self { #(#path)* }
+                }
                 // TODO: Find a solution to have this computed at compile time.
                 pub fn iter<T>(output: &mut alloc::vec::Vec<T>, wrap: impl Fn(Self) -> T) {
                     #(#iter)*
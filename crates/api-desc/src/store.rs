@@ -44,11 +44,13 @@ pub(crate) fn new() -> Item {
         item! {
             /// Inserts an entry in the store.
             ///
-            /// If an entry for that key was already present, it is overwritten.
+            /// If an entry for that key was already present, it is overwritten. The platform may
+            /// limit the total size of the entries of an applet.
             fn insert "si" {
                 /// Key of the entry.
                 ///
-                /// This must be smaller than 4096.
+                /// Keys are private to the applet. This must be smaller than a platform limit (the
+                /// platform shares the store between applets and reserves some keys).
                 key: usize,
 
                 /// Value of the entry.
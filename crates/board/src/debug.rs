@@ -35,6 +35,13 @@ pub trait Api {
     /// equivalent to not supporting this API.
     fn time() -> u64;
 
+    /// Reports the profile of an applet in folded-stack format.
+    ///
+    /// Each line is a call stack of semicolon-separated frames (from outermost to innermost)
+    /// followed by a space and the number of executed instructions. This format is accepted by
+    /// flamegraph tools. The default implementation does nothing.
+    fn profile(_stacks: &str) {}
+
     /// Exits the platform with a success/failure result.
     fn exit(success: bool) -> !;
 }
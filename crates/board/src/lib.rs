@@ -31,6 +31,7 @@ pub mod button;
 pub mod crypto;
 pub mod debug;
 pub mod led;
+pub mod protocol;
 pub mod rng;
 mod storage;
 pub mod timer;
@@ -65,10 +66,68 @@ pub trait Api {
         None
     }
 
+    /// Called when an applet exits abnormally.
+    ///
+    /// This lets the board report the exit (e.g. on a display or with a led). The default
+    /// implementation does nothing.
+    fn applet_exited(_name: &str, _exit: AppletExit) {}
+
+    /// Returns how applets are restarted after they exit abnormally.
+    ///
+    /// The default implementation never restarts applets.
+    fn restart_policy() -> RestartPolicy {
+        RestartPolicy::Never
+    }
+
+    /// Returns the memory pool for the linear memory of applets.
+    ///
+    /// The default implementation returns a static pool of 64KiB with a quota of 64KiB per applet
+    /// (enough for one applet with one page of linear memory).
+    ///
+    /// # Safety
+    ///
+    /// This function must be called at most once, because the pool is borrowed for the rest of
+    /// the program.
+    unsafe fn applet_memory() -> AppletMemory {
+        #[repr(align(16))]
+        struct Pool([u8; 0x10000]);
+        static mut POOL: Pool = Pool([0; 0x10000]);
+        // SAFETY: This function is called at most once by contract.
+        AppletMemory { pool: unsafe { &mut POOL.0 }, quota: 0x10000 }
+    }
+
+    /// Returns the maximum number of bytes an applet may store.
+    ///
+    /// This bounds the sum of the lengths of the values in the store namespace of each applet, such
+    /// that an applet can't fill the store for the others. The default implementation returns
+    /// `None` and applets are only limited by the store capacity.
+    fn applet_store_quota() -> Option<usize> {
+        None
+    }
+
+    /// Returns the public key that applets must be signed with.
+    ///
+    /// When this function returns a key, the scheduler rejects applets without a valid ECDSA
+    /// P-256 signature from this key. The default implementation returns `None` and applets are
+    /// not verified.
+    fn applet_trust_anchor() -> Option<TrustAnchor> {
+        None
+    }
+
+    /// Called when an applet is rejected because it is not signed by the trust anchor.
+    ///
+    /// This lets the board report the rejection. The default implementation does nothing.
+    fn applet_rejected(_name: &str) {}
+
+    /// Storage for the applet module.
+    ///
+    /// This is usually a dedicated flash region, distinct from [`Self::Storage`].
+    type Applet: Singleton + wasefire_store::Storage;
     type Button: button::Api;
     type Crypto: crypto::Api;
     type Debug: debug::Api;
     type Led: led::Api;
+    type Protocol: protocol::Api;
     type Rng: rng::Api;
     type Storage: Singleton + wasefire_store::Storage;
     type Timer: timer::Api;
@@ -105,6 +164,9 @@ pub enum Event<B: Api + ?Sized> {
     /// Button event.
     Button(button::Event<B>),
 
+    /// Protocol event.
+    Protocol(protocol::Event),
+
     /// Timer event.
     Timer(timer::Event<B>),
 
@@ -115,6 +177,55 @@ pub enum Event<B: Api + ?Sized> {
     Usb(usb::Event),
 }
 
+/// Reasons for an applet to exit abnormally.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AppletExit {
+    /// The applet trapped while executing.
+    Trap,
+
+    /// The applet trapped in a host function (e.g. invalid arguments or explicit abort).
+    HostTrap,
+}
+
+/// Policies to restart applets after they exit abnormally.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RestartPolicy {
+    /// The applet stays stopped until it is installed again.
+    Never,
+
+    /// The applet is restarted immediately.
+    Immediately,
+
+    /// The applet is restarted after a delay.
+    ///
+    /// The delay starts at `initial_ms` and doubles after each consecutive exit, up to `max_ms`.
+    Backoff { initial_ms: usize, max_ms: usize },
+}
+
+/// Memory pool for the linear memory of applets.
+#[derive(Debug)]
+pub struct AppletMemory {
+    /// The memory of the pool.
+    ///
+    /// It must be aligned to 16 bytes (the alignment of linear memories).
+    pub pool: &'static mut [u8],
+
+    /// The maximum number of bytes of linear memory of an applet.
+    pub quota: usize,
+}
+
+/// Public key of the platform trust anchor.
+///
+/// This is a P-256 point in affine coordinates, each encoded in big-endian.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TrustAnchor {
+    /// The x coordinate of the public key.
+    pub x: [u8; 32],
+
+    /// The y coordinate of the public key.
+    pub y: [u8; 32],
+}
+
 /// Errors that interfaces may return.
 ///
 /// Because a board interfaces between the user and the world, there's 2 types of errors: those due
@@ -130,10 +241,12 @@ pub enum Error {
     World,
 }
 
+pub type Applet<B> = <B as Api>::Applet;
 pub type Button<B> = <B as Api>::Button;
 pub type Crypto<B> = <B as Api>::Crypto;
 pub type Debug<B> = <B as Api>::Debug;
 pub type Led<B> = <B as Api>::Led;
+pub type Protocol<B> = <B as Api>::Protocol;
 pub type Rng<B> = <B as Api>::Rng;
 pub type Storage<B> = <B as Api>::Storage;
 pub type Timer<B> = <B as Api>::Timer;
@@ -216,10 +329,12 @@ mod tests {
                 todo!()
             }
 
+            type Applet = Unsupported;
             type Button = Unsupported;
             type Crypto = Unsupported;
             type Debug = Unsupported;
             type Led = Unsupported;
+            type Protocol = Unsupported;
             type Rng = Unsupported;
             type Storage = Unsupported;
             type Timer = Unsupported;
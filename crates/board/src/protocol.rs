@@ -0,0 +1,61 @@
+// Copyright 2023 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Platform protocol interface.
+//!
+//! This interface lets a host tool (like the `wasefire` CLI) send requests to the platform and
+//! receive responses. Messages are opaque to the board: they are interpreted by the scheduler. The
+//! board is only responsible for framing them over its transport (e.g. UART or USB serial).
+
+use alloc::boxed::Box;
+
+use crate::{Error, Support, Unsupported};
+
+/// Protocol event.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Event;
+
+impl<B: crate::Api> From<Event> for crate::Event<B> {
+    fn from(event: Event) -> Self {
+        crate::Event::Protocol(event)
+    }
+}
+
+/// Protocol interface.
+pub trait Api: Support<bool> {
+    /// Reads the next request, if any.
+    ///
+    /// Returns `None` if there are no pending requests.
+    fn read() -> Result<Option<Box<[u8]>>, Error>;
+
+    /// Writes a response to the last request.
+    fn write(response: &[u8]) -> Result<(), Error>;
+
+    /// Enables the protocol event to be triggered when a request is available.
+    fn enable() -> Result<(), Error>;
+}
+
+impl Api for Unsupported {
+    fn read() -> Result<Option<Box<[u8]>>, Error> {
+        unreachable!()
+    }
+
+    fn write(_: &[u8]) -> Result<(), Error> {
+        unreachable!()
+    }
+
+    fn enable() -> Result<(), Error> {
+        unreachable!()
+    }
+}
@@ -12,7 +12,150 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-fn main() {
-    println!("The Wasefire CLI is not yet implemented.");
-    std::process::exit(1);
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use clap::{Args, Parser, Subcommand};
+use wasefire_protocol::{AppletInfo, Request, Response};
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Flags {
+    #[command(flatten)]
+    connection: ConnectionOptions,
+
+    #[command(subcommand)]
+    action: Action,
+}
+
+#[derive(Args)]
+struct ConnectionOptions {
+    /// Unix socket of the host runner.
+    #[arg(long, default_value = "target/wasefire/protocol")]
+    unix: PathBuf,
+
+    /// Serial port of the board (e.g. /dev/ttyACM1).
+    ///
+    /// Takes precedence over --unix.
+    #[arg(long)]
+    serial: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum Action {
+    /// Installs an applet.
+    ///
+    /// An installed applet with the same name is replaced.
+    Install {
+        /// Path to the applet module.
+        wasm: PathBuf,
+
+        /// Name of the applet (defaults to the file stem of the module).
+        #[arg(long)]
+        name: Option<String>,
+    },
+
+    /// Lists the installed applets.
+    List,
+
+    /// Uninstalls an applet.
+    Uninstall {
+        /// Name of the applet.
+        name: String,
+    },
+
+    /// Prints the applet logs.
+    Logs {
+        /// Keeps printing new logs.
+        #[arg(long)]
+        follow: bool,
+    },
+}
+
+trait Connection: Read + Write {}
+impl<T: Read + Write + ?Sized> Connection for T {}
+
+impl ConnectionOptions {
+    fn connect(&self) -> Result<Box<dyn Connection>> {
+        Ok(match &self.serial {
+            Some(path) => {
+                let path = path.to_str().context("serial path is not UTF-8")?;
+                let port = serialport::new(path, 115200).timeout(Duration::from_secs(5));
+                Box::new(port.open().with_context(|| format!("opening {path}"))?)
+            }
+            None => Box::new(
+                UnixStream::connect(&self.unix)
+                    .with_context(|| format!("connecting to {}", self.unix.display()))?,
+            ),
+        })
+    }
+}
+
+/// Sends a request and returns the serialized response.
+fn call(connection: &mut dyn Connection, request: &Request) -> Result<Vec<u8>> {
+    connection.write_all(&wasefire_protocol::frame(&request.serialize()))?;
+    connection.flush()?;
+    let mut len = [0; 4];
+    connection.read_exact(&mut len).context("reading response length")?;
+    let mut response = vec![0; u32::from_le_bytes(len) as usize];
+    connection.read_exact(&mut response).context("reading response")?;
+    Ok(response)
+}
+
+fn parse(response: &[u8]) -> Result<Response> {
+    match Response::deserialize(response) {
+        Ok(Response::Error(error)) => bail!("platform error: {error}"),
+        Ok(response) => Ok(response),
+        Err(error) => bail!("invalid response: {error}"),
+    }
+}
+
+fn main() -> Result<()> {
+    let flags = Flags::parse();
+    let mut connection = flags.connection.connect()?;
+    let connection = &mut *connection;
+    match flags.action {
+        Action::Install { wasm, name } => {
+            let name = match name {
+                Some(x) => x,
+                None => wasm.file_stem().and_then(|x| x.to_str()).context("invalid name")?.into(),
+            };
+            let wasm =
+                std::fs::read(&wasm).with_context(|| format!("reading {}", wasm.display()))?;
+            let response = call(connection, &Request::Install { name: &name, wasm: &wasm })?;
+            match parse(&response)? {
+                Response::Ok => println!("Installed {name}."),
+                x => bail!("unexpected response: {x:?}"),
+            }
+        }
+        Action::List => match parse(&call(connection, &Request::List)?)? {
+            Response::List(applets) => {
+                for AppletInfo { name, size } in applets {
+                    println!("{name} ({size} bytes)");
+                }
+            }
+            x => bail!("unexpected response: {x:?}"),
+        },
+        Action::Uninstall { name } => {
+            let response = call(connection, &Request::Uninstall { name: &name })?;
+            match parse(&response)? {
+                Response::Ok => println!("Uninstalled {name}."),
+                x => bail!("unexpected response: {x:?}"),
+            }
+        }
+        Action::Logs { follow } => loop {
+            match parse(&call(connection, &Request::Logs)?)? {
+                Response::Logs(logs) => std::io::stdout().write_all(logs)?,
+                x => bail!("unexpected response: {x:?}"),
+            }
+            if !follow {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(500));
+        },
+    }
+    Ok(())
 }
@@ -15,13 +15,16 @@
 use wasefire_interpreter::*;
 
 fn main() {
-    // Create an empty store.
-    let mut store = Store::default();
+    // Create an empty linker.
+    let mut linker = Linker::default();
 
-    // Link a "println" function in module "env" in the store. This function takes 2 arguments and
+    // Link a "println" function in module "env" in the linker. This function takes 2 arguments and
     // doesn't return anything. The 2 arguments are the pointer and length of a buffer in the
     // calling module memory. This buffer is printed as a string.
-    store.link_func("env", "println", 2, 0).unwrap();
+    linker.link_func("env", "println", 2, 0).unwrap();
+
+    // Create an empty store using the linker.
+    let mut store = Store::new(&linker);
 
     // Validate a module. Here, we have `wasm2wat hello.wasm` print:
     //
@@ -57,6 +60,8 @@ fn main() {
                 assert!(results.is_empty());
                 break;
             }
+            // We didn't limit execution with fuel.
+            RunResult::OutOfFuel => unreachable!(),
         };
 
         // We only linked one function, which has thus index zero.
@@ -25,8 +25,71 @@ pub enum Error {
     Unsupported,
 
     /// Execution trapped.
-    // TODO: In debug mode, trap could describe the reason (like resource exhaustion, etc).
-    Trap,
+    Trap(TrapReason),
+}
+
+/// Reasons for execution to trap.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TrapReason {
+    /// An `unreachable` instruction was executed.
+    Unreachable,
+
+    /// A memory access was out of bounds.
+    MemoryOutOfBounds,
+
+    /// An atomic memory access was not naturally aligned.
+    UnalignedAtomic,
+
+    /// An atomic wait was on a memory that is not shared.
+    UnsharedMemory,
+
+    /// A table access was out of bounds.
+    TableOutOfBounds,
+
+    /// An indirect call was out of the bounds of its table.
+    UndefinedElement,
+
+    /// An indirect call was on a null reference.
+    UninitializedElement,
+
+    /// An indirect call was on a function with a different type.
+    IndirectCallTypeMismatch,
+
+    /// An integer was divided by zero.
+    IntegerDivideByZero,
+
+    /// An integer operation or conversion overflowed.
+    IntegerOverflow,
+
+    /// A NaN was converted to an integer.
+    InvalidConversionToInteger,
+
+    /// The call stack was exhausted.
+    StackExhausted,
+
+    /// An exception was not caught.
+    UncaughtException,
+}
+
+impl core::fmt::Display for TrapReason {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        // These are the messages of the specification tests.
+        f.write_str(match self {
+            TrapReason::Unreachable => "unreachable",
+            TrapReason::MemoryOutOfBounds => "out of bounds memory access",
+            TrapReason::UnalignedAtomic => "unaligned atomic",
+            TrapReason::UnsharedMemory => "expected shared memory",
+            TrapReason::TableOutOfBounds => "out of bounds table access",
+            TrapReason::UndefinedElement => "undefined element",
+            TrapReason::UninitializedElement => "uninitialized element",
+            TrapReason::IndirectCallTypeMismatch => "indirect call type mismatch",
+            TrapReason::IntegerDivideByZero => "integer divide by zero",
+            TrapReason::IntegerOverflow => "integer overflow",
+            TrapReason::InvalidConversionToInteger => "invalid conversion to integer",
+            TrapReason::StackExhausted => "call stack exhausted",
+            TrapReason::UncaughtException => "uncaught exception",
+        })
+    }
 }
 
 #[cfg(feature = "debug")]
@@ -55,10 +118,10 @@ pub fn unsupported() -> Error {
     Error::Unsupported
 }
 
-pub fn trap() -> Error {
+pub fn trap(reason: TrapReason) -> Error {
     #[cfg(feature = "debug")]
     print_backtrace();
-    Error::Trap
+    Error::Trap(reason)
 }
 
 pub fn check(cond: bool) -> Result<(), Error> {
@@ -13,8 +13,16 @@
 // limitations under the License.
 
 // TODO: Some toctou could be used instead of panic.
+#[cfg(feature = "debugger")]
+use alloc::boxed::Box;
+#[cfg(feature = "profile")]
+use alloc::collections::BTreeMap;
+#[cfg(feature = "debugger")]
+use alloc::collections::BTreeSet;
 use alloc::vec;
 use alloc::vec::Vec;
+use core::marker::PhantomData;
+use core::mem::size_of;
 
 use crate::error::*;
 use crate::module::*;
@@ -24,6 +32,9 @@ use crate::*;
 
 pub const MEMORY_ALIGN: usize = 16;
 
+/// Default maximum number of bytes used by the frames and values of a store.
+pub const DEFAULT_STACK_LIMIT: usize = 0x10000;
+
 /// Runtime values.
 // TODO: Introduce untyped values? (to save the tag)
 #[derive(Copy, Clone, PartialEq, Eq)]
@@ -44,15 +55,44 @@ pub enum Val {
 static STORE_ID: id::UniqueId = id::UniqueId::new();
 
 /// Runtime store.
-// We cannot GC by design. Vectors can only grow.
+// We cannot GC by design. Instances can only be dropped in reverse order of instantiation.
 #[derive(Debug)]
 pub struct Store<'m> {
     id: usize,
     insts: Vec<Instance<'m>>,
-    // TODO: This should be an Linker struct that can be shared between stores. The FuncType can be
-    // reconstructed on demand (only counts can be stored).
-    funcs: Vec<(HostName<'m>, FuncType<'m>)>,
+    linker: &'m Linker<'m>,
+    // Instances of the host tables, memories, and globals of the linker (in the same order).
+    host: HostInstance<'m>,
     threads: Vec<Continuation<'m>>,
+    // Remaining number of instructions to execute, if limited.
+    fuel: Option<u64>,
+    // Maximum number of bytes used by the frames and values of all threads.
+    stack_limit: usize,
+    // Thread that ran out of fuel, if any. It is always the innermost thread.
+    suspended: Option<Thread<'m>>,
+    // Core dump of the last trap, if any.
+    core_dump: Option<CoreDump>,
+    // Instruction counts per call stack, if profiling.
+    #[cfg(feature = "profile")]
+    profile: Option<Profile>,
+    // Debugger and its breakpoints, if any.
+    #[cfg(feature = "debugger")]
+    debug: Option<Debug>,
+}
+
+/// Host definitions that modules may import.
+///
+/// A linker is built once and may be shared between stores. Definitions of the same kind must be
+/// linked in increasing order of module and name.
+#[derive(Debug, Default)]
+pub struct Linker<'m> {
+    // TODO: The FuncType can be reconstructed on demand (only counts can be stored).
+    funcs: Vec<(HostName<'m>, FuncType<'m>)>,
+    tables: Vec<(HostName<'m>, TableType)>,
+    mems: Vec<(HostName<'m>, MemType)>,
+    globals: Vec<(HostName<'m>, (GlobalType, Val))>,
+    #[cfg(feature = "exception-handling")]
+    tags: Vec<(HostName<'m>, FuncType<'m>)>,
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -61,6 +101,13 @@ struct HostName<'m> {
     name: &'m str,
 }
 
+#[derive(Debug, Default)]
+struct HostInstance<'m> {
+    tables: Vec<Table>,
+    mems: Vec<Memory<'m>>,
+    globals: Vec<Global>,
+}
+
 /// Identifies a store.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct StoreId(usize);
@@ -79,6 +126,63 @@ impl InstId {
     }
 }
 
+/// Execution state of a thread when it trapped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoreDump {
+    /// Why the thread trapped.
+    pub reason: TrapReason,
+
+    /// The frames of the thread, from innermost to outermost.
+    pub frames: Vec<CoreFrame>,
+}
+
+/// Frame of a core dump.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CoreFrame {
+    /// The instance of the function.
+    pub inst: InstId,
+
+    /// The index of the function in its module (including imported functions).
+    pub func: u32,
+
+    /// The byte offset in the module binary.
+    ///
+    /// For the innermost frame, this is the offset of the trapping instruction. For the other
+    /// frames, this is the offset of the instruction following the call.
+    pub offset: usize,
+}
+
+/// Number of instructions executed with a given call stack.
+#[cfg(feature = "profile")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileSample {
+    /// The functions of the call stack, from outermost to innermost.
+    ///
+    /// Each function is given by its instance and its index in its module (including imported
+    /// functions).
+    pub stack: Vec<(InstId, u32)>,
+
+    /// The number of instructions executed with this call stack as innermost frames.
+    pub count: u64,
+}
+
+/// Debugger of a store.
+#[cfg(feature = "debugger")]
+pub trait Debugger {
+    /// Called when execution pauses before an instruction.
+    ///
+    /// Execution pauses at breakpoints and before each instruction when stepping. It resumes when
+    /// this function returns.
+    fn pause(&mut self, paused: Paused<'_, '_>);
+}
+
+/// Execution state of a thread paused by its debugger.
+#[cfg(feature = "debugger")]
+pub struct Paused<'a, 'm> {
+    store: &'a mut Store<'m>,
+    thread: &'a Thread<'m>,
+}
+
 /// Store wrapper when calling into the host.
 #[derive(Debug)]
 // Invariant that there is at least one thread.
@@ -88,11 +192,134 @@ pub struct Call<'a, 'm> {
 
 impl<'m> Default for Store<'m> {
     fn default() -> Self {
-        Self { id: STORE_ID.next(), insts: vec![], funcs: vec![], threads: vec![] }
+        static EMPTY: Linker<'static> = Linker::new();
+        Store::new(&EMPTY)
+    }
+}
+
+impl<'m> Linker<'m> {
+    /// Creates an empty linker.
+    pub const fn new() -> Self {
+        Linker {
+            funcs: Vec::new(),
+            tables: Vec::new(),
+            mems: Vec::new(),
+            globals: Vec::new(),
+            #[cfg(feature = "exception-handling")]
+            tags: Vec::new(),
+        }
+    }
+
+    /// Links a host function provided its signature.
+    ///
+    /// Currently, only functions which take and return `i32` are supported. So the `params` and
+    /// `results` parameters describe how many `i32` are taken as parameters and returned as
+    /// results respectively.
+    ///
+    /// Note that the order in which functions are linked defines their index. The first linked
+    /// function has index 0, the second has index 1, etc. This index is used when a module calls in
+    /// the host to identify the function.
+    pub fn link_func(
+        &mut self, module: &'m str, name: &'m str, params: usize, results: usize,
+    ) -> Result<(), Error> {
+        static TYPES: &[ValType] = &[ValType::I32; 8];
+        let name = HostName { module, name };
+        check(self.funcs.last().map_or(true, |x| x.0 < name))?;
+        check(params <= TYPES.len() && results <= TYPES.len())?;
+        let type_ = FuncType { params: TYPES[.. params].into(), results: TYPES[.. results].into() };
+        self.funcs.push((name, type_));
+        Ok(())
+    }
+
+    /// Links a host table provided its type.
+    ///
+    /// Each store has its own instance of the table, initially filled with null references.
+    pub fn link_table(
+        &mut self, module: &'m str, name: &'m str, type_: TableType,
+    ) -> Result<(), Error> {
+        let name = HostName { module, name };
+        check(self.tables.last().map_or(true, |x| x.0 < name))?;
+        check(type_.limits.valid(TABLE_MAX))?;
+        self.tables.push((name, type_));
+        Ok(())
+    }
+
+    /// Links a host memory provided its limits.
+    ///
+    /// Each store has its own instance of the memory. Its data must be provided with
+    /// [`Store::set_host_mem()`], otherwise accessing it traps.
+    pub fn link_mem(
+        &mut self, module: &'m str, name: &'m str, limits: Limits,
+    ) -> Result<(), Error> {
+        let name = HostName { module, name };
+        check(self.mems.last().map_or(true, |x| x.0 < name))?;
+        check(limits.valid(MEM_MAX))?;
+        self.mems.push((name, MemType { limits, shared: false }));
+        Ok(())
+    }
+
+    /// Links a host global provided its type and initial value.
+    ///
+    /// Each store has its own instance of the global. References are not supported as initial
+    /// value, only null references.
+    pub fn link_global(
+        &mut self, module: &'m str, name: &'m str, type_: GlobalType, value: Val,
+    ) -> Result<(), Error> {
+        let name = HostName { module, name };
+        check(self.globals.last().map_or(true, |x| x.0 < name))?;
+        check(type_.value.contains(value) && !matches!(value, Val::Ref(_)))?;
+        self.globals.push((name, (type_, value)));
+        Ok(())
+    }
+
+    /// Links a host tag provided its number of `i32` parameters.
+    ///
+    /// Host functions may throw exceptions with this tag using [`Call::throw()`]. Like functions,
+    /// the order in which tags are linked defines their index.
+    #[cfg(feature = "exception-handling")]
+    pub fn link_tag(&mut self, module: &'m str, name: &'m str, params: usize) -> Result<(), Error> {
+        static TYPES: &[ValType] = &[ValType::I32; 8];
+        let name = HostName { module, name };
+        check(self.tags.last().map_or(true, |x| x.0 < name))?;
+        check(params <= TYPES.len())?;
+        self.tags.push((name, FuncType { params: TYPES[.. params].into(), results: ().into() }));
+        Ok(())
     }
 }
 
 impl<'m> Store<'m> {
+    /// Creates an empty store using the definitions of a linker.
+    pub fn new(linker: &'m Linker<'m>) -> Self {
+        let host = HostInstance {
+            tables: linker.tables.iter().map(|x| Table::new(x.1)).collect(),
+            mems: linker
+                .mems
+                .iter()
+                .map(|x| {
+                    let mut mem = Memory::default();
+                    mem.init(&mut [], x.1).unwrap();
+                    mem
+                })
+                .collect(),
+            globals: linker.globals.iter().map(|x| Global::new(x.1 .1)).collect(),
+        };
+        Store {
+            id: STORE_ID.next(),
+            insts: vec![],
+            linker,
+            host,
+            threads: vec![],
+            fuel: None,
+            stack_limit: DEFAULT_STACK_LIMIT,
+            suspended: None,
+            core_dump: None,
+            #[cfg(feature = "profile")]
+            profile: None,
+            #[cfg(feature = "debugger")]
+            debug: None,
+        }
+    }
+
     /// Returns the identifier of this store.
     pub fn id(&self) -> StoreId {
         StoreId(self.id)
@@ -103,12 +330,19 @@ impl<'m> Store<'m> {
     /// The memory is not dynamically allocated and must thus be provided. It is not necessary for
     /// the memory length to be a multiple of 64kB. Execution will trap if the module tries to
     /// access part of the memory that does not exist.
+    ///
+    /// If the module defines multiple memories (with the `multi-memory` feature), they are taken
+    /// in order from the provided memory. Each memory but the last gets its minimum size, and the
+    /// last memory gets the rest. The memory is given back when the instance is dropped with
+    /// [`Self::unload()`] or [`Self::reset()`].
     pub fn instantiate(
         &mut self, module: Module<'m>, memory: &'m mut [u8],
     ) -> Result<InstId, Error> {
         let inst_id = self.insts.len();
         self.insts.push(Instance::default());
         self.last_inst().module = module;
+        let (provided, mut memory) = Provided::new(memory);
+        self.last_inst().memory = provided;
         for import in self.last_inst().module.imports() {
             let type_ = import.type_(&self.last_inst().module);
             let id = self.resolve(&import, type_)?;
@@ -117,6 +351,8 @@ impl<'m> Store<'m> {
                 ImportDesc::Table(_) => self.last_inst().tables.ext.push(id),
                 ImportDesc::Mem(_) => self.last_inst().mems.ext.push(id),
                 ImportDesc::Global(_) => self.last_inst().globals.ext.push(id),
+                #[cfg(feature = "exception-handling")]
+                ImportDesc::Tag(_) => self.last_inst().tags.ext.push(id),
             }
         }
         if let Some(mut parser) = self.last_inst().module.section(SectionId::Table) {
@@ -125,13 +361,22 @@ impl<'m> Store<'m> {
             }
         }
         if let Some(mut parser) = self.last_inst().module.section(SectionId::Memory) {
-            match parser.parse_vec().into_ok() {
-                0 => (),
-                1 => {
-                    let limits = parser.parse_memtype().into_ok();
-                    self.last_inst().mems.int.init(memory, limits)?;
-                }
-                _ => unimplemented!(),
+            let n = parser.parse_vec().into_ok();
+            for i in 0 .. n {
+                let type_ = parser.parse_memtype().into_ok();
+                let limits = type_.limits;
+                let data = match i + 1 == n {
+                    true => core::mem::take(&mut memory),
+                    false => {
+                        let len = core::cmp::min(limits.min as usize * 0x10000, memory.len());
+                        let (data, rest) = core::mem::take(&mut memory).split_at_mut(len);
+                        memory = rest;
+                        data
+                    }
+                };
+                let mut mem = Memory::default();
+                mem.init(data, type_)?;
+                self.last_inst().mems.int.push(mem);
             }
         }
         if let Some(mut parser) = self.last_inst().module.section(SectionId::Global) {
@@ -152,7 +397,7 @@ impl<'m> Store<'m> {
                     ElemMode::Active { table, offset } => {
                         let n = init.len();
                         let table = self.table(inst_id, table);
-                        table_init(offset, 0, n, table, &init)?;
+                        table_init(offset, 0, n, table, &init).map_err(trap)?;
                         true
                     }
                     ElemMode::Declarative => true,
@@ -170,7 +415,7 @@ impl<'m> Store<'m> {
                     DataMode::Active { memory, offset } => {
                         let n = init.len();
                         let memory = self.mem(inst_id, memory);
-                        memory_init(offset, 0, n, memory, init)?;
+                        memory_init(offset, 0, n, memory, init).map_err(trap)?;
                         true
                     }
                 };
@@ -184,21 +429,79 @@ impl<'m> Store<'m> {
             let mut parser = self.insts[inst_id].module.func(ptr.index());
             let mut locals = Vec::new();
             append_locals(&mut parser, &mut locals);
-            let thread = Thread::new(parser, vec![Frame::new(inst_id, 0, &[], locals)]);
-            let result = thread.run(self)?;
-            assert!(matches!(result, RunResult::Done(x) if x.is_empty()));
+            let stp = self.insts[inst_id].module.func_stp(ptr.index());
+            let frame = Frame::new(inst_id, ptr.index(), 0, &[], locals, stp, 0);
+            let thread = Thread::new(parser, vec![frame]);
+            // The start function is not metered because it cannot be resumed.
+            let fuel = self.fuel.take();
+            let result = thread.run(self).map(|x| x.forget());
+            self.fuel = fuel;
+            assert!(matches!(result?, RunAnswer::Done(x) if x.is_empty()));
         }
         Ok(InstId { store_id: self.id, inst_id })
     }
 
+    /// Drops an instance and returns the memory it was instantiated with.
+    ///
+    /// Only the last instance may be dropped, such that instances are dropped in reverse order of
+    /// instantiation. Its identifier may then be reused by the next instance. Returns an error if
+    /// the instance is not the last one, if a thread waiting for the host or out of fuel executes
+    /// in the instance or holds a reference to it, or if the host or another instance holds a
+    /// reference to it (e.g. in a table).
+    pub fn unload(&mut self, inst: InstId) -> Result<&'m mut [u8], Error> {
+        let inst_id = self.inst_id(inst)?;
+        check(inst_id + 1 == self.insts.len())?;
+        let refers = |x: &Val| matches!(x, Val::Ref(p) if p.instance() == Side::Wasm(inst_id));
+        let mut threads = self.threads.iter().map(|x| &x.thread).chain(&self.suspended);
+        check(!threads.any(|x| x.refers(inst_id)))?;
+        let tables = self.insts[.. inst_id].iter().flat_map(|x| &x.tables.int);
+        check(!tables.chain(&self.host.tables).any(|x| x.elems.iter().any(refers)))?;
+        let globals = self.insts[.. inst_id].iter().flat_map(|x| &x.globals.int);
+        check(!globals.chain(&self.host.globals).any(|x| refers(&x.value)))?;
+        #[cfg(feature = "debugger")]
+        if let Some(debug) = &mut self.debug {
+            debug.breakpoints.retain(|x| x.0 != inst_id);
+        }
+        Ok(self.insts.pop().unwrap().take_memory())
+    }
+
+    /// Drops all instances and returns their memories in order of instantiation.
+    ///
+    /// The host tables, memories, and globals get back their initial value, and the core dump is
+    /// cleared. Returns an error if a thread waits for the host or ran out of fuel.
+    pub fn reset(&mut self) -> Result<Vec<&'m mut [u8]>, Error> {
+        check(self.threads.is_empty() && self.suspended.is_none())?;
+        let memories = self.insts.drain(..).map(|x| x.take_memory()).collect();
+        let linker = self.linker;
+        for (table, (_, type_)) in self.host.tables.iter_mut().zip(&linker.tables) {
+            *table = Table::new(*type_);
+        }
+        for (mem, (_, type_)) in self.host.mems.iter_mut().zip(&linker.mems) {
+            let data = core::mem::take(&mut mem.data);
+            mem.init(data, *type_).unwrap();
+        }
+        for (global, (_, (_, value))) in self.host.globals.iter_mut().zip(&linker.globals) {
+            *global = Global::new(*value);
+        }
+        #[cfg(feature = "debugger")]
+        if let Some(debug) = &mut self.debug {
+            debug.breakpoints.clear();
+        }
+        self.core_dump = None;
+        Ok(memories)
+    }
+
     /// Invokes a function in an instance provided its name.
     ///
     /// If a function was already running, it will resume once the function being called terminates.
     /// In other words, execution satisfies a stack property. Without this, the stack of the module
     /// may be corrupted.
+    ///
+    /// Returns an error if a thread ran out of fuel and was not resumed.
     pub fn invoke<'a>(
         &'a mut self, inst: InstId, name: &str, args: Vec<Val>,
     ) -> Result<RunResult<'a, 'm>, Error> {
+        check(self.suspended.is_none())?;
         let inst_id = self.inst_id(inst)?;
         let inst = &self.insts[inst_id];
         let ptr = match inst.module.export(name).ok_or_else(not_found)? {
@@ -213,7 +516,10 @@ impl<'m> Store<'m> {
         check_types(&t.params, &args)?;
         let mut locals = args;
         append_locals(&mut parser, &mut locals);
-        let frame = Frame::new(inst_id, t.results.len(), &[], locals);
+        // The thread uses the stack after the thread waiting for the host (if any).
+        let stack = self.threads.last().map_or(0, |x| x.thread.stack_size());
+        let stp = inst.module.func_stp(x);
+        let frame = Frame::new(inst_id, x, t.results.len(), &[], locals, stp, stack);
         Thread::new(parser, vec![frame]).run(self)
     }
 
@@ -221,13 +527,11 @@ impl<'m> Store<'m> {
     pub fn get_global(&mut self, inst: InstId, name: &str) -> Result<Val, Error> {
         let inst_id = self.inst_id(inst)?;
         let inst = &self.insts[inst_id];
-        let ptr = match inst.module.export(name).ok_or_else(not_found)? {
-            ExportDesc::Global(x) => inst.globals.ptr(inst_id, x),
+        let x = match inst.module.export(name).ok_or_else(not_found)? {
+            ExportDesc::Global(x) => x,
             _ => return Err(Error::Invalid),
         };
-        let inst_id = ptr.instance().unwrap_wasm();
-        let x = ptr.index() as usize;
-        Ok(self.insts[inst_id].globals.int[x].value)
+        Ok(self.global(inst_id, x).value)
     }
 
     /// Sets the name of an instance.
@@ -237,39 +541,212 @@ impl<'m> Store<'m> {
         Ok(())
     }
 
-    /// Links a host function provided its signature.
-    ///
-    /// Currently, only functions which take and return `i32` are supported. So the `params` and
-    /// `results` parameters describe how many `i32` are taken as parameters and returned as
-    /// results respectively.
+    /// Provides the data of a host memory.
     ///
-    /// Note that the order in which functions are linked defines their index. The first linked
-    /// function has index 0, the second has index 1, etc. This index is used when a module calls in
-    /// the host to identify the function.
-    pub fn link_func(
-        &mut self, module: &'m str, name: &'m str, params: usize, results: usize,
+    /// This should be called before instantiating modules importing the memory. The same
+    /// constraints as for [`Self::instantiate()`] apply to the data.
+    pub fn set_host_mem(
+        &mut self, module: &str, name: &str, data: &'m mut [u8],
     ) -> Result<(), Error> {
-        static TYPES: &[ValType] = &[ValType::I32; 8];
         let name = HostName { module, name };
-        check(self.insts.is_empty())?;
-        check(self.funcs.last().map_or(true, |x| x.0 < name))?;
-        check(params <= TYPES.len() && results <= TYPES.len())?;
-        let type_ = FuncType { params: TYPES[.. params].into(), results: TYPES[.. results].into() };
-        self.funcs.push((name, type_));
-        Ok(())
+        let x = find_host(&self.linker.mems, &name).ok_or_else(not_found)?;
+        let type_ = self.linker.mems[x as usize].1;
+        self.host.mems[x as usize].init(data, type_)
     }
 
     /// Returns the call in the host, if any.
     ///
-    /// This function returns `None` if nothing is running.
+    /// This function returns `None` if nothing is running or if a thread ran out of fuel.
     // NOTE: This is like poll. Could be called next.
     pub fn last_call(&mut self) -> Option<Call<'_, 'm>> {
-        if self.threads.is_empty() {
+        if self.threads.is_empty() || self.suspended.is_some() {
             None
         } else {
             Some(Call { store: self })
         }
     }
+
+    /// Sets the number of instructions that may execute before running out of fuel.
+    ///
+    /// Execution is not limited if `None` (the default). When execution runs out of fuel, it
+    /// returns [`RunResult::OutOfFuel`] and may be resumed with [`Self::resume()`] after adding
+    /// fuel.
+    pub fn set_fuel(&mut self, fuel: Option<u64>) {
+        self.fuel = fuel;
+    }
+
+    /// Returns the number of instructions that may execute before running out of fuel.
+    ///
+    /// Returns `None` if execution is not limited.
+    pub fn fuel(&self) -> Option<u64> {
+        self.fuel
+    }
+
+    /// Sets the maximum number of bytes used by the frames and values of all threads.
+    ///
+    /// This limit applies to the call stacks of the store, including threads waiting for the host.
+    /// Calling a function that would exceed it traps with [`TrapReason::StackExhausted`]. The
+    /// default is [`DEFAULT_STACK_LIMIT`].
+    pub fn set_stack_limit(&mut self, limit: usize) {
+        self.stack_limit = limit;
+    }
+
+    /// Returns the maximum number of bytes used by the frames and values of all threads.
+    pub fn stack_limit(&self) -> usize {
+        self.stack_limit
+    }
+
+    /// Starts or stops profiling the executed instructions.
+    ///
+    /// Starting discards the previous profile. Stopping keeps it until profiling starts again.
+    #[cfg(feature = "profile")]
+    pub fn set_profile(&mut self, enabled: bool) {
+        match enabled {
+            true => self.profile = Some(Profile::default()),
+            false => self.profile.iter_mut().for_each(|x| x.stop()),
+        }
+    }
+
+    /// Returns the number of instructions executed per call stack since profiling started.
+    ///
+    /// Returns `None` if profiling never started.
+    #[cfg(feature = "profile")]
+    pub fn profile(&self) -> Option<Vec<ProfileSample>> {
+        let profile = self.profile.as_ref()?;
+        let mut counts = profile.counts.clone();
+        if 0 < profile.pending {
+            *counts.entry(profile.stack.clone()).or_default() += profile.pending;
+        }
+        let inst = |inst_id| InstId { store_id: self.id, inst_id };
+        let sample = |(stack, count): (Vec<(usize, u32)>, u64)| ProfileSample {
+            stack: stack.into_iter().map(|(x, f)| (inst(x), f)).collect(),
+            count,
+        };
+        Some(counts.into_iter().map(sample).collect())
+    }
+
+    /// Sets the debugger of the store.
+    ///
+    /// Setting a debugger clears the breakpoints and pauses execution before the next instruction.
+    #[cfg(feature = "debugger")]
+    pub fn set_debugger(&mut self, debugger: Option<Box<dyn Debugger>>) {
+        self.debug = debugger.map(|debugger| Debug {
+            debugger: Some(debugger),
+            breakpoints: BTreeSet::new(),
+            step: true,
+        });
+    }
+
+    /// Returns whether a thread ran out of fuel and waits to be resumed.
+    pub fn is_out_of_fuel(&self) -> bool {
+        self.suspended.is_some()
+    }
+
+    /// Resumes the thread which ran out of fuel.
+    ///
+    /// Returns an error if no thread ran out of fuel.
+    pub fn resume(&mut self) -> Result<RunResult<'_, 'm>, Error> {
+        let thread = self.suspended.take().ok_or_else(invalid)?;
+        thread.run(self)
+    }
+
+    /// Returns the core dump of the last trap, if any.
+    pub fn core_dump(&self) -> Option<&CoreDump> {
+        self.core_dump.as_ref()
+    }
+
+    /// Returns the name of a function of an instance, if any.
+    ///
+    /// The index includes imported functions. Names are taken from the name section of the module.
+    pub fn func_name(&self, inst: InstId, func: u32) -> Option<&'m str> {
+        let inst_id = self.inst_id(inst).ok()?;
+        self.insts[inst_id].module.func_name(func)
+    }
+
+    /// Returns a snapshot of the execution state of the store.
+    ///
+    /// The snapshot contains the memories, tables, and globals of the instances (including the host
+    /// definitions), the dropped segments, and the threads waiting for the host or out of fuel. It
+    /// doesn't contain the modules, the fuel, nor the stack limit.
+    pub fn snapshot(&self) -> Vec<u8> {
+        let mut snapshot = Snapshot::default();
+        snapshot.tables(&self.host.tables);
+        snapshot.mems(&self.host.mems);
+        snapshot.globals(&self.host.globals);
+        snapshot.usize(self.insts.len());
+        for inst in &self.insts {
+            snapshot.tables(&inst.tables.int);
+            snapshot.mems(&inst.mems.int);
+            snapshot.globals(&inst.globals.int);
+            snapshot.flags(&inst.elems);
+            snapshot.flags(&inst.datas);
+        }
+        snapshot.usize(self.threads.len());
+        for cont in &self.threads {
+            snapshot.thread(self, &cont.thread);
+            snapshot.usize(cont.index);
+            snapshot.vals(&cont.args);
+            snapshot.usize(cont.arity);
+            snapshot.flag(cont.tail);
+        }
+        snapshot.flag(self.suspended.is_some());
+        if let Some(thread) = &self.suspended {
+            snapshot.thread(self, thread);
+        }
+        snapshot.0
+    }
+
+    /// Restores a snapshot of the execution state of the store.
+    ///
+    /// The store must have the same modules instantiated in the same order, and the same host
+    /// memories set, as the store of the snapshot. Execution may then continue where it was when
+    /// the snapshot was taken, for example with [`Self::last_call()`] or [`Self::resume()`].
+    ///
+    /// Returns an error if the snapshot is malformed or doesn't fit the store, in which case the
+    /// store is not modified.
+    ///
+    /// # Safety
+    ///
+    /// The snapshot must come from [`Self::snapshot()`] for a store with the same modules.
+    pub unsafe fn restore(&mut self, snapshot: &[u8]) -> Result<(), Error> {
+        let mut restore = Restore(snapshot);
+        let host = restore.instance(&self.host.tables, &self.host.mems, &self.host.globals);
+        let host = host.ok_or_else(invalid)?;
+        check(restore.usize() == Some(self.insts.len()))?;
+        let mut insts = Vec::with_capacity(self.insts.len());
+        for inst in &self.insts {
+            let mut state = restore
+                .instance(&inst.tables.int, &inst.mems.int, &inst.globals.int)
+                .ok_or_else(invalid)?;
+            state.elems = restore.flags(inst.elems.len()).ok_or_else(invalid)?;
+            state.datas = restore.flags(inst.datas.len()).ok_or_else(invalid)?;
+            insts.push(state);
+        }
+        let mut threads = Vec::new();
+        for _ in 0 .. restore.usize().ok_or_else(invalid)? {
+            let thread = restore.thread(self).ok_or_else(invalid)?;
+            let index = restore.usize().ok_or_else(invalid)?;
+            check(index < self.linker.funcs.len())?;
+            let args = restore.vals().ok_or_else(invalid)?;
+            let arity = restore.usize().ok_or_else(invalid)?;
+            let tail = restore.flag().ok_or_else(invalid)?;
+            threads.push(Continuation { thread, index, args, arity, tail });
+        }
+        let suspended = match restore.flag().ok_or_else(invalid)? {
+            false => None,
+            true => Some(restore.thread(self).ok_or_else(invalid)?),
+        };
+        check(restore.0.is_empty())?;
+        host.apply(&mut self.host.tables, &mut self.host.mems, &mut self.host.globals);
+        for (inst, state) in self.insts.iter_mut().zip(insts) {
+            inst.elems.copy_from_slice(&state.elems);
+            inst.datas.copy_from_slice(&state.datas);
+            state.apply(&mut inst.tables.int, &mut inst.mems.int, &mut inst.globals.int);
+        }
+        self.threads = threads;
+        self.suspended = suspended;
+        Ok(())
+    }
 }
 
 impl<'a, 'm> Call<'a, 'm> {
@@ -298,14 +775,48 @@ impl<'a, 'm> Call<'a, 'm> {
         self.store.mem(self.inst().inst_id, 0).data
     }
 
+    /// Returns the frames of the thread calling the host, from innermost to outermost.
+    ///
+    /// The offset of each frame is the offset of the instruction following the call.
+    pub fn backtrace(&self) -> Vec<CoreFrame> {
+        let thread = &self.cont().thread;
+        thread.backtrace(self.store, thread.parser.save())
+    }
+
     /// Resumes execution with the results from the host.
     pub fn resume(self, results: &[Val]) -> Result<RunResult<'a, 'm>, Error> {
-        let Continuation { mut thread, arity, .. } = self.store.threads.pop().unwrap();
+        let Continuation { mut thread, arity, tail, .. } = self.store.threads.pop().unwrap();
         check(results.len() == arity)?;
         thread.push_values(results);
+        if tail {
+            match thread.exit_frame() {
+                ThreadResult::Continue(x) => thread = x,
+                ThreadResult::Done(x) => return Ok(RunResult::Done(x)),
+                ThreadResult::Host => unreachable!(),
+            }
+        }
         thread.run(self.store)
     }
 
+    /// Resumes execution by throwing an exception from the host.
+    ///
+    /// The tag is the index of a host tag (see [`Linker::link_tag()`]). The exception may be caught
+    /// by the module, otherwise execution traps with [`TrapReason::UncaughtException`].
+    #[cfg(feature = "exception-handling")]
+    pub fn throw(self, tag: usize, values: &[Val]) -> Result<RunResult<'a, 'm>, Error> {
+        let type_ = self.store.linker.tags.get(tag).ok_or_else(invalid)?.1;
+        check_types(&type_.params, values)?;
+        let Continuation { mut thread, tail, .. } = self.store.threads.pop().unwrap();
+        if tail {
+            // The calling frame was replaced by the host function, so it doesn't handle exceptions.
+            thread.frame().tries.clear();
+        }
+        let tag = Ptr::new(Side::Host, tag as u32);
+        let exception = Exception { tag, values: values.to_vec() };
+        let pc = thread.parser.save();
+        thread.throw(self.store, pc, exception)?.run(self.store)
+    }
+
     fn cont(&self) -> &Continuation {
         self.store.threads.last().unwrap()
     }
@@ -384,7 +895,7 @@ impl core::fmt::Debug for Val {
             #[cfg(feature = "float-types")]
             Val::F64(x) => write!(f, "{}", f64::from_bits(x)),
             #[cfg(feature = "vector-types")]
-            Val::V128(_) => todo!(),
+            Val::V128(x) => write!(f, "{x:#034x}"),
             Val::Null(_) => write!(f, "null"),
             Val::Ref(p) => write!(f, "ref@{:?}:{}", p.instance(), p.index()),
             Val::RefExtern(p) => write!(f, "ext@{p}"),
@@ -417,10 +928,61 @@ struct Instance<'m> {
     module: Module<'m>,
     funcs: Component<()>,
     tables: Component<Vec<Table>>,
-    mems: Component<Memory<'m>>,
+    mems: Component<Vec<Memory<'m>>>,
     globals: Component<Vec<Global>>,
     elems: Vec<bool>, // whether the elem segment is dropped
     datas: Vec<bool>, // whether the data segment is dropped
+    #[cfg(feature = "exception-handling")]
+    tags: Component<()>,
+    memory: Provided<'m>,
+}
+
+impl<'m> Instance<'m> {
+    /// Drops the instance and returns the memory it was instantiated with.
+    fn take_memory(self) -> &'m mut [u8] {
+        let Instance { memory, mems, .. } = self;
+        drop(mems);
+        // SAFETY: The memories of the instance are dropped.
+        unsafe { memory.take() }
+    }
+}
+
+/// Memory provided to instantiate a module.
+///
+/// The memories of the instance are split from it.
+#[derive(Debug)]
+struct Provided<'m> {
+    ptr: *mut [u8],
+    lifetime: PhantomData<&'m mut [u8]>,
+}
+
+// SAFETY: This is a mutable borrow.
+unsafe impl<'m> Send for Provided<'m> {}
+// SAFETY: This is a mutable borrow.
+unsafe impl<'m> Sync for Provided<'m> {}
+
+impl<'m> Default for Provided<'m> {
+    fn default() -> Self {
+        Provided { ptr: &mut [], lifetime: PhantomData }
+    }
+}
+
+impl<'m> Provided<'m> {
+    /// Returns the provided memory and a borrow of it for the memories of the instance.
+    fn new(memory: &'m mut [u8]) -> (Self, &'m mut [u8]) {
+        let ptr = memory as *mut [u8];
+        // SAFETY: The pointer is only used again in `take()` once this borrow is dropped.
+        (Provided { ptr, lifetime: PhantomData }, unsafe { &mut *ptr })
+    }
+
+    /// Returns the provided memory.
+    ///
+    /// # Safety
+    ///
+    /// The borrow returned by `new()` and the slices split from it must be dropped.
+    unsafe fn take(self) -> &'m mut [u8] {
+        unsafe { &mut *self.ptr }
+    }
 }
 
 #[derive(Debug)]
@@ -437,6 +999,11 @@ pub enum RunResult<'a, 'm> {
 
     /// Execution is calling into the host.
     Host(Call<'a, 'm>),
+
+    /// Execution ran out of fuel.
+    ///
+    /// Execution may be resumed with [`Store::resume()`].
+    OutOfFuel,
 }
 
 /// Runtime result without host call information.
@@ -444,6 +1011,7 @@ pub enum RunResult<'a, 'm> {
 pub enum RunAnswer {
     Done(Vec<Val>),
     Host,
+    OutOfFuel,
 }
 
 impl<'a, 'm> RunResult<'a, 'm> {
@@ -451,6 +1019,7 @@ impl<'a, 'm> RunResult<'a, 'm> {
         match self {
             RunResult::Done(result) => RunAnswer::Done(result),
             RunResult::Host(_) => RunAnswer::Host,
+            RunResult::OutOfFuel => RunAnswer::OutOfFuel,
         }
     }
 }
@@ -461,6 +1030,8 @@ struct Continuation<'m> {
     index: usize,
     args: Vec<Val>,
     arity: usize,
+    // Whether the host was tail called, in which case the calling frame returns with the results.
+    tail: bool,
 }
 
 impl<'m> Store<'m> {
@@ -479,11 +1050,53 @@ impl<'m> Store<'m> {
 
     fn func_type(&self, ptr: Ptr) -> FuncType<'m> {
         match ptr.instance() {
-            Side::Host => self.funcs[ptr.index() as usize].1,
+            Side::Host => self.linker.funcs[ptr.index() as usize].1,
             Side::Wasm(x) => self.insts[x].module.func_type(ptr.index()),
         }
     }
 
+    fn table_type(&self, ptr: Ptr) -> TableType {
+        let i = ptr.index() as usize;
+        let (mut t, table) = match ptr.instance() {
+            Side::Host => (self.linker.tables[i].1, &self.host.tables[i]),
+            Side::Wasm(x) => {
+                (self.insts[x].module.table_type(ptr.index()), &self.insts[x].tables.int[i])
+            }
+        };
+        t.limits.min = table.size();
+        t
+    }
+
+    fn mem_type(&self, ptr: Ptr) -> MemType {
+        let (mut t, mem) = match ptr.instance() {
+            Side::Host => {
+                let i = ptr.index() as usize;
+                (self.linker.mems[i].1, &self.host.mems[i])
+            }
+            Side::Wasm(x) => {
+                let i = ptr.index();
+                (self.insts[x].module.mem_type(i), &self.insts[x].mems.int[i as usize])
+            }
+        };
+        t.limits.min = mem.size();
+        t
+    }
+
+    fn global_type(&self, ptr: Ptr) -> GlobalType {
+        match ptr.instance() {
+            Side::Host => self.linker.globals[ptr.index() as usize].1 .0,
+            Side::Wasm(x) => self.insts[x].module.global_type(ptr.index()),
+        }
+    }
+
+    #[cfg(feature = "exception-handling")]
+    fn tag_type(&self, ptr: Ptr) -> FuncType<'m> {
+        match ptr.instance() {
+            Side::Host => self.linker.tags[ptr.index() as usize].1,
+            Side::Wasm(x) => self.insts[x].module.tag_type(ptr.index()),
+        }
+    }
+
     fn func_ptr(&self, inst_id: usize, x: FuncIdx) -> Ptr {
         self.insts[inst_id].funcs.ptr(inst_id, x)
     }
@@ -500,71 +1113,76 @@ impl<'m> Store<'m> {
         self.insts[inst_id].globals.ptr(inst_id, x)
     }
 
+    #[cfg(feature = "exception-handling")]
+    fn tag_ptr(&self, inst_id: usize, x: TagIdx) -> Ptr {
+        self.insts[inst_id].tags.ptr(inst_id, x)
+    }
+
     fn table(&mut self, inst_id: usize, x: TableIdx) -> &mut Table {
         let ptr = self.table_ptr(inst_id, x);
-        let x = ptr.instance().unwrap_wasm();
-        &mut self.insts[x].tables.int[ptr.index() as usize]
+        let i = ptr.index() as usize;
+        match ptr.instance() {
+            Side::Host => &mut self.host.tables[i],
+            Side::Wasm(x) => &mut self.insts[x].tables.int[i],
+        }
     }
 
     fn mem(&mut self, inst_id: usize, x: MemIdx) -> &mut Memory<'m> {
         let ptr = self.mem_ptr(inst_id, x);
-        let x = ptr.instance().unwrap_wasm();
-        assert_eq!(ptr.index(), 0);
-        &mut self.insts[x].mems.int
+        let i = ptr.index() as usize;
+        match ptr.instance() {
+            Side::Host => &mut self.host.mems[i],
+            Side::Wasm(x) => &mut self.insts[x].mems.int[i],
+        }
     }
 
     fn global(&mut self, inst_id: usize, x: GlobalIdx) -> &mut Global {
         let ptr = self.global_ptr(inst_id, x);
-        let x = ptr.instance().unwrap_wasm();
-        &mut self.insts[x].globals.int[ptr.index() as usize]
+        let i = ptr.index() as usize;
+        match ptr.instance() {
+            Side::Host => &mut self.host.globals[i],
+            Side::Wasm(x) => &mut self.insts[x].globals.int[i],
+        }
     }
 
     fn resolve(&self, import: &Import<'m>, imp_type_: ExternType<'m>) -> Result<Ptr, Error> {
         let host_name = HostName { module: import.module, name: import.name };
-        let mut found = None;
-        if let Ok(x) = self.funcs.binary_search_by(|x| x.0.cmp(&host_name)) {
-            found = Some((Ptr::new(Side::Host, x as u32), ExternType::Func(self.funcs[x].1)));
-        } else {
-            let inst_id = self.resolve_inst(import.module)?;
-            let inst = &self.insts[inst_id];
-            if let Some(mut parser) = inst.module.section(SectionId::Export) {
-                for _ in 0 .. parser.parse_vec().into_ok() {
-                    let name = parser.parse_name().into_ok();
-                    let desc = parser.parse_exportdesc().into_ok();
-                    if name != import.name {
-                        continue;
+        let host = match import.desc {
+            ImportDesc::Func(_) => find_host(&self.linker.funcs, &host_name),
+            ImportDesc::Table(_) => find_host(&self.linker.tables, &host_name),
+            ImportDesc::Mem(_) => find_host(&self.linker.mems, &host_name),
+            ImportDesc::Global(_) => find_host(&self.linker.globals, &host_name),
+            #[cfg(feature = "exception-handling")]
+            ImportDesc::Tag(_) => find_host(&self.linker.tags, &host_name),
+        };
+        let ptr = match host {
+            Some(x) => Ptr::new(Side::Host, x),
+            None => {
+                let inst_id = self.resolve_inst(import.module)?;
+                let inst = &self.insts[inst_id];
+                match (inst.module.export(import.name), &import.desc) {
+                    (Some(ExportDesc::Func(x)), ImportDesc::Func(_)) => self.func_ptr(inst_id, x),
+                    (Some(ExportDesc::Table(x)), ImportDesc::Table(_)) => {
+                        self.table_ptr(inst_id, x)
                     }
-                    found = Some(match desc {
-                        ExportDesc::Func(x) => {
-                            let ptr = self.func_ptr(inst_id, x);
-                            (ptr, ExternType::Func(self.func_type(ptr)))
-                        }
-                        ExportDesc::Table(x) => {
-                            let ptr = self.table_ptr(inst_id, x);
-                            let inst = &self.insts[ptr.instance().unwrap_wasm()];
-                            let mut t = inst.module.table_type(ptr.index());
-                            t.limits.min = inst.tables.int[ptr.index() as usize].size();
-                            (ptr, ExternType::Table(t))
-                        }
-                        ExportDesc::Mem(x) => {
-                            let ptr = self.mem_ptr(inst_id, x);
-                            let inst = &self.insts[ptr.instance().unwrap_wasm()];
-                            let mut t = inst.module.mem_type(ptr.index());
-                            assert_eq!(ptr.index(), 0);
-                            t.min = inst.mems.int.size();
-                            (ptr, ExternType::Mem(t))
-                        }
-                        ExportDesc::Global(x) => {
-                            let ptr = self.global_ptr(inst_id, x);
-                            let inst = &self.insts[ptr.instance().unwrap_wasm()];
-                            (ptr, ExternType::Global(inst.module.global_type(ptr.index())))
-                        }
-                    });
-                    break;
+                    (Some(ExportDesc::Mem(x)), ImportDesc::Mem(_)) => self.mem_ptr(inst_id, x),
+                    (Some(ExportDesc::Global(x)), ImportDesc::Global(_)) => {
+                        self.global_ptr(inst_id, x)
+                    }
+                    #[cfg(feature = "exception-handling")]
+                    (Some(ExportDesc::Tag(x)), ImportDesc::Tag(_)) => self.tag_ptr(inst_id, x),
+                    _ => return Err(not_found()),
                 }
             }
-        }
-        let (ptr, ext_type_) = found.ok_or_else(not_found)?;
+        };
+        let ext_type_ = match import.desc {
+            ImportDesc::Func(_) => ExternType::Func(self.func_type(ptr)),
+            ImportDesc::Table(_) => ExternType::Table(self.table_type(ptr)),
+            ImportDesc::Mem(_) => ExternType::Mem(self.mem_type(ptr)),
+            ImportDesc::Global(_) => ExternType::Global(self.global_type(ptr)),
+            #[cfg(feature = "exception-handling")]
+            ImportDesc::Tag(_) => ExternType::Tag(self.tag_type(ptr)),
+        };
         if ext_type_.matches(&imp_type_) {
             Ok(ptr)
         } else {
@@ -573,6 +1191,10 @@ impl<'m> Store<'m> {
     }
 }
 
+fn find_host<'m, T>(xs: &[(HostName<'m>, T)], name: &HostName<'m>) -> Option<u32> {
+    xs.binary_search_by(|x| x.0.cmp(name)).ok().map(|x| x as u32)
+}
+
 #[derive(Debug)]
 enum ElemMode {
     Passive,
@@ -679,7 +1301,8 @@ impl<'m> Thread<'m> {
     }
 
     fn const_expr(store: &mut Store<'m>, inst_id: usize, mut_parser: &mut Parser<'m>) -> Val {
-        let frames = vec![Frame::new(inst_id, 1, &[], Vec::new())];
+        // Constant expressions are not functions but they can't trap, so the index is never used.
+        let frames = vec![Frame::new(inst_id, 0, 1, &[], Vec::new(), 0, 0)];
         let parser = mut_parser.clone();
         let mut thread = Thread::new(parser, frames);
         let (parser, results) = loop {
@@ -699,8 +1322,23 @@ impl<'m> Thread<'m> {
 
     fn run<'a>(mut self, store: &'a mut Store<'m>) -> Result<RunResult<'a, 'm>, Error> {
         loop {
-            // TODO: When trapping, we could return some CoreDump<'m> that contains the Thread<'m>.
-            // This permits to dump the frames.
+            if let Some(fuel) = &mut store.fuel {
+                match fuel.checked_sub(1) {
+                    Some(x) => *fuel = x,
+                    None => {
+                        store.suspended = Some(self);
+                        return Ok(RunResult::OutOfFuel);
+                    }
+                }
+            }
+            #[cfg(feature = "profile")]
+            if let Some(profile) = &mut store.profile {
+                profile.step(&store.insts, &self.frames);
+            }
+            #[cfg(feature = "debugger")]
+            if store.debug.is_some() {
+                self.debug(store);
+            }
             match self.step(store)? {
                 ThreadResult::Continue(x) => self = x,
                 ThreadResult::Done(x) => return Ok(RunResult::Done(x)),
@@ -715,44 +1353,75 @@ impl<'m> Thread<'m> {
         let inst_id = self.frame().inst_id;
         let inst = &mut store.insts[inst_id];
         match self.parser.parse_instr().into_ok() {
-            Unreachable => return Err(trap()),
+            Unreachable => return Err(self.trap(store, saved, TrapReason::Unreachable)),
             Nop => (),
-            Block(b) => self.push_label(self.blocktype(inst, &b), LabelKind::Block),
-            Loop(b) => self.push_label(self.blocktype(inst, &b), LabelKind::Loop(saved)),
-            If(b) => match self.pop_value().unwrap_i32() {
-                0 => {
-                    self.skip_to_else(inst);
-                    self.push_label(self.blocktype(inst, &b), LabelKind::Block);
+            Block(b) | Loop(b) => self.push_label(inst.module.blocktype(&b)),
+            If(b) => {
+                match self.pop_value().unwrap_i32() {
+                    0 => drop(self.jump(inst, saved, 0)),
+                    _ => self.frame().stp += 1,
                 }
-                _ => self.push_label(self.blocktype(inst, &b), LabelKind::If),
-            },
+                self.push_label(inst.module.blocktype(&b));
+            }
             Else => {
-                self.skip_to_end(inst, 0);
+                self.jump(inst, saved, 0);
+                return Ok(self.exit_label());
+            }
+            #[cfg(feature = "exception-handling")]
+            Try(b) => {
+                self.push_label(inst.module.blocktype(&b));
+                let frame = self.frame();
+                let label = frame.labels.len() - 1;
+                frame.tries.push(TryBlock { label, pc: saved, stp: frame.stp, caught: None });
+                frame.stp += 1;
+            }
+            #[cfg(feature = "exception-handling")]
+            Catch(_) | CatchAll => {
+                self.jump(inst, saved, 0);
                 return Ok(self.exit_label());
             }
+            #[cfg(feature = "exception-handling")]
+            Delegate(_) => return Ok(self.exit_label()),
+            #[cfg(feature = "exception-handling")]
+            Throw(x) => {
+                let tag = store.tag_ptr(inst_id, x);
+                let values = self.pop_values(store.tag_type(tag).params.len());
+                let exception = Exception { tag, values };
+                return Ok(ThreadResult::Continue(self.throw(store, saved, exception)?));
+            }
+            #[cfg(feature = "exception-handling")]
+            Rethrow(l) => {
+                let frame = self.frame();
+                let label = frame.labels.len() - l as usize - 1;
+                let try_ = frame.tries.iter().rfind(|x| x.label == label).unwrap();
+                let exception = try_.caught.clone().unwrap();
+                return Ok(ThreadResult::Continue(self.throw(store, saved, exception)?));
+            }
             End => return Ok(self.exit_label()),
-            Br(l) => return Ok(self.pop_label(inst, l)),
+            Br(l) => return Ok(self.pop_label(inst, saved, l, 0)),
             BrIf(l) => {
                 if self.pop_value().unwrap_i32() != 0 {
-                    return Ok(self.pop_label(inst, l));
+                    return Ok(self.pop_label(inst, saved, l, 0));
                 }
+                self.frame().stp += 1;
             }
             BrTable(ls, ln) => {
-                let i = self.pop_value().unwrap_i32() as usize;
-                return Ok(self.pop_label(inst, ls.get(i).cloned().unwrap_or(ln)));
+                let i = core::cmp::min(self.pop_value().unwrap_i32() as usize, ls.len());
+                let l = ls.get(i).cloned().unwrap_or(ln);
+                return Ok(self.pop_label(inst, saved, l, i));
             }
             Return => return Ok(self.exit_frame()),
-            Call(x) => return self.invoke(store, store.func_ptr(inst_id, x)),
+            Call(x) => return self.invoke(store, saved, store.func_ptr(inst_id, x), false),
             CallIndirect(x, y) => {
-                let i = self.pop_value().unwrap_i32();
-                let x = match store.table(inst_id, x).elems.get(i as usize) {
-                    None | Some(Val::Null(_)) => return Err(trap()),
-                    Some(x) => x.unwrap_ref(),
-                };
-                if store.func_type(x) != store.insts[inst_id].module.types()[y as usize] {
-                    return Err(trap());
-                }
-                return self.invoke(store, x);
+                let ptr = self.indirect_ptr(store, saved, x, y)?;
+                return self.invoke(store, saved, ptr, false);
+            }
+            #[cfg(feature = "tail-call")]
+            ReturnCall(x) => return self.invoke(store, saved, store.func_ptr(inst_id, x), true),
+            #[cfg(feature = "tail-call")]
+            ReturnCallIndirect(x, y) => {
+                let ptr = self.indirect_ptr(store, saved, x, y)?;
+                return self.invoke(store, saved, ptr, true);
             }
             Drop => drop(self.pop_value()),
             Select(_) => {
@@ -780,31 +1449,86 @@ impl<'m> Thread<'m> {
             GlobalSet(x) => store.global(inst_id, x).value = self.pop_value(),
             TableGet(x) => {
                 let i = self.pop_value().unwrap_i32();
-                let v = *store.table(inst_id, x).elems.get(i as usize).ok_or_else(trap)?;
+                let v = match store.table(inst_id, x).elems.get(i as usize) {
+                    None => return Err(self.trap(store, saved, TrapReason::TableOutOfBounds)),
+                    Some(x) => *x,
+                };
                 self.push_value(v);
             }
             TableSet(x) => {
                 let val = self.pop_value();
                 let i = self.pop_value().unwrap_i32();
-                let v = store.table(inst_id, x).elems.get_mut(i as usize).ok_or_else(trap)?;
-                *v = val;
+                match store.table(inst_id, x).elems.get_mut(i as usize) {
+                    None => return Err(self.trap(store, saved, TrapReason::TableOutOfBounds)),
+                    Some(x) => *x = val,
+                }
             }
-            ILoad(n, m) => self.load(store.mem(inst_id, 0), NumType::i(n), n.into(), Sx::U, m)?,
+            ILoad(n, m) => self
+                .load(store.mem(inst_id, m.memory), NumType::i(n), n.into(), Sx::U, m)
+                .map_err(|x| self.trap(store, saved, x))?,
             #[cfg(feature = "float-types")]
-            FLoad(n, m) => self.load(store.mem(inst_id, 0), NumType::f(n), n.into(), Sx::U, m)?,
-            ILoad_(b, s, m) => {
-                self.load(store.mem(inst_id, 0), NumType::i(b.into()), b.into(), s, m)?
-            }
-            IStore(n, m) => self.store(store.mem(inst_id, 0), NumType::i(n), n.into(), m)?,
+            FLoad(n, m) => self
+                .load(store.mem(inst_id, m.memory), NumType::f(n), n.into(), Sx::U, m)
+                .map_err(|x| self.trap(store, saved, x))?,
+            ILoad_(b, s, m) => self
+                .load(store.mem(inst_id, m.memory), NumType::i(b.into()), b.into(), s, m)
+                .map_err(|x| self.trap(store, saved, x))?,
+            IStore(n, m) => self
+                .store(store.mem(inst_id, m.memory), NumType::i(n), n.into(), m)
+                .map_err(|x| self.trap(store, saved, x))?,
             #[cfg(feature = "float-types")]
-            FStore(n, m) => self.store(store.mem(inst_id, 0), NumType::f(n), n.into(), m)?,
-            IStore_(b, m) => {
-                self.store(store.mem(inst_id, 0), NumType::i(b.into()), b.into(), m)?
-            }
-            MemorySize => self.push_value(Val::I32(store.mem(inst_id, 0).size())),
-            MemoryGrow => {
+            FStore(n, m) => self
+                .store(store.mem(inst_id, m.memory), NumType::f(n), n.into(), m)
+                .map_err(|x| self.trap(store, saved, x))?,
+            IStore_(b, m) => self
+                .store(store.mem(inst_id, m.memory), NumType::i(b.into()), b.into(), m)
+                .map_err(|x| self.trap(store, saved, x))?,
+            #[cfg(feature = "threads")]
+            AtomicNotify(m) => self
+                .atomic_notify(store.mem(inst_id, m.memory), m)
+                .map_err(|x| self.trap(store, saved, x))?,
+            #[cfg(feature = "threads")]
+            AtomicWait(n, m) => self
+                .atomic_wait(store.mem(inst_id, m.memory), n.into(), m)
+                .map_err(|x| self.trap(store, saved, x))?,
+            #[cfg(feature = "threads")]
+            AtomicFence => (),
+            #[cfg(feature = "threads")]
+            IAtomicLoad(n, m) => self
+                .atomic_load(store.mem(inst_id, m.memory), NumType::i(n), n.into(), m)
+                .map_err(|x| self.trap(store, saved, x))?,
+            #[cfg(feature = "threads")]
+            IAtomicLoad_(b, m) => self
+                .atomic_load(store.mem(inst_id, m.memory), NumType::i(b.into()), b.into(), m)
+                .map_err(|x| self.trap(store, saved, x))?,
+            #[cfg(feature = "threads")]
+            IAtomicStore(n, m) => self
+                .atomic_store(store.mem(inst_id, m.memory), n.into(), m)
+                .map_err(|x| self.trap(store, saved, x))?,
+            #[cfg(feature = "threads")]
+            IAtomicStore_(b, m) => self
+                .atomic_store(store.mem(inst_id, m.memory), b.into(), m)
+                .map_err(|x| self.trap(store, saved, x))?,
+            #[cfg(feature = "threads")]
+            IAtomicRmw(n, op, m) => self
+                .atomic_rmw(store.mem(inst_id, m.memory), NumType::i(n), n.into(), op, m)
+                .map_err(|x| self.trap(store, saved, x))?,
+            #[cfg(feature = "threads")]
+            IAtomicRmw_(b, op, m) => self
+                .atomic_rmw(store.mem(inst_id, m.memory), NumType::i(b.into()), b.into(), op, m)
+                .map_err(|x| self.trap(store, saved, x))?,
+            #[cfg(feature = "threads")]
+            IAtomicCmpxchg(n, m) => self
+                .atomic_cmpxchg(store.mem(inst_id, m.memory), NumType::i(n), n.into(), m)
+                .map_err(|x| self.trap(store, saved, x))?,
+            #[cfg(feature = "threads")]
+            IAtomicCmpxchg_(b, m) => self
+                .atomic_cmpxchg(store.mem(inst_id, m.memory), NumType::i(b.into()), b.into(), m)
+                .map_err(|x| self.trap(store, saved, x))?,
+            MemorySize(x) => self.push_value(Val::I32(store.mem(inst_id, x).size())),
+            MemoryGrow(x) => {
                 let n = self.pop_value().unwrap_i32();
-                self.push_value(Val::I32(grow(store.mem(inst_id, 0), n, ())));
+                self.push_value(Val::I32(grow(store.mem(inst_id, x), n, ())));
             }
             I32Const(c) => self.push_value(Val::I32(c)),
             I64Const(c) => self.push_value(Val::I64(c)),
@@ -816,13 +1540,13 @@ impl<'m> Thread<'m> {
             IRelOp(n, op) => self.irelop(n, op),
             #[cfg(feature = "float-types")]
             FRelOp(n, op) => self.frelop(n, op),
-            IUnOp(n, op) => self.iunop(n, op)?,
+            IUnOp(n, op) => self.iunop(n, op).map_err(|x| self.trap(store, saved, x))?,
             #[cfg(feature = "float-types")]
             FUnOp(n, op) => self.funop(n, op),
-            IBinOp(n, op) => self.ibinop(n, op)?,
+            IBinOp(n, op) => self.ibinop(n, op).map_err(|x| self.trap(store, saved, x))?,
             #[cfg(feature = "float-types")]
             FBinOp(n, op) => self.fbinop(n, op),
-            CvtOp(op) => self.cvtop(op)?,
+            CvtOp(op) => self.cvtop(op).map_err(|x| self.trap(store, saved, x))?,
             IExtend(b) => self.extend(b),
             RefNull(t) => self.push_value(Val::Null(t)),
             RefIsNull => {
@@ -830,40 +1554,57 @@ impl<'m> Thread<'m> {
                 self.push_value(Val::I32(c));
             }
             RefFunc(x) => self.push_value(Val::Ref(store.func_ptr(inst_id, x))),
-            MemoryInit(x) => {
+            MemoryInit(x, y) => {
                 let n = self.pop_value().unwrap_i32() as usize;
                 let s = self.pop_value().unwrap_i32() as usize;
                 let d = self.pop_value().unwrap_i32() as usize;
-                let data = if inst.datas[x as usize] {
+                let data = if inst.datas[y as usize] {
                     &[]
                 } else {
-                    let mut parser = inst.module.data(x);
+                    let mut parser = inst.module.data(y);
                     let mut data = ComputeData::new(store, inst_id);
                     parser.parse_data(&mut data).into_ok();
                     data.init
                 };
-                let mem = store.mem(inst_id, 0);
-                memory_init(d, s, n, mem, data)?;
+                let mem = store.mem(inst_id, x);
+                let result = memory_init(d, s, n, mem, data);
+                result.map_err(|x| self.trap(store, saved, x))?;
             }
             DataDrop(x) => inst.datas[x as usize] = true,
-            MemoryCopy => {
+            MemoryCopy(x, y) => {
                 let n = self.pop_value().unwrap_i32() as usize;
                 let s = self.pop_value().unwrap_i32() as usize;
                 let d = self.pop_value().unwrap_i32() as usize;
-                let mem = store.mem(inst_id, 0);
-                if core::cmp::max(s, d).checked_add(n).map_or(true, |x| x > mem.len() as usize) {
-                    return Err(trap());
+                if store.mem_ptr(inst_id, x) == store.mem_ptr(inst_id, y) {
+                    let mem = store.mem(inst_id, x);
+                    if core::cmp::max(s, d).checked_add(n).map_or(true, |x| x > mem.len() as usize)
+                    {
+                        return Err(self.trap(store, saved, TrapReason::MemoryOutOfBounds));
+                    }
+                    mem.data.copy_within(s .. s + n, d);
+                } else {
+                    let src = store.mem(inst_id, y);
+                    if s.checked_add(n).map_or(true, |x| x > src.len() as usize) {
+                        return Err(self.trap(store, saved, TrapReason::MemoryOutOfBounds));
+                    }
+                    let src = src.data[s ..][.. n].as_ptr();
+                    let dst = store.mem(inst_id, x);
+                    if d.checked_add(n).map_or(true, |x| x > dst.len() as usize) {
+                        return Err(self.trap(store, saved, TrapReason::MemoryOutOfBounds));
+                    }
+                    // SAFETY: Different memories don't overlap because their data are mutable
+                    // borrows. The source is not modified between its borrow and this copy.
+                    unsafe { core::ptr::copy_nonoverlapping(src, dst.data[d ..].as_mut_ptr(), n) };
                 }
-                mem.data.copy_within(s .. s + n, d);
             }
-            MemoryFill => {
+            MemoryFill(x) => {
                 let n = self.pop_value().unwrap_i32() as usize;
                 let val = self.pop_value().unwrap_i32() as u8;
                 let d = self.pop_value().unwrap_i32() as usize;
-                let mem = store.mem(inst_id, 0);
+                let mem = store.mem(inst_id, x);
                 if d.checked_add(n).map_or(true, |x| x > mem.len() as usize) {
                     memory_too_small(d, n, mem);
-                    return Err(trap());
+                    return Err(self.trap(store, saved, TrapReason::MemoryOutOfBounds));
                 }
                 mem.data[d ..][.. n].fill(val);
             }
@@ -880,19 +1621,26 @@ impl<'m> Thread<'m> {
                     elems.init
                 };
                 let table = store.table(inst_id, x);
-                table_init(d, s, n, table, &elems)?;
+                let result = table_init(d, s, n, table, &elems);
+                result.map_err(|x| self.trap(store, saved, x))?;
             }
             ElemDrop(x) => inst.elems[x as usize] = true,
             TableCopy(x, y) => {
                 let n = self.pop_value().unwrap_i32() as usize;
                 let s = self.pop_value().unwrap_i32() as usize;
                 let d = self.pop_value().unwrap_i32() as usize;
-                let sn = s.checked_add(n).ok_or_else(trap)?;
-                let dn = d.checked_add(n).ok_or_else(trap)?;
+                let (sn, dn) = match (s.checked_add(n), d.checked_add(n)) {
+                    (Some(sn), Some(dn))
+                        if sn <= store.table(inst_id, y).elems.len()
+                            && dn <= store.table(inst_id, x).elems.len() =>
+                    {
+                        (sn, dn)
+                    }
+                    _ => return Err(self.trap(store, saved, TrapReason::TableOutOfBounds)),
+                };
                 // TODO: This is not efficient.
-                let ys = store.table(inst_id, y).elems.get(s .. sn).ok_or_else(trap)?.to_vec();
-                let xs = store.table(inst_id, x).elems.get_mut(d .. dn).ok_or_else(trap)?;
-                xs.copy_from_slice(&ys);
+                let ys = store.table(inst_id, y).elems[s .. sn].to_vec();
+                store.table(inst_id, x).elems[d .. dn].copy_from_slice(&ys);
             }
             TableGrow(x) => {
                 let n = self.pop_value().unwrap_i32();
@@ -907,10 +1655,118 @@ impl<'m> Thread<'m> {
                 let i = self.pop_value().unwrap_i32() as usize;
                 let table = store.table(inst_id, x);
                 if i.checked_add(n).map_or(true, |x| x > table.elems.len()) {
-                    return Err(trap());
+                    return Err(self.trap(store, saved, TrapReason::TableOutOfBounds));
                 }
                 table.elems[i ..][.. n].fill(val);
             }
+            #[cfg(feature = "vector-types")]
+            V128Load(m) => self
+                .vload(store.mem(inst_id, m.memory), 128, m, |x| x)
+                .map_err(|x| self.trap(store, saved, x))?,
+            #[cfg(feature = "vector-types")]
+            V128LoadExtend(l, s, m) => self
+                .vload(store.mem(inst_id, m.memory), 64, m, |x| {
+                    syntax::VUnOp::Extend(l, Half::Low, s).v128(x)
+                })
+                .map_err(|x| self.trap(store, saved, x))?,
+            #[cfg(feature = "vector-types")]
+            V128LoadSplat(l, m) => self
+                .vload(store.mem(inst_id, m.memory), l.into(), m, |x| Shape::I(l).splat(x as u64))
+                .map_err(|x| self.trap(store, saved, x))?,
+            #[cfg(feature = "vector-types")]
+            V128LoadZero(n, m) => self
+                .vload(store.mem(inst_id, m.memory), n.into(), m, |x| x)
+                .map_err(|x| self.trap(store, saved, x))?,
+            #[cfg(feature = "vector-types")]
+            V128LoadLane(l, m, i) => {
+                let v = self.pop_value().unwrap_v128();
+                self.vload(store.mem(inst_id, m.memory), l.into(), m, |x| {
+                    Shape::I(l).replace_lane(v, i, x as u64)
+                })
+                .map_err(|x| self.trap(store, saved, x))?
+            }
+            #[cfg(feature = "vector-types")]
+            V128Store(m) => self
+                .vstore(store.mem(inst_id, m.memory), 128, m, |x| x)
+                .map_err(|x| self.trap(store, saved, x))?,
+            #[cfg(feature = "vector-types")]
+            V128StoreLane(l, m, i) => self
+                .vstore(store.mem(inst_id, m.memory), l.into(), m, |x| {
+                    Shape::I(l).extract_lane(x, i, None) as u128
+                })
+                .map_err(|x| self.trap(store, saved, x))?,
+            #[cfg(feature = "vector-types")]
+            V128Const(c) => self.push_value(Val::V128(c)),
+            #[cfg(feature = "vector-types")]
+            I8x16Shuffle(lanes) => {
+                let y = self.pop_value().unwrap_v128().to_le_bytes();
+                let x = self.pop_value().unwrap_v128().to_le_bytes();
+                let z = lanes.map(|i| if i < 16 { x[i as usize] } else { y[i as usize - 16] });
+                self.push_value(Val::V128(u128::from_le_bytes(z)));
+            }
+            #[cfg(feature = "vector-types")]
+            Splat(s) => {
+                let x = match self.pop_value() {
+                    Val::I32(x) | Val::F32(x) => x as u64,
+                    Val::I64(x) | Val::F64(x) => x,
+                    _ => unreachable!(),
+                };
+                self.push_value(Val::V128(s.splat(x)));
+            }
+            #[cfg(feature = "vector-types")]
+            ExtractLane(s, sx, i) => {
+                let x = s.extract_lane(self.pop_value().unwrap_v128(), i, sx);
+                self.push_value(match s.unpacked() {
+                    NumType::I32 => Val::I32(x as u32),
+                    NumType::I64 => Val::I64(x),
+                    NumType::F32 => Val::F32(x as u32),
+                    NumType::F64 => Val::F64(x),
+                });
+            }
+            #[cfg(feature = "vector-types")]
+            ReplaceLane(s, i) => {
+                let y = match self.pop_value() {
+                    Val::I32(x) | Val::F32(x) => x as u64,
+                    Val::I64(x) | Val::F64(x) => x,
+                    _ => unreachable!(),
+                };
+                let x = self.pop_value().unwrap_v128();
+                self.push_value(Val::V128(s.replace_lane(x, i, y)));
+            }
+            #[cfg(feature = "vector-types")]
+            VUnOp(op) => {
+                let x = self.pop_value().unwrap_v128();
+                self.push_value(Val::V128(op.v128(x)));
+            }
+            #[cfg(feature = "vector-types")]
+            VBinOp(op) => {
+                let y = self.pop_value().unwrap_v128();
+                let x = self.pop_value().unwrap_v128();
+                self.push_value(Val::V128(op.v128(x, y)));
+            }
+            #[cfg(feature = "vector-types")]
+            V128Bitselect => {
+                let c = self.pop_value().unwrap_v128();
+                let y = self.pop_value().unwrap_v128();
+                let x = self.pop_value().unwrap_v128();
+                self.push_value(Val::V128((x & c) | (y & !c)));
+            }
+            #[cfg(feature = "vector-types")]
+            VTestOp(op) => {
+                let x = self.pop_value().unwrap_v128();
+                self.push_value(Val::I32(op.v128(x) as u32));
+            }
+            #[cfg(feature = "vector-types")]
+            VBitmask(l) => {
+                let x = self.pop_value().unwrap_v128();
+                self.push_value(Val::I32(l.bitmask(x)));
+            }
+            #[cfg(feature = "vector-types")]
+            VShiftOp(l, op) => {
+                let y = self.pop_value().unwrap_i32();
+                let x = self.pop_value().unwrap_v128();
+                self.push_value(Val::V128(op.v128(l, x, y)));
+            }
         }
         Ok(ThreadResult::Continue(self))
     }
@@ -923,15 +1779,20 @@ impl<'m> Thread<'m> {
         InstId { store_id: store.id, inst_id: self.inst_id() }
     }
 
+    /// Returns the number of bytes used by the frames and values of this thread.
+    fn stack_size(&self) -> usize {
+        self.frames.last().map_or(0, |x| x.stack + x.labels_size())
+    }
+
     fn frame(&mut self) -> &mut Frame<'m> {
         self.frames.last_mut().unwrap()
     }
 
-    fn labels(&mut self) -> &mut Vec<Label<'m>> {
+    fn labels(&mut self) -> &mut Vec<Label> {
         &mut self.frame().labels
     }
 
-    fn label(&mut self) -> &mut Label<'m> {
+    fn label(&mut self) -> &mut Label {
         self.labels().last_mut().unwrap()
     }
 
@@ -947,12 +1808,14 @@ impl<'m> Thread<'m> {
         self.values().push(value);
     }
 
-    fn push_value_or_trap(&mut self, value: Option<Val>) -> Result<(), Error> {
+    fn push_value_or_trap(
+        &mut self, value: Option<Val>, reason: TrapReason,
+    ) -> Result<(), TrapReason> {
         if let Some(x) = value {
             self.push_value(x);
             Ok(())
         } else {
-            Err(trap())
+            Err(reason)
         }
     }
 
@@ -973,35 +1836,46 @@ impl<'m> Thread<'m> {
         values
     }
 
-    fn push_label(&mut self, type_: FuncType<'m>, kind: LabelKind<'m>) {
+    fn push_label(&mut self, type_: FuncType<'m>) {
         let values = self.pop_values(type_.params.len());
-        let arity = match kind {
-            LabelKind::Block | LabelKind::If => type_.results.len(),
-            LabelKind::Loop(_) => type_.params.len(),
-        };
-        let label = Label { arity, kind, values };
-        self.labels().push(label);
+        self.labels().push(Label { values });
     }
 
-    fn pop_label(mut self, inst: &mut Instance<'m>, l: LabelIdx) -> ThreadResult<'m> {
+    /// Branches to label `l` using the side-table entry `k` of the instruction at `pc`.
+    fn pop_label(
+        mut self, inst: &Instance<'m>, pc: &'m [u8], l: LabelIdx, k: usize,
+    ) -> ThreadResult<'m> {
         let i = self.labels().len() - l as usize - 1;
         if i == 0 {
             return self.exit_frame();
         }
+        let arity = self.jump(inst, pc, k);
         let values = core::mem::take(self.values());
-        let frame = self.frame();
-        let Label { arity, kind, .. } = frame.labels.drain(i ..).next().unwrap();
+        self.frame().labels.drain(i ..);
+        #[cfg(feature = "exception-handling")]
+        self.frame().exit_tries();
         self.values().extend_from_slice(&values[values.len() - arity ..]);
-        match kind {
-            LabelKind::Loop(pos) => unsafe { self.parser.restore(pos) },
-            LabelKind::Block | LabelKind::If => self.skip_to_end(inst, l),
-        }
         ThreadResult::Continue(self)
     }
 
+    /// Jumps using the side-table entry `k` of the instruction at `pc`.
+    ///
+    /// Returns the number of values kept on the stack.
+    fn jump(&mut self, inst: &Instance<'m>, pc: &'m [u8], k: usize) -> usize {
+        let frame = self.frame();
+        let stp = frame.stp + k;
+        let entry = inst.module.side_table(stp);
+        frame.stp = stp.wrapping_add_signed(entry.delta_stp as isize);
+        // SAFETY: The side-table targets an instruction of the same function body.
+        unsafe { self.parser.restore(target(pc, entry.delta_ip)) };
+        entry.val_count as usize
+    }
+
     fn exit_label(mut self) -> ThreadResult<'m> {
         let frame = self.frame();
         let label = frame.labels.pop().unwrap();
+        #[cfg(feature = "exception-handling")]
+        frame.exit_tries();
         if frame.labels.is_empty() {
             let frame = self.frames.pop().unwrap();
             debug_assert_eq!(label.values.len(), frame.arity);
@@ -1027,20 +1901,74 @@ impl<'m> Thread<'m> {
         ThreadResult::Continue(self)
     }
 
-    fn skip_to_else(&mut self, inst: &mut Instance<'m>) {
-        inst.module.skip_to_else(&mut self.parser);
-    }
-
-    fn skip_to_end(&mut self, inst: &mut Instance<'m>, l: LabelIdx) {
-        inst.module.skip_to_end(&mut self.parser, l);
-    }
-
-    fn blocktype(&self, inst: &Instance<'m>, b: &BlockType) -> FuncType<'m> {
-        match *b {
-            BlockType::None => FuncType { params: ().into(), results: ().into() },
-            BlockType::Type(t) => FuncType { params: ().into(), results: t.into() },
-            BlockType::Index(x) => inst.module.types()[x as usize],
+    /// Throws an exception at `pc`.
+    ///
+    /// Execution continues in the innermost handler of the exception. Traps if there is none.
+    #[cfg(feature = "exception-handling")]
+    fn throw(
+        mut self, store: &mut Store<'m>, pc: &'m [u8], exception: Exception,
+    ) -> Result<Thread<'m>, Error> {
+        let handler = match self.handler(store, exception.tag) {
+            Some(x) => x,
+            None => return Err(self.trap(store, pc, TrapReason::UncaughtException)),
+        };
+        self.frames.truncate(handler.frame + 1);
+        let frame = self.frame();
+        frame.tries.truncate(handler.try_ + 1);
+        let try_ = frame.tries.last_mut().unwrap();
+        frame.labels.truncate(try_.label + 1);
+        frame.labels[try_.label].values = match handler.all {
+            true => Vec::new(),
+            false => exception.values.clone(),
+        };
+        try_.caught = Some(exception);
+        frame.stp = handler.stp;
+        unsafe { self.parser.restore(handler.body) };
+        Ok(self)
+    }
+
+    /// Returns the innermost handler of an exception with the given tag, if any.
+    #[cfg(feature = "exception-handling")]
+    fn handler(&self, store: &Store<'m>, tag: Ptr) -> Option<Handler<'m>> {
+        for (f, frame) in self.frames.iter().enumerate().rev() {
+            let module = &store.insts[frame.inst_id].module;
+            // Blocks inside the target of a `delegate` don't handle the exception.
+            let mut target_label = usize::MAX;
+            for (t, try_) in frame.tries.iter().enumerate().rev() {
+                // Blocks in a clause don't handle exceptions thrown in the clause.
+                if target_label < try_.label || try_.caught.is_some() {
+                    continue;
+                }
+                let mut clause = try_.pc;
+                let mut stp = try_.stp;
+                loop {
+                    let entry = module.side_table(stp);
+                    // SAFETY: The side-table targets an instruction of the same function body.
+                    clause = unsafe { target(clause, entry.delta_ip) };
+                    stp = stp.wrapping_add_signed(entry.delta_stp as isize);
+                    let mut parser = unsafe { Parser::new(clause) };
+                    let (stp, all) = match parser.parse_instr().into_ok() {
+                        Instr::Catch(x) if store.tag_ptr(frame.inst_id, x) == tag => {
+                            (stp + 2, false)
+                        }
+                        // The second entry of a `catch` targets the next clause.
+                        Instr::Catch(_) => {
+                            stp += 1;
+                            continue;
+                        }
+                        Instr::CatchAll => (stp + 1, true),
+                        Instr::Delegate(l) => {
+                            target_label = try_.label - l as usize - 1;
+                            break;
+                        }
+                        Instr::End => break,
+                        _ => unreachable!(),
+                    };
+                    return Some(Handler { frame: f, try_: t, body: parser.save(), stp, all });
+                }
+            }
         }
+        None
     }
 
     fn mem_slice<'a>(
@@ -1056,10 +1984,10 @@ impl<'m> Thread<'m> {
 
     fn load(
         &mut self, mem: &mut Memory<'m>, t: NumType, n: usize, s: Sx, m: MemArg,
-    ) -> Result<(), Error> {
+    ) -> Result<(), TrapReason> {
         let i = self.pop_value().unwrap_i32();
         let mem = match self.mem_slice(mem, m, i, n / 8) {
-            None => return Err(trap()),
+            None => return Err(TrapReason::MemoryOutOfBounds),
             Some(x) => x,
         };
         macro_rules! convert {
@@ -1092,11 +2020,11 @@ impl<'m> Thread<'m> {
 
     fn store(
         &mut self, mem: &mut Memory<'m>, t: NumType, n: usize, m: MemArg,
-    ) -> Result<(), Error> {
+    ) -> Result<(), TrapReason> {
         let c = self.pop_value();
         let i = self.pop_value().unwrap_i32();
         let mem = match self.mem_slice(mem, m, i, n / 8) {
-            None => return Err(trap()),
+            None => return Err(TrapReason::MemoryOutOfBounds),
             Some(x) => x,
         };
         macro_rules! convert {
@@ -1123,6 +2051,136 @@ impl<'m> Thread<'m> {
         Ok(())
     }
 
+    /// Returns the bytes of an atomic access.
+    ///
+    /// Traps if the access is out of bounds or not naturally aligned.
+    #[cfg(feature = "threads")]
+    fn atomic_slice<'a>(
+        &mut self, mem: &'a mut Memory<'m>, m: MemArg, i: u32, n: usize,
+    ) -> Result<&'a mut [u8], TrapReason> {
+        let mem = self.mem_slice(mem, m, i, n / 8).ok_or(TrapReason::MemoryOutOfBounds)?;
+        // The effective address does not overflow since the access is in bounds.
+        if (i + m.offset) as usize % (n / 8) != 0 {
+            return Err(TrapReason::UnalignedAtomic);
+        }
+        Ok(mem)
+    }
+
+    #[cfg(feature = "threads")]
+    fn atomic_notify(&mut self, mem: &mut Memory<'m>, m: MemArg) -> Result<(), TrapReason> {
+        let _count = self.pop_value().unwrap_i32();
+        let i = self.pop_value().unwrap_i32();
+        self.atomic_slice(mem, m, i, 32)?;
+        // There are no other threads, so nobody is waiting.
+        self.push_value(Val::I32(0));
+        Ok(())
+    }
+
+    #[cfg(feature = "threads")]
+    fn atomic_wait(&mut self, mem: &mut Memory<'m>, n: usize, m: MemArg) -> Result<(), TrapReason> {
+        let _timeout = self.pop_value().unwrap_i64();
+        let c = atomic_bits(self.pop_value());
+        let i = self.pop_value().unwrap_i32();
+        if !mem.shared {
+            return Err(TrapReason::UnsharedMemory);
+        }
+        let mem = self.atomic_slice(mem, m, i, n)?;
+        // There are no other threads, so nobody can notify. Waiting times out immediately instead
+        // of blocking forever.
+        let r = if atomic_read(mem) == c { 2 } else { 1 };
+        self.push_value(Val::I32(r));
+        Ok(())
+    }
+
+    #[cfg(feature = "threads")]
+    fn atomic_load(
+        &mut self, mem: &mut Memory<'m>, t: NumType, n: usize, m: MemArg,
+    ) -> Result<(), TrapReason> {
+        let i = self.pop_value().unwrap_i32();
+        let mem = self.atomic_slice(mem, m, i, n)?;
+        let c = atomic_read(mem);
+        self.push_value(atomic_val(t, c));
+        Ok(())
+    }
+
+    #[cfg(feature = "threads")]
+    fn atomic_store(
+        &mut self, mem: &mut Memory<'m>, n: usize, m: MemArg,
+    ) -> Result<(), TrapReason> {
+        let c = atomic_bits(self.pop_value());
+        let i = self.pop_value().unwrap_i32();
+        let mem = self.atomic_slice(mem, m, i, n)?;
+        atomic_write(mem, c);
+        Ok(())
+    }
+
+    #[cfg(feature = "threads")]
+    fn atomic_rmw(
+        &mut self, mem: &mut Memory<'m>, t: NumType, n: usize, op: RmwOp, m: MemArg,
+    ) -> Result<(), TrapReason> {
+        let c = atomic_bits(self.pop_value());
+        let i = self.pop_value().unwrap_i32();
+        let mem = self.atomic_slice(mem, m, i, n)?;
+        let old = atomic_read(mem);
+        let new = match op {
+            RmwOp::Add => old.wrapping_add(c),
+            RmwOp::Sub => old.wrapping_sub(c),
+            RmwOp::And => old & c,
+            RmwOp::Or => old | c,
+            RmwOp::Xor => old ^ c,
+            RmwOp::Xchg => c,
+        };
+        atomic_write(mem, new);
+        self.push_value(atomic_val(t, old));
+        Ok(())
+    }
+
+    #[cfg(feature = "threads")]
+    fn atomic_cmpxchg(
+        &mut self, mem: &mut Memory<'m>, t: NumType, n: usize, m: MemArg,
+    ) -> Result<(), TrapReason> {
+        let replacement = atomic_bits(self.pop_value());
+        let expected = atomic_bits(self.pop_value());
+        let i = self.pop_value().unwrap_i32();
+        let mem = self.atomic_slice(mem, m, i, n)?;
+        let old = atomic_read(mem);
+        // The expected value is wrapped to the access width before comparison.
+        if old == expected & (u64::MAX >> (64 - n)) {
+            atomic_write(mem, replacement);
+        }
+        self.push_value(atomic_val(t, old));
+        Ok(())
+    }
+
+    #[cfg(feature = "vector-types")]
+    fn vload(
+        &mut self, mem: &mut Memory<'m>, n: usize, m: MemArg, f: impl FnOnce(u128) -> u128,
+    ) -> Result<(), TrapReason> {
+        let i = self.pop_value().unwrap_i32();
+        let mem = match self.mem_slice(mem, m, i, n / 8) {
+            None => return Err(TrapReason::MemoryOutOfBounds),
+            Some(x) => x,
+        };
+        let mut bytes = [0; 16];
+        bytes[.. n / 8].copy_from_slice(mem);
+        self.push_value(Val::V128(f(u128::from_le_bytes(bytes))));
+        Ok(())
+    }
+
+    #[cfg(feature = "vector-types")]
+    fn vstore(
+        &mut self, mem: &mut Memory<'m>, n: usize, m: MemArg, f: impl FnOnce(u128) -> u128,
+    ) -> Result<(), TrapReason> {
+        let c = f(self.pop_value().unwrap_v128());
+        let i = self.pop_value().unwrap_i32();
+        let mem = match self.mem_slice(mem, m, i, n / 8) {
+            None => return Err(TrapReason::MemoryOutOfBounds),
+            Some(x) => x,
+        };
+        mem.copy_from_slice(&c.to_le_bytes()[.. n / 8]);
+        Ok(())
+    }
+
     fn itestop(&mut self, n: Nx, op: ITestOp) {
         let x = self.pop_value();
         let z = match n {
@@ -1142,7 +2200,7 @@ impl<'m> Thread<'m> {
         self.push_value(Val::I32(z as u32))
     }
 
-    fn iunop(&mut self, n: Nx, op: IUnOp) -> Result<(), Error> {
+    fn iunop(&mut self, n: Nx, op: IUnOp) -> Result<(), TrapReason> {
         let x = self.pop_value();
         let z = try {
             match n {
@@ -1150,10 +2208,10 @@ impl<'m> Thread<'m> {
                 Nx::N64 => Val::I64(op.n64(x.unwrap_i64())?),
             }
         };
-        self.push_value_or_trap(z)
+        self.push_value_or_trap(z, TrapReason::IntegerOverflow)
     }
 
-    fn ibinop(&mut self, n: Nx, op: IBinOp) -> Result<(), Error> {
+    fn ibinop(&mut self, n: Nx, op: IBinOp) -> Result<(), TrapReason> {
         let y = self.pop_value();
         let x = self.pop_value();
         let z = try {
@@ -1162,7 +2220,13 @@ impl<'m> Thread<'m> {
                 Nx::N64 => Val::I64(op.n64(x.unwrap_i64(), y.unwrap_i64())?),
             }
         };
-        self.push_value_or_trap(z)
+        let reason = match (op, y) {
+            (IBinOp::Div(_) | IBinOp::Rem(_), Val::I32(0) | Val::I64(0)) => {
+                TrapReason::IntegerDivideByZero
+            }
+            _ => TrapReason::IntegerOverflow,
+        };
+        self.push_value_or_trap(z, reason)
     }
 
     #[cfg(feature = "float-types")]
@@ -1197,7 +2261,7 @@ impl<'m> Thread<'m> {
         self.push_value(z);
     }
 
-    fn cvtop(&mut self, op: CvtOp) -> Result<(), Error> {
+    fn cvtop(&mut self, op: CvtOp) -> Result<(), TrapReason> {
         #[cfg(feature = "float-types")]
         macro_rules! trunc {
             ($x:expr, $n:tt, $m:tt, $s:tt) => {{
@@ -1275,7 +2339,14 @@ impl<'m> Thread<'m> {
                 },
             }
         };
-        self.push_value_or_trap(z)
+        let reason = match x {
+            #[cfg(feature = "float-types")]
+            Val::F32(x) if f32::from_bits(x).is_nan() => TrapReason::InvalidConversionToInteger,
+            #[cfg(feature = "float-types")]
+            Val::F64(x) if f64::from_bits(x).is_nan() => TrapReason::InvalidConversionToInteger,
+            _ => TrapReason::IntegerOverflow,
+        };
+        self.push_value_or_trap(z, reason)
     }
 
     fn extend(&mut self, n: Bx) {
@@ -1290,20 +2361,40 @@ impl<'m> Thread<'m> {
         self.push_value(z)
     }
 
-    fn invoke(mut self, store: &mut Store<'m>, ptr: Ptr) -> Result<ThreadResult<'m>, Error> {
-        // TODO: This should be based on actual size in RAM.
-        const MAX_FRAMES: usize = 1000;
-        if self.frames.len() >= MAX_FRAMES {
-            return Err(trap());
+    /// Returns the function of table `x` called by `call_indirect` with type `y`.
+    fn indirect_ptr(
+        &mut self, store: &mut Store<'m>, pc: &'m [u8], x: TableIdx, y: TypeIdx,
+    ) -> Result<Ptr, Error> {
+        let inst_id = self.frame().inst_id;
+        let i = self.pop_value().unwrap_i32();
+        let ptr = match store.table(inst_id, x).elems.get(i as usize) {
+            None => return Err(self.trap(store, pc, TrapReason::UndefinedElement)),
+            Some(Val::Null(_)) => {
+                return Err(self.trap(store, pc, TrapReason::UninitializedElement));
+            }
+            Some(x) => x.unwrap_ref(),
+        };
+        if store.func_type(ptr) != store.insts[inst_id].module.types()[y as usize] {
+            return Err(self.trap(store, pc, TrapReason::IndirectCallTypeMismatch));
         }
+        Ok(ptr)
+    }
+
+    /// Calls a function.
+    ///
+    /// A tail call replaces the current frame instead of pushing a new one. Calls to the host can't
+    /// replace the current frame, so it is popped when the host returns.
+    fn invoke(
+        mut self, store: &mut Store<'m>, pc: &'m [u8], ptr: Ptr, tail: bool,
+    ) -> Result<ThreadResult<'m>, Error> {
         let t = store.func_type(ptr);
         let inst_id = match ptr.instance() {
             Side::Host => {
                 let index = ptr.index() as usize;
-                let t = store.funcs[index].1;
+                let t = store.linker.funcs[index].1;
                 let arity = t.results.len();
                 let args = self.pop_values(t.params.len());
-                store.threads.push(Continuation { thread: self, arity, index, args });
+                store.threads.push(Continuation { thread: self, arity, index, args, tail });
                 return Ok(ThreadResult::Host);
             }
             Side::Wasm(x) => x,
@@ -1311,68 +2402,332 @@ impl<'m> Thread<'m> {
         let mut parser = store.insts[inst_id].module.func(ptr.index());
         let mut locals = self.pop_values(t.params.len());
         append_locals(&mut parser, &mut locals);
-        let ret = self.parser.save();
+        let (ret, stack) = match tail {
+            false => (self.parser.save(), self.stack_size()),
+            true => {
+                let frame = self.frames.last().unwrap();
+                (frame.ret, frame.base())
+            }
+        };
+        let stp = store.insts[inst_id].module.func_stp(ptr.index());
+        let frame = Frame::new(inst_id, ptr.index(), t.results.len(), ret, locals, stp, stack);
+        if frame.stack > store.stack_limit {
+            return Err(self.trap(store, pc, TrapReason::StackExhausted));
+        }
         self.parser = parser;
-        self.frames.push(Frame::new(inst_id, t.results.len(), ret, locals));
+        if tail {
+            self.frames.pop();
+        }
+        self.frames.push(frame);
         Ok(ThreadResult::Continue(self))
     }
+
+    /// Records the core dump of this thread trapping at `pc`.
+    fn trap(&self, store: &mut Store<'m>, pc: &'m [u8], reason: TrapReason) -> Error {
+        let frames = self.backtrace(store, pc);
+        store.core_dump = Some(CoreDump { reason, frames });
+        trap(reason)
+    }
+
+    /// Returns whether this thread executes in an instance or holds a reference to it.
+    fn refers(&self, inst_id: usize) -> bool {
+        let refers = |x: &Val| matches!(x, Val::Ref(p) if p.instance() == Side::Wasm(inst_id));
+        self.frames.iter().any(|frame| {
+            #[cfg(feature = "exception-handling")]
+            for try_ in &frame.tries {
+                let Some(exception) = &try_.caught else { continue };
+                if exception.tag.instance() == Side::Wasm(inst_id)
+                    || exception.values.iter().any(refers)
+                {
+                    return true;
+                }
+            }
+            let mut values = frame.labels.iter().flat_map(|x| &x.values);
+            frame.inst_id == inst_id || frame.locals.iter().any(refers) || values.any(refers)
+        })
+    }
+
+    /// Pauses the thread if it is at a breakpoint or stepping.
+    #[cfg(feature = "debugger")]
+    fn debug(&self, store: &mut Store<'m>) {
+        let inst_id = self.inst_id();
+        let offset = store.insts[inst_id].module.offset(self.parser.save());
+        let debug = store.debug.as_mut().unwrap();
+        if !debug.step && !debug.breakpoints.contains(&(inst_id, offset)) {
+            return;
+        }
+        let mut debugger = debug.debugger.take().unwrap();
+        debugger.pause(Paused { store, thread: self });
+        // The debugger may not be replaced while paused.
+        store.debug.as_mut().unwrap().debugger = Some(debugger);
+    }
+
+    /// Returns the frames of this thread executing at `pc`, from innermost to outermost.
+    fn backtrace(&self, store: &Store<'m>, mut pc: &'m [u8]) -> Vec<CoreFrame> {
+        let mut frames = Vec::with_capacity(self.frames.len());
+        for frame in self.frames.iter().rev() {
+            let inst = &store.insts[frame.inst_id];
+            frames.push(CoreFrame {
+                inst: InstId { store_id: store.id, inst_id: frame.inst_id },
+                func: inst.funcs.ext.len() as u32 + frame.func,
+                offset: inst.module.offset(pc),
+            });
+            pc = frame.ret;
+        }
+        frames
+    }
 }
 
-fn table_init(d: usize, s: usize, n: usize, table: &mut Table, elems: &[Val]) -> Result<(), Error> {
+fn table_init(
+    d: usize, s: usize, n: usize, table: &mut Table, elems: &[Val],
+) -> Result<(), TrapReason> {
     if s.checked_add(n).map_or(true, |x| x > elems.len())
         || d.checked_add(n).map_or(true, |x| x > table.elems.len())
     {
-        Err(trap())
+        Err(TrapReason::TableOutOfBounds)
     } else {
         table.elems[d ..][.. n].copy_from_slice(&elems[s ..][.. n]);
         Ok(())
     }
 }
 
-fn memory_init(d: usize, s: usize, n: usize, mem: &mut Memory, data: &[u8]) -> Result<(), Error> {
+fn memory_init(
+    d: usize, s: usize, n: usize, mem: &mut Memory, data: &[u8],
+) -> Result<(), TrapReason> {
     if s.checked_add(n).map_or(true, |x| x > data.len())
         || d.checked_add(n).map_or(true, |x| x > mem.len() as usize)
     {
         memory_too_small(d, n, mem);
-        Err(trap())
+        Err(TrapReason::MemoryOutOfBounds)
     } else {
         mem.data[d ..][.. n].copy_from_slice(&data[s ..][.. n]);
         Ok(())
     }
 }
 
+#[cfg(feature = "debugger")]
+struct Debug {
+    // The debugger is taken while execution is paused.
+    debugger: Option<Box<dyn Debugger>>,
+    // Instance and byte offset of the instructions where execution pauses.
+    breakpoints: BTreeSet<(usize, usize)>,
+    // Whether execution pauses before each instruction.
+    step: bool,
+}
+
+#[cfg(feature = "debugger")]
+impl core::fmt::Debug for Debug {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Debug")
+            .field("breakpoints", &self.breakpoints)
+            .field("step", &self.step)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "debugger")]
+impl<'a, 'm> Paused<'a, 'm> {
+    /// Returns the frames of the thread, from innermost to outermost.
+    ///
+    /// The offset of the innermost frame is the instruction about to execute.
+    pub fn backtrace(&self) -> Vec<CoreFrame> {
+        self.thread.backtrace(self.store, self.thread.parser.save())
+    }
+
+    /// Returns the locals (including parameters) of a frame, if any.
+    ///
+    /// Frames are indexed from innermost (zero) to outermost.
+    pub fn locals(&self, frame: usize) -> Option<&[Val]> {
+        let frames = &self.thread.frames;
+        Some(&frames.get(frames.len().checked_sub(frame + 1)?)?.locals)
+    }
+
+    /// Returns the memory of an instance, if any.
+    pub fn mem(&mut self, inst: InstId) -> Option<&mut [u8]> {
+        let inst_id = self.store.inst_id(inst).ok()?;
+        let mems = &self.store.insts.get(inst_id)?.mems;
+        if mems.ext.is_empty() && mems.int.is_empty() {
+            return None;
+        }
+        Some(self.store.mem(inst_id, 0).data)
+    }
+
+    /// Returns the module binary of an instance (without its 8-byte header), if any.
+    pub fn binary(&self, inst: InstId) -> Option<&'m [u8]> {
+        let inst_id = self.store.inst_id(inst).ok()?;
+        Some(self.store.insts.get(inst_id)?.module.binary())
+    }
+
+    /// Pauses execution before the instruction at this byte offset of an instance.
+    pub fn set_breakpoint(&mut self, inst: InstId, offset: usize) -> Result<(), Error> {
+        let inst_id = self.store.inst_id(inst)?;
+        self.debug().breakpoints.insert((inst_id, offset));
+        Ok(())
+    }
+
+    /// Removes a breakpoint (if any).
+    pub fn clear_breakpoint(&mut self, inst: InstId, offset: usize) -> Result<(), Error> {
+        let inst_id = self.store.inst_id(inst)?;
+        self.debug().breakpoints.remove(&(inst_id, offset));
+        Ok(())
+    }
+
+    /// Removes all breakpoints.
+    pub fn clear_breakpoints(&mut self) {
+        self.debug().breakpoints.clear();
+    }
+
+    /// Sets whether execution pauses before each instruction.
+    pub fn set_step(&mut self, step: bool) {
+        self.debug().step = step;
+    }
+
+    fn debug(&mut self) -> &mut Debug {
+        self.store.debug.as_mut().unwrap()
+    }
+}
+
+#[cfg(feature = "profile")]
+#[derive(Debug, Default)]
+struct Profile {
+    // Whether instructions are counted.
+    stopped: bool,
+    // Number of instructions per call stack (instance and function index of each frame).
+    counts: BTreeMap<Vec<(usize, u32)>, u64>,
+    // Call stack of the last counted instruction.
+    stack: Vec<(usize, u32)>,
+    // Number of instructions of the current call stack not yet in `counts`.
+    pending: u64,
+}
+
+#[cfg(feature = "profile")]
+impl Profile {
+    /// Counts an instruction about to execute with the given call stack.
+    ///
+    /// Only the depth and innermost frame are compared with the previous instruction, because the
+    /// other frames can't change without going through a shallower call stack first.
+    fn step(&mut self, insts: &[Instance], frames: &[Frame]) {
+        if self.stopped {
+            return;
+        }
+        let func = |x: &Frame| (x.inst_id, insts[x.inst_id].funcs.ext.len() as u32 + x.func);
+        let last = frames.last().map(func);
+        if self.stack.len() != frames.len() || self.stack.last() != last.as_ref() {
+            self.flush();
+            self.stack.clear();
+            self.stack.extend(frames.iter().map(func));
+        }
+        self.pending += 1;
+    }
+
+    fn flush(&mut self) {
+        if 0 < self.pending {
+            *self.counts.entry(self.stack.clone()).or_default() += self.pending;
+            self.pending = 0;
+        }
+    }
+
+    fn stop(&mut self) {
+        self.flush();
+        self.stopped = true;
+    }
+}
+
 #[derive(Debug)]
 struct Frame<'m> {
     inst_id: usize,
+    // Index of the function in its instance (excluding imported functions).
+    func: FuncIdx,
     arity: usize,
     ret: &'m [u8],
     locals: Vec<Val>,
-    labels: Vec<Label<'m>>,
+    labels: Vec<Label>,
+    // Index of the next side-table entry of the function.
+    stp: usize,
+    // Number of bytes used by the thread up to this frame (excluding its labels and values).
+    stack: usize,
+    // The `try` blocks of the labels, from outermost to innermost.
+    #[cfg(feature = "exception-handling")]
+    tries: Vec<TryBlock<'m>>,
 }
 
 impl<'m> Frame<'m> {
-    fn new(inst_id: usize, arity: usize, ret: &'m [u8], locals: Vec<Val>) -> Self {
-        let label = Label { arity, kind: LabelKind::Block, values: vec![] };
-        Frame { inst_id, arity, ret, locals, labels: vec![label] }
+    fn new(
+        inst_id: usize, func: FuncIdx, arity: usize, ret: &'m [u8], locals: Vec<Val>, stp: usize,
+        stack: usize,
+    ) -> Self {
+        let label = Label { values: vec![] };
+        let stack = stack + size_of::<Frame>() + locals.len() * size_of::<Val>();
+        Frame {
+            inst_id,
+            func,
+            arity,
+            ret,
+            locals,
+            labels: vec![label],
+            stp,
+            stack,
+            #[cfg(feature = "exception-handling")]
+            tries: Vec::new(),
+        }
+    }
+
+    /// Returns the number of bytes used by the thread before this frame.
+    fn base(&self) -> usize {
+        self.stack - size_of::<Frame>() - self.locals.len() * size_of::<Val>()
+    }
+
+    /// Returns the number of bytes used by the labels and values of this frame.
+    fn labels_size(&self) -> usize {
+        let values: usize = self.labels.iter().map(|x| x.values.len()).sum();
+        self.labels.len() * size_of::<Label>() + values * size_of::<Val>()
+    }
+
+    /// Drops the `try` blocks whose label was exited.
+    #[cfg(feature = "exception-handling")]
+    fn exit_tries(&mut self) {
+        let n = self.labels.len();
+        while self.tries.last().map_or(false, |x| n <= x.label) {
+            self.tries.pop();
+        }
     }
 }
 
 #[derive(Debug)]
-struct Label<'m> {
-    arity: usize,
-    kind: LabelKind<'m>,
+struct Label {
     values: Vec<Val>,
 }
 
+#[cfg(feature = "exception-handling")]
 #[derive(Debug)]
-enum LabelKind<'m> {
-    // TODO: If and Block can be merged and then we just have Option<NonNull<u8>> which is
-    // optimized.
-    Block,
-    // TODO: Could be just NonNull<u8> since we can reuse the end of current parser since it
-    // never changes.
-    Loop(&'m [u8]),
-    If,
+struct TryBlock<'m> {
+    // Index of the label of the block in its frame.
+    label: usize,
+    // The `try` instruction and the index of its side-table entry.
+    pc: &'m [u8],
+    stp: usize,
+    // The exception being handled, once in a `catch` or `catch_all` clause.
+    caught: Option<Exception>,
+}
+
+#[cfg(feature = "exception-handling")]
+#[derive(Debug, Clone)]
+struct Exception {
+    tag: Ptr,
+    values: Vec<Val>,
+}
+
+/// Clause handling an exception.
+#[cfg(feature = "exception-handling")]
+struct Handler<'m> {
+    // Index of the frame and of its `try` block.
+    frame: usize,
+    try_: usize,
+    // Start of the clause body and its first side-table entry.
+    body: &'m [u8],
+    stp: usize,
+    // Whether the clause is `catch_all` (the exception values are not pushed).
+    all: bool,
 }
 
 impl Table {
@@ -1391,11 +2746,14 @@ struct Memory<'m> {
     // The size currently available to the module. May be larger than the actual data.
     size: u32,
     max: u32,
+    #[cfg(feature = "threads")]
+    shared: bool,
 }
 
 impl<'m> Memory<'m> {
-    fn init(&mut self, mut data: &'m mut [u8], limits: Limits) -> Result<(), Error> {
-        if !data.as_ptr().is_aligned_to(MEMORY_ALIGN) {
+    fn init(&mut self, mut data: &'m mut [u8], type_: MemType) -> Result<(), Error> {
+        let limits = type_.limits;
+        if !data.is_empty() && !data.as_ptr().is_aligned_to(MEMORY_ALIGN) {
             return Err(invalid());
         }
         if limits.max < 0x10000 {
@@ -1408,6 +2766,10 @@ impl<'m> Memory<'m> {
         self.data.fill(0);
         self.size = limits.min;
         self.max = limits.max;
+        #[cfg(feature = "threads")]
+        {
+            self.shared = type_.shared;
+        }
         Ok(())
     }
 
@@ -1416,6 +2778,39 @@ impl<'m> Memory<'m> {
     }
 }
 
+/// Returns the zero-extended little-endian value of an atomic access.
+#[cfg(feature = "threads")]
+fn atomic_read(mem: &[u8]) -> u64 {
+    let mut bytes = [0; 8];
+    bytes[.. mem.len()].copy_from_slice(mem);
+    u64::from_le_bytes(bytes)
+}
+
+/// Writes the wrapped little-endian value of an atomic access.
+#[cfg(feature = "threads")]
+fn atomic_write(mem: &mut [u8], x: u64) {
+    let n = mem.len();
+    mem.copy_from_slice(&x.to_le_bytes()[.. n]);
+}
+
+#[cfg(feature = "threads")]
+fn atomic_bits(x: Val) -> u64 {
+    match x {
+        Val::I32(x) => x as u64,
+        Val::I64(x) => x,
+        _ => unreachable!(),
+    }
+}
+
+#[cfg(feature = "threads")]
+fn atomic_val(t: NumType, x: u64) -> Val {
+    match t {
+        NumType::I32 => Val::I32(x as u32),
+        NumType::I64 => Val::I64(x),
+        _ => unreachable!(),
+    }
+}
+
 impl Global {
     fn new(value: Val) -> Self {
         Global { value }
@@ -1499,6 +2894,8 @@ impl_val_unwrap!(unwrap_i64, I64, u64);
 impl_val_unwrap!(unwrap_f32, F32, u32);
 #[cfg(feature = "float-types")]
 impl_val_unwrap!(unwrap_f64, F64, u64);
+#[cfg(feature = "vector-types")]
+impl_val_unwrap!(unwrap_v128, V128, u128);
 impl_val_unwrap!(unwrap_ref, Ref, Ptr);
 
 impl ValType {
@@ -1529,6 +2926,17 @@ impl ValType {
     }
 }
 
+/// Returns the target of a side-table entry given its branching instruction.
+///
+/// # Safety
+///
+/// The target must be in the same function body as the branching instruction.
+unsafe fn target(pc: &[u8], delta_ip: i32) -> &[u8] {
+    let delta = delta_ip as isize;
+    let len = pc.len().wrapping_add_signed(-delta);
+    unsafe { core::slice::from_raw_parts(pc.as_ptr().offset(delta), len) }
+}
+
 fn append_locals(parser: &mut Parser, locals: &mut Vec<Val>) {
     for _ in 0 .. parser.parse_vec().into_ok() {
         let len = parser.parse_u32().into_ok() as usize;
@@ -1542,6 +2950,303 @@ fn check_types(types: &[ValType], values: &[Val]) -> Result<(), Error> {
     check(types.iter().zip(values.iter()).all(|(t, x)| t.contains(*x)))
 }
 
+/// Writer of store snapshots.
+///
+/// Integers are little-endian 32-bits, except for external references which are 64-bits. Positions
+/// in a module are their byte offset and length.
+#[derive(Default)]
+struct Snapshot(Vec<u8>);
+
+impl Snapshot {
+    fn u32(&mut self, x: u32) {
+        self.0.extend_from_slice(&x.to_le_bytes());
+    }
+
+    fn u64(&mut self, x: u64) {
+        self.0.extend_from_slice(&x.to_le_bytes());
+    }
+
+    fn usize(&mut self, x: usize) {
+        self.u32(x as u32);
+    }
+
+    fn flag(&mut self, x: bool) {
+        self.0.push(x as u8);
+    }
+
+    fn flags(&mut self, xs: &[bool]) {
+        xs.iter().for_each(|&x| self.flag(x));
+    }
+
+    fn val(&mut self, x: Val) {
+        match x {
+            Val::I32(x) => {
+                self.0.push(0);
+                self.u32(x);
+            }
+            Val::I64(x) => {
+                self.0.push(1);
+                self.u64(x);
+            }
+            #[cfg(feature = "float-types")]
+            Val::F32(x) => {
+                self.0.push(2);
+                self.u32(x);
+            }
+            #[cfg(feature = "float-types")]
+            Val::F64(x) => {
+                self.0.push(3);
+                self.u64(x);
+            }
+            #[cfg(feature = "vector-types")]
+            Val::V128(x) => {
+                self.0.push(4);
+                self.0.extend_from_slice(&x.to_le_bytes());
+            }
+            Val::Null(t) => self.0.extend_from_slice(&[5, t as u8]),
+            Val::Ref(x) => {
+                self.0.push(6);
+                self.u32(x.0);
+            }
+            Val::RefExtern(x) => {
+                self.0.push(7);
+                self.u64(x as u64);
+            }
+        }
+    }
+
+    fn vals(&mut self, xs: &[Val]) {
+        self.usize(xs.len());
+        xs.iter().for_each(|&x| self.val(x));
+    }
+
+    fn tables(&mut self, xs: &[Table]) {
+        self.usize(xs.len());
+        xs.iter().for_each(|x| self.vals(&x.elems));
+    }
+
+    fn mems(&mut self, xs: &[Memory]) {
+        self.usize(xs.len());
+        for x in xs {
+            let data = &x.data[.. x.len() as usize];
+            self.u32(x.size);
+            self.usize(data.len());
+            self.0.extend_from_slice(data);
+        }
+    }
+
+    fn globals(&mut self, xs: &[Global]) {
+        self.usize(xs.len());
+        xs.iter().for_each(|x| self.val(x.value));
+    }
+
+    fn pos(&mut self, module: &Module, pos: &[u8]) {
+        self.usize(module.offset(pos));
+        self.usize(pos.len());
+    }
+
+    fn thread(&mut self, store: &Store, thread: &Thread) {
+        self.usize(thread.frames.len());
+        for (i, frame) in thread.frames.iter().enumerate() {
+            self.usize(frame.inst_id);
+            self.u32(frame.func);
+            self.usize(frame.arity);
+            // The outermost frame returns to the host.
+            if let Some(i) = i.checked_sub(1) {
+                self.pos(&store.insts[thread.frames[i].inst_id].module, frame.ret);
+            }
+            self.vals(&frame.locals);
+            self.usize(frame.labels.len());
+            frame.labels.iter().for_each(|x| self.vals(&x.values));
+            self.usize(frame.stp);
+            self.usize(frame.stack);
+            #[cfg(feature = "exception-handling")]
+            {
+                self.usize(frame.tries.len());
+                for try_ in &frame.tries {
+                    self.usize(try_.label);
+                    self.pos(&store.insts[frame.inst_id].module, try_.pc);
+                    self.usize(try_.stp);
+                    self.flag(try_.caught.is_some());
+                    if let Some(exception) = &try_.caught {
+                        self.u32(exception.tag.0);
+                        self.vals(&exception.values);
+                    }
+                }
+            }
+        }
+        self.pos(&store.insts[thread.inst_id()].module, thread.parser.save());
+    }
+}
+
+/// Reader of store snapshots.
+struct Restore<'s>(&'s [u8]);
+
+/// Restored state of an instance.
+struct InstanceState<'s> {
+    tables: Vec<Vec<Val>>,
+    mems: Vec<(u32, &'s [u8])>,
+    globals: Vec<Val>,
+    elems: Vec<bool>,
+    datas: Vec<bool>,
+}
+
+impl<'s> Restore<'s> {
+    fn bytes(&mut self, n: usize) -> Option<&'s [u8]> {
+        if self.0.len() < n {
+            return None;
+        }
+        let (result, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Some(result)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+
+    fn usize(&mut self) -> Option<usize> {
+        Some(self.u32()? as usize)
+    }
+
+    fn flag(&mut self) -> Option<bool> {
+        match self.bytes(1)?[0] {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+
+    fn flags(&mut self, n: usize) -> Option<Vec<bool>> {
+        (0 .. n).map(|_| self.flag()).collect()
+    }
+
+    fn val(&mut self) -> Option<Val> {
+        Some(match self.bytes(1)?[0] {
+            0 => Val::I32(self.u32()?),
+            1 => Val::I64(self.u64()?),
+            #[cfg(feature = "float-types")]
+            2 => Val::F32(self.u32()?),
+            #[cfg(feature = "float-types")]
+            3 => Val::F64(self.u64()?),
+            #[cfg(feature = "vector-types")]
+            4 => Val::V128(u128::from_le_bytes(self.bytes(16)?.try_into().unwrap())),
+            5 => Val::Null(RefType::try_from(self.bytes(1)?[0]).ok()?),
+            6 => Val::Ref(Ptr(self.u32()?)),
+            7 => Val::RefExtern(self.u64()?.try_into().ok()?),
+            _ => return None,
+        })
+    }
+
+    fn vals(&mut self) -> Option<Vec<Val>> {
+        (0 .. self.usize()?).map(|_| self.val()).collect()
+    }
+
+    fn instance<'m>(
+        &mut self, tables: &[Table], mems: &[Memory<'m>], globals: &[Global],
+    ) -> Option<InstanceState<'s>> {
+        if self.usize()? != tables.len() {
+            return None;
+        }
+        let tables = tables
+            .iter()
+            .map(|x| self.vals().filter(|elems| elems.len() as u64 <= x.max as u64))
+            .collect::<Option<_>>()?;
+        if self.usize()? != mems.len() {
+            return None;
+        }
+        let mems = mems
+            .iter()
+            .map(|x| {
+                let size = self.u32()?;
+                let len = self.usize()?;
+                let data = self.bytes(len)?;
+                (size <= x.max && data.len() <= x.data.len()).then_some((size, data))
+            })
+            .collect::<Option<_>>()?;
+        if self.usize()? != globals.len() {
+            return None;
+        }
+        let globals = globals.iter().map(|_| self.val()).collect::<Option<_>>()?;
+        Some(InstanceState { tables, mems, globals, elems: Vec::new(), datas: Vec::new() })
+    }
+
+    fn pos<'m>(&mut self, module: &Module<'m>) -> Option<&'m [u8]> {
+        let offset = self.usize()?;
+        let len = self.usize()?;
+        module.position(offset, len)
+    }
+
+    fn thread<'m>(&mut self, store: &Store<'m>) -> Option<Thread<'m>> {
+        let mut frames: Vec<Frame<'m>> = Vec::new();
+        for _ in 0 .. self.usize()? {
+            let inst_id = self.usize()?;
+            let func = self.u32()?;
+            let arity = self.usize()?;
+            let ret = match frames.last() {
+                None => &[],
+                Some(caller) => self.pos(&store.insts.get(caller.inst_id)?.module)?,
+            };
+            let locals = self.vals()?;
+            let labels: Vec<_> = (0 .. self.usize()?)
+                .map(|_| Some(Label { values: self.vals()? }))
+                .collect::<Option<_>>()?;
+            let stp = self.usize()?;
+            let stack = self.usize()?;
+            store.insts.get(inst_id)?;
+            #[cfg(feature = "exception-handling")]
+            let tries = (0 .. self.usize()?)
+                .map(|_| {
+                    let label = self.usize().filter(|&x| x < labels.len())?;
+                    let pc = self.pos(&store.insts[inst_id].module)?;
+                    let stp = self.usize()?;
+                    let caught = match self.flag()? {
+                        false => None,
+                        true => Some(Exception { tag: Ptr(self.u32()?), values: self.vals()? }),
+                    };
+                    Some(TryBlock { label, pc, stp, caught })
+                })
+                .collect::<Option<_>>()?;
+            frames.push(Frame {
+                inst_id,
+                func,
+                arity,
+                ret,
+                locals,
+                labels,
+                stp,
+                stack,
+                #[cfg(feature = "exception-handling")]
+                tries,
+            });
+        }
+        let pos = self.pos(&store.insts[frames.last()?.inst_id].module)?;
+        // SAFETY: The position comes from a thread of a store with the same modules.
+        let parser = unsafe { Parser::new(pos) };
+        Some(Thread::new(parser, frames))
+    }
+}
+
+impl<'s> InstanceState<'s> {
+    fn apply(self, tables: &mut [Table], mems: &mut [Memory], globals: &mut [Global]) {
+        for (table, elems) in tables.iter_mut().zip(self.tables) {
+            table.elems = elems;
+        }
+        for (mem, (size, data)) in mems.iter_mut().zip(self.mems) {
+            mem.size = size;
+            mem.data[.. data.len()].copy_from_slice(data);
+            mem.data[data.len() ..].fill(0);
+        }
+        for (global, value) in globals.iter_mut().zip(self.globals) {
+            global.value = value;
+        }
+    }
+}
+
 fn memory_too_small(x: usize, n: usize, mem: &Memory) {
     #[cfg(not(feature = "debug"))]
     let _ = (x, n, mem);
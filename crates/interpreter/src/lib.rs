@@ -16,11 +16,16 @@
 //!
 //! The main concepts of this crate are:
 //!
+//! - A [`Linker`] contains the host definitions (functions, tables, memories, globals, and tags with
+//! the `exception-handling` feature) that modules may import. It may be shared between stores.
+//!
 //! - A [`Store`] contains instantiated modules and permits execution. Note that execution within
 //! the same store must follow a stack behavior. A function "bar" may be called while a function
 //! "foo" is running: "bar" will temporarily interrupt "foo" until "bar" returns at which point
-//! "foo" would resume. This is to avoid corrupting the stack within the same instance. Once the
-//! WebAssembly threads proposal lands, this restriction may be removed.
+//! "foo" would resume. This is to avoid corrupting the stack within the same instance. The
+//! `threads` feature supports the atomic instructions and shared memories of the WebAssembly
+//! threads proposal, but a store still runs a single thread: waiting on an address never blocks
+//! (it returns "not-equal" or "timed-out") and notifying never wakes anybody.
 //!
 //! - A [`Module`] represents a valid module. Only valid modules may be instantiated. A module is
 //! just a byte slice holding a WebAssembly module in binary format.
@@ -35,20 +40,24 @@
 //! For a concrete "hello" example, see `examples/hello.rs` which walks through most important steps
 //! in using this crate. Otherwise, here are some short excerpts:
 //!
-//! Creating a store can simply use the `Default` trait:
+//! Linking a host function in a linker is done with [`Linker::link_func()`]:
 //!
 //! ```
 //! # use wasefire_interpreter::*;
-//! let mut store = Store::default();
+//! # fn doc(linker: &mut Linker) -> Result<(), Error> {
+//! linker.link_func("env", "add", 2, 1)?;
+//! # Ok(())
+//! # }
 //! ```
 //!
-//! Linking a host function in a store is done with [`Store::link_func()`]:
+//! Creating a store using a linker is done with [`Store::new()`]. When no host definitions are
+//! needed, the `Default` trait can be used instead:
 //!
 //! ```
 //! # use wasefire_interpreter::*;
-//! # fn doc(store: &mut Store) -> Result<(), Error> {
-//! store.link_func("env", "add", 2, 1)?;
-//! # Ok(())
+//! # fn doc<'a>(linker: &'a Linker<'a>) {
+//! let mut store = Store::new(linker);
+//! let mut other = Store::default();
 //! # }
 //! ```
 //!
@@ -83,6 +92,8 @@
 //!     let mut call = match result {
 //!         RunResult::Done(results) => return Ok(results),
 //!         RunResult::Host(call) => call,
+//!         // Execution is not limited with fuel by default.
+//!         RunResult::OutOfFuel => unreachable!(),
 //!     };
 //!     let results = process(&mut call)?;
 //!     result = call.resume(&results)?;
@@ -130,18 +141,25 @@ macro_rules! support_if {
     }};
 }
 
-mod cache;
 mod error;
 mod exec;
 mod id;
 mod module;
 mod parser;
+mod side_table;
 mod syntax;
 mod toctou;
 mod valid;
 
-pub use error::Error;
-pub use exec::{Call, InstId, RunAnswer, RunResult, Store, StoreId, Val, MEMORY_ALIGN};
+pub use error::{Error, TrapReason};
+#[cfg(feature = "profile")]
+pub use exec::ProfileSample;
+pub use exec::{
+    Call, CoreDump, CoreFrame, InstId, Linker, RunAnswer, RunResult, Store, StoreId, Val,
+    DEFAULT_STACK_LIMIT, MEMORY_ALIGN,
+};
+#[cfg(feature = "debugger")]
+pub use exec::{Debugger, Paused};
 pub use module::Module;
-pub use syntax::{GlobalType, ImportDesc, Limits, Mut, RefType, TableType, ValType};
+pub use syntax::{GlobalType, Import, ImportDesc, Limits, Mut, RefType, TableType, ValType};
 pub use valid::validate;
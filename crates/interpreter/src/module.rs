@@ -15,34 +15,20 @@
 use alloc::vec::Vec;
 use core::cmp::Ordering;
 
-use crate::cache::Cache;
+use crate::error::*;
 use crate::parser::{SkipData, SkipElem};
+use crate::side_table::{SideTable, SideTableBuilder, SideTableEntry};
 use crate::syntax::*;
 use crate::toctou::*;
-use crate::valid::validate;
+use crate::valid::prepare;
 use crate::*;
 
 /// Valid module.
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct Module<'m> {
     binary: &'m [u8],
     types: Vec<FuncType<'m>>,
-    cache: Cache<CacheKey, CacheValue>,
-}
-
-impl<'m> Default for Module<'m> {
-    fn default() -> Self {
-        Self { binary: &[], types: Vec::new(), cache: Cache::unbounded() }
-    }
-}
-
-#[derive(Debug, PartialEq, Eq, Hash)]
-enum CacheKey {
-    Skip { ptr: *const u8, depth: LabelIdx },
-}
-
-union CacheValue {
-    skip: usize, // delta
+    side_table: SideTable,
 }
 
 impl<'m> Import<'m> {
@@ -58,6 +44,8 @@ impl ImportDesc {
             ImportDesc::Table(t) => ExternType::Table(t),
             ImportDesc::Mem(t) => ExternType::Mem(t),
             ImportDesc::Global(t) => ExternType::Global(t),
+            #[cfg(feature = "exception-handling")]
+            ImportDesc::Tag(x) => ExternType::Tag(module.types[x as usize]),
         }
     }
 }
@@ -65,18 +53,60 @@ impl ImportDesc {
 impl<'m> Module<'m> {
     /// Validates a WASM module in binary format.
     pub fn new(binary: &'m [u8]) -> Result<Self, Error> {
-        validate(binary)?;
-        Ok(unsafe { Self::new_unchecked(binary) })
+        let side_table = prepare(binary)?;
+        let module = unsafe { Self::from_parts(binary, side_table) };
+        // Both ways of computing the side-table must agree.
+        debug_assert_eq!(module.side_table, module.compute_side_table());
+        Ok(module)
     }
 
     /// Creates a valid module from binary format.
     ///
+    /// The side-table is computed without validation, which only costs one pass over the code.
+    ///
     /// # Safety
     ///
     /// The module must be valid.
     pub unsafe fn new_unchecked(binary: &'m [u8]) -> Self {
+        let mut module = unsafe { Self::from_parts(binary, SideTable::default()) };
+        module.side_table = module.compute_side_table();
+        module
+    }
+
+    /// Creates a valid module from binary format and its serialized side-table.
+    ///
+    /// This skips validation and the computation of the side-table. Returns an error if the
+    /// side-table is malformed or doesn't have one entry per function of the module.
+    ///
+    /// # Safety
+    ///
+    /// The module must be valid and the side-table must come from [`Self::serialize_side_table()`]
+    /// for the same module.
+    pub unsafe fn new_with_side_table(binary: &'m [u8], side_table: &[u8]) -> Result<Self, Error> {
+        let side_table = SideTable::deserialize(side_table).ok_or_else(invalid)?;
+        let module = unsafe { Self::from_parts(binary, side_table) };
+        let num_funcs = match module.section(SectionId::Function) {
+            Some(mut parser) => parser.parse_vec().into_ok(),
+            None => 0,
+        };
+        if module.side_table.num_funcs() != num_funcs {
+            return Err(invalid());
+        }
+        Ok(module)
+    }
+
+    /// Returns the side-table of the module in serialized form.
+    ///
+    /// It may be stored next to the module binary to create the module with
+    /// [`Self::new_with_side_table()`].
+    pub fn serialize_side_table(&self) -> Vec<u8> {
+        self.side_table.serialize()
+    }
+
+    // Safety: The module must be valid and the side-table must be its own (or empty).
+    unsafe fn from_parts(binary: &'m [u8], side_table: SideTable) -> Self {
         // Only keep the sections (i.e. skip the header).
-        let mut module = Module { binary: &binary[8 ..], ..Self::default() };
+        let mut module = Module { binary: &binary[8 ..], types: Vec::new(), side_table };
         if let Some(mut parser) = module.section(SectionId::Type) {
             for _ in 0 .. parser.parse_vec().into_ok() {
                 module.types.push(parser.parse_functype().into_ok());
@@ -85,11 +115,28 @@ impl<'m> Module<'m> {
         module
     }
 
+    /// Returns the byte offset of a position in the module binary.
+    #[cfg(feature = "debugger")]
+    pub(crate) fn binary(&self) -> &'m [u8] {
+        self.binary
+    }
+
+    pub(crate) fn offset(&self, pos: &'m [u8]) -> usize {
+        // The binary does not contain the header (8 bytes).
+        pos.as_ptr() as usize - self.binary.as_ptr() as usize + 8
+    }
+
+    /// Returns the position at a byte offset in the module binary, given its length.
+    pub(crate) fn position(&self, offset: usize, len: usize) -> Option<&'m [u8]> {
+        self.binary.get(offset.checked_sub(8)? ..)?.get(.. len)
+    }
+
     pub(crate) fn types(&self) -> &[FuncType<'m>] {
         &self.types
     }
 
-    pub(crate) fn imports(&self) -> impl Iterator<Item = Import<'m>> {
+    /// Returns the imports of the module.
+    pub fn imports(&self) -> impl Iterator<Item = Import<'m>> {
         let (n, mut parser) = match self.section(SectionId::Import) {
             None => (0, Parser::default()),
             Some(mut parser) => (parser.parse_vec().into_ok(), parser),
@@ -125,6 +172,58 @@ impl<'m> Module<'m> {
         }
     }
 
+    /// Returns the name of a function from the name section, if any.
+    ///
+    /// The index includes imported functions. The name section is ignored if malformed, because
+    /// custom sections are not validated.
+    pub fn func_name(&self, x: FuncIdx) -> Option<&'m str> {
+        let mut parser = <parser::Parser<Check>>::new(self.custom_section("name")?);
+        while !parser.is_empty() {
+            let id = parser.parse_byte().ok()?;
+            let mut section = parser.split_section().ok()?;
+            // Only the function names subsection is used.
+            if id != 1 {
+                continue;
+            }
+            for _ in 0 .. section.parse_vec().ok()? {
+                let index = section.parse_funcidx().ok()?;
+                let name = section.parse_name().ok()?;
+                if index == x {
+                    return Some(name);
+                }
+            }
+            break;
+        }
+        None
+    }
+
+    /// Returns the limits of the memories defined by the module (excluding imported memories).
+    ///
+    /// Limits are in pages of 64kB. This may be used to size the memory given to
+    /// [`Store::instantiate()`].
+    pub fn mem_limits(&self) -> Vec<Limits> {
+        let mut result = Vec::new();
+        if let Some(mut parser) = self.section(SectionId::Memory) {
+            for _ in 0 .. parser.parse_vec().into_ok() {
+                result.push(parser.parse_memtype().into_ok().limits);
+            }
+        }
+        result
+    }
+
+    /// Returns the content of the first custom section with a given name, if any.
+    pub fn custom_section(&self, name: &str) -> Option<&'m [u8]> {
+        let mut parser = unsafe { Parser::new(self.binary) };
+        while !parser.is_empty() {
+            let id = parser.parse_section_id().into_ok();
+            let mut section = parser.split_section().into_ok();
+            if id == SectionId::Custom && section.parse_name().into_ok() == name {
+                return Some(section.save());
+            }
+        }
+        None
+    }
+
     pub(crate) fn func_type(&self, x: FuncIdx) -> FuncType<'m> {
         let mut parser = self.section(SectionId::Function).unwrap();
         for i in 0 .. parser.parse_vec().into_ok() {
@@ -170,8 +269,20 @@ impl<'m> Module<'m> {
         unreachable!()
     }
 
+    #[cfg(feature = "exception-handling")]
+    pub(crate) fn tag_type(&self, x: TagIdx) -> FuncType<'m> {
+        let mut parser = self.section(SectionId::Tag).unwrap();
+        for i in 0 .. parser.parse_vec().into_ok() {
+            let y = parser.parse_tagtype().into_ok();
+            if i == x as usize {
+                return self.types[y as usize];
+            }
+        }
+        unreachable!()
+    }
+
     pub(crate) fn export(&self, expected_name: &str) -> Option<ExportDesc> {
-        let mut parser = self.section(SectionId::Export).unwrap();
+        let mut parser = self.section(SectionId::Export)?;
         for _ in 0 .. parser.parse_vec().into_ok() {
             let actual_name = parser.parse_name().into_ok();
             let desc = parser.parse_exportdesc().into_ok();
@@ -216,27 +327,77 @@ impl<'m> Module<'m> {
         unreachable!()
     }
 
-    pub(crate) fn skip(
-        &mut self, parser: &mut Parser<'m>, depth: LabelIdx,
-        compute: impl Fn(&mut Parser<'m>, LabelIdx),
-    ) {
-        let saved = parser.save();
-        let key = CacheKey::Skip { ptr: saved.as_ptr(), depth };
-        match self.cache.get(&key) {
-            Some(delta) => unsafe { parser.restore(&saved[delta.skip ..]) },
-            None => {
-                compute(parser, depth);
-                let delta = saved.len() - parser.save().len();
-                self.cache.put(key, CacheValue { skip: delta });
-            }
-        }
+    /// Returns the index of the first side-table entry of a function.
+    pub(crate) fn func_stp(&self, x: FuncIdx) -> usize {
+        self.side_table.func(x)
+    }
+
+    pub(crate) fn side_table(&self, stp: usize) -> SideTableEntry {
+        self.side_table.entry(stp)
     }
 
-    pub(crate) fn skip_to_else(&mut self, parser: &mut Parser<'m>) {
-        self.skip(parser, 0, |p, _| p.skip_to_else().into_ok());
+    pub(crate) fn blocktype(&self, b: &BlockType) -> FuncType<'m> {
+        match *b {
+            BlockType::None => FuncType { params: ().into(), results: ().into() },
+            BlockType::Type(t) => FuncType { params: ().into(), results: t.into() },
+            BlockType::Index(x) => self.types[x as usize],
+        }
     }
 
-    pub(crate) fn skip_to_end(&mut self, parser: &mut Parser<'m>, l: LabelIdx) {
-        self.skip(parser, l, |p, l| p.skip_to_end(l).into_ok());
+    fn compute_side_table(&self) -> SideTable {
+        let mut side_table = SideTableBuilder::default();
+        let mut parser = match self.section(SectionId::Code) {
+            Some(x) => x,
+            None => return side_table.finish(),
+        };
+        for x in 0 .. parser.parse_vec().into_ok() {
+            let size = parser.parse_u32().into_ok() as usize;
+            let mut parser = parser.split_at(size).into_ok();
+            parser.parse_locals(&mut Vec::new()).into_ok();
+            side_table.func(self.func_type(x as FuncIdx).results.len());
+            let mut depth = 1;
+            while depth > 0 {
+                let saved = parser.save();
+                match parser.parse_instr().into_ok() {
+                    Instr::Block(b) => {
+                        side_table.push_block(self.blocktype(&b).results.len());
+                        depth += 1;
+                    }
+                    Instr::Loop(b) => {
+                        side_table.push_loop(self.blocktype(&b).params.len(), saved);
+                        depth += 1;
+                    }
+                    Instr::If(b) => {
+                        side_table.push_if(self.blocktype(&b).results.len(), saved);
+                        depth += 1;
+                    }
+                    Instr::Else => side_table.else_(saved, parser.save()),
+                    #[cfg(feature = "exception-handling")]
+                    Instr::Try(b) => {
+                        side_table.push_try(self.blocktype(&b).results.len(), saved);
+                        depth += 1;
+                    }
+                    #[cfg(feature = "exception-handling")]
+                    Instr::Catch(_) => side_table.catch(saved, false),
+                    #[cfg(feature = "exception-handling")]
+                    Instr::CatchAll => side_table.catch(saved, true),
+                    #[cfg(feature = "exception-handling")]
+                    Instr::Delegate(_) => {
+                        side_table.end(saved, parser.save());
+                        depth -= 1;
+                    }
+                    Instr::End => {
+                        side_table.end(saved, parser.save());
+                        depth -= 1;
+                    }
+                    Instr::Br(l) | Instr::BrIf(l) => side_table.branch(saved, l),
+                    Instr::BrTable(ls, ln) => {
+                        ls.iter().chain([&ln]).for_each(|&l| side_table.branch(saved, l))
+                    }
+                    _ => (),
+                }
+            }
+        }
+        side_table.finish()
     }
 }
@@ -132,6 +132,16 @@ impl<'m, M: Mode> Parser<'m, M> {
         self.parse_u32()
     }
 
+    /// Parses the memory index of a memory instruction.
+    ///
+    /// Only the first memory may be used without the `multi-memory` feature.
+    fn parse_instr_memidx(&mut self) -> MResult<MemIdx, M> {
+        support_if!("multi-memory"[], self.parse_memidx(), {
+            check_eq::<M, _>(self.parse_byte()?, 0)?;
+            Ok(0)
+        })
+    }
+
     pub fn parse_globalidx(&mut self) -> MResult<TypeIdx, M> {
         self.parse_u32()
     }
@@ -144,6 +154,11 @@ impl<'m, M: Mode> Parser<'m, M> {
         self.parse_u32()
     }
 
+    #[cfg(feature = "exception-handling")]
+    pub fn parse_tagidx(&mut self) -> MResult<TagIdx, M> {
+        self.parse_u32()
+    }
+
     pub fn parse_localidx(&mut self) -> MResult<TypeIdx, M> {
         self.parse_u32()
     }
@@ -199,8 +214,12 @@ impl<'m, M: Mode> Parser<'m, M> {
         byte_enum::<M, _>(self.parse_byte()?)
     }
 
-    pub fn parse_limits(&mut self, mut max: u32) -> MResult<Limits, M> {
+    pub fn parse_limits(&mut self, max: u32) -> MResult<Limits, M> {
         let has_max = byte_enum::<M, bool>(self.parse_byte()?)?;
+        self.parse_limits_(has_max, max)
+    }
+
+    fn parse_limits_(&mut self, has_max: bool, mut max: u32) -> MResult<Limits, M> {
         let min = self.parse_u32()?;
         if has_max {
             max = self.parse_u32()?;
@@ -215,7 +234,16 @@ impl<'m, M: Mode> Parser<'m, M> {
     }
 
     pub fn parse_memtype(&mut self) -> MResult<MemType, M> {
-        self.parse_limits(MEM_MAX)
+        let (has_max, shared) = match self.parse_byte()? {
+            0 => (false, false),
+            1 => (true, false),
+            // Shared memories must have a maximum.
+            #[cfg(feature = "threads")]
+            3 => (true, true),
+            _ => M::invalid()?,
+        };
+        let limits = self.parse_limits_(has_max, MEM_MAX)?;
+        Ok(MemType { limits, shared })
     }
 
     pub fn parse_mut(&mut self) -> MResult<Mut, M> {
@@ -228,12 +256,22 @@ impl<'m, M: Mode> Parser<'m, M> {
         Ok(GlobalType { mutable, value })
     }
 
+    /// Parses a tag type and returns the index of its function type.
+    #[cfg(feature = "exception-handling")]
+    pub fn parse_tagtype(&mut self) -> MResult<TypeIdx, M> {
+        // The only attribute is exception.
+        check_eq::<M, _>(self.parse_byte()?, 0)?;
+        self.parse_typeidx()
+    }
+
     pub fn parse_importdesc(&mut self) -> MResult<ImportDesc, M> {
         Ok(match self.parse_byte()? {
             0 => ImportDesc::Func(self.parse_typeidx()?),
             1 => ImportDesc::Table(self.parse_tabletype()?),
             2 => ImportDesc::Mem(self.parse_memtype()?),
             3 => ImportDesc::Global(self.parse_globaltype()?),
+            #[cfg(feature = "exception-handling")]
+            4 => ImportDesc::Tag(self.parse_tagtype()?),
             _ => M::invalid()?,
         })
     }
@@ -244,14 +282,22 @@ impl<'m, M: Mode> Parser<'m, M> {
             1 => ExportDesc::Table(self.parse_tableidx()?),
             2 => ExportDesc::Mem(self.parse_memidx()?),
             3 => ExportDesc::Global(self.parse_globalidx()?),
+            #[cfg(feature = "exception-handling")]
+            4 => ExportDesc::Tag(self.parse_tagidx()?),
             _ => M::invalid()?,
         })
     }
 
     pub fn parse_memarg(&mut self) -> MResult<MemArg, M> {
         let align = self.parse_u32()?;
+        // The memory index is present if the bit 6 of the alignment is set.
+        let (align, memory) = match align & 0x40 {
+            #[cfg(feature = "multi-memory")]
+            0x40 => (align & !0x40, self.parse_memidx()?),
+            _ => (align, 0),
+        };
         let offset = self.parse_u32()?;
-        Ok(MemArg { align, offset })
+        Ok(MemArg { align, offset, memory })
     }
 
     pub fn parse_blocktype(&mut self) -> MResult<BlockType, M> {
@@ -278,6 +324,26 @@ impl<'m, M: Mode> Parser<'m, M> {
             0x03 => Instr::Loop(self.parse_blocktype()?),
             0x04 => Instr::If(self.parse_blocktype()?),
             0x05 => Instr::Else,
+            0x06 => support_if!(
+                "exception-handling"[],
+                Instr::Try(self.parse_blocktype()?),
+                M::unsupported()?
+            ),
+            0x07 => support_if!(
+                "exception-handling"[],
+                Instr::Catch(self.parse_tagidx()?),
+                M::unsupported()?
+            ),
+            0x08 => support_if!(
+                "exception-handling"[],
+                Instr::Throw(self.parse_tagidx()?),
+                M::unsupported()?
+            ),
+            0x09 => support_if!(
+                "exception-handling"[],
+                Instr::Rethrow(self.parse_labelidx()?),
+                M::unsupported()?
+            ),
             0x0b => Instr::End,
             0x0c => Instr::Br(self.parse_labelidx()?),
             0x0d => Instr::BrIf(self.parse_labelidx()?),
@@ -290,6 +356,26 @@ impl<'m, M: Mode> Parser<'m, M> {
                 let x = self.parse_tableidx()?;
                 Instr::CallIndirect(x, y)
             }
+            0x12 => support_if!(
+                "tail-call"[],
+                Instr::ReturnCall(self.parse_funcidx()?),
+                M::unsupported()?
+            ),
+            0x13 => support_if!(
+                "tail-call"[],
+                {
+                    let y = self.parse_typeidx()?;
+                    let x = self.parse_tableidx()?;
+                    Instr::ReturnCallIndirect(x, y)
+                },
+                M::unsupported()?
+            ),
+            0x18 => support_if!(
+                "exception-handling"[],
+                Instr::Delegate(self.parse_labelidx()?),
+                M::unsupported()?
+            ),
+            0x19 => support_if!("exception-handling"[], Instr::CatchAll, M::unsupported()?),
             0x1a => Instr::Drop,
             0x1b => Instr::Select(None),
             0x1c => Instr::Select(Some(self.parse_resulttype()?)),
@@ -318,14 +404,8 @@ impl<'m, M: Mode> Parser<'m, M> {
                 M::unsupported()?
             ),
             x @ 0x3a ..= 0x3e => Instr::IStore_((x - 0x3a).into(), self.parse_memarg()?),
-            0x3f => {
-                check_eq::<M, _>(self.parse_byte()?, 0)?;
-                Instr::MemorySize
-            }
-            0x40 => {
-                check_eq::<M, _>(self.parse_byte()?, 0)?;
-                Instr::MemoryGrow
-            }
+            0x3f => Instr::MemorySize(self.parse_instr_memidx()?),
+            0x40 => Instr::MemoryGrow(self.parse_instr_memidx()?),
             0x41 => Instr::I32Const(self.parse_i32()?),
             0x42 => Instr::I64Const(self.parse_i64()?),
             0x43 => support_if!(
@@ -427,19 +507,14 @@ impl<'m, M: Mode> Parser<'m, M> {
                     M::unsupported()?
                 ),
                 8 => {
-                    let x = self.parse_dataidx()?;
-                    check_eq::<M, _>(self.parse_byte()?, 0)?;
-                    Instr::MemoryInit(x)
+                    // For some reason, parsing order differs from field order here.
+                    let y = self.parse_dataidx()?;
+                    let x = self.parse_instr_memidx()?;
+                    Instr::MemoryInit(x, y)
                 }
                 9 => Instr::DataDrop(self.parse_dataidx()?),
-                10 => {
-                    check_eq::<M, _>(self.parse_bytes(2)?, &[0; 2])?;
-                    Instr::MemoryCopy
-                }
-                11 => {
-                    check_eq::<M, _>(self.parse_byte()?, 0)?;
-                    Instr::MemoryFill
-                }
+                10 => Instr::MemoryCopy(self.parse_instr_memidx()?, self.parse_instr_memidx()?),
+                11 => Instr::MemoryFill(self.parse_instr_memidx()?),
                 12 => {
                     // For some reason, parsing order differs from field order here.
                     let y = self.parse_elemidx()?;
@@ -453,7 +528,184 @@ impl<'m, M: Mode> Parser<'m, M> {
                 17 => Instr::TableFill(self.parse_tableidx()?),
                 _ => M::invalid()?,
             },
-            0xfd => support_if!("vector-types"[], unimplemented!(), M::unsupported()?),
+            0xfd => support_if!("vector-types"[], self.parse_vector_instr()?, M::unsupported()?),
+            0xfe => support_if!("threads"[], self.parse_atomic_instr()?, M::unsupported()?),
+            _ => M::invalid()?,
+        })
+    }
+
+    #[cfg(feature = "threads")]
+    fn parse_atomic_instr(&mut self) -> MResult<Instr<'m>, M> {
+        // Integer atomic instructions come in groups of 7 for the full widths (i32 and i64) then
+        // the narrow widths (i32 8 and 16 bits, i64 8, 16, and 32 bits).
+        Ok(match self.parse_u32()? {
+            0x00 => Instr::AtomicNotify(self.parse_memarg()?),
+            x @ 0x01 ..= 0x02 => Instr::AtomicWait((x as u8 - 1).into(), self.parse_memarg()?),
+            0x03 => {
+                check_eq::<M, _>(self.parse_byte()?, 0)?;
+                Instr::AtomicFence
+            }
+            x @ 0x10 ..= 0x4e => {
+                let y = x - 0x10;
+                let m = self.parse_memarg()?;
+                let (n, b) = match (y % 7) as u8 {
+                    y @ 0 ..= 1 => (Some(Nx::from(y)), None),
+                    y => (None, Some(Bx::from(y - 2))),
+                };
+                match (y / 7, n, b) {
+                    (0, Some(n), _) => Instr::IAtomicLoad(n, m),
+                    (0, _, Some(b)) => Instr::IAtomicLoad_(b, m),
+                    (1, Some(n), _) => Instr::IAtomicStore(n, m),
+                    (1, _, Some(b)) => Instr::IAtomicStore_(b, m),
+                    (8, Some(n), _) => Instr::IAtomicCmpxchg(n, m),
+                    (8, _, Some(b)) => Instr::IAtomicCmpxchg_(b, m),
+                    (op, Some(n), _) => Instr::IAtomicRmw(n, (op as u8 - 2).into(), m),
+                    (op, _, Some(b)) => Instr::IAtomicRmw_(b, (op as u8 - 2).into(), m),
+                    _ => unreachable!(),
+                }
+            }
+            _ => M::invalid()?,
+        })
+    }
+
+    #[cfg(feature = "vector-types")]
+    fn parse_vector_instr(&mut self) -> MResult<Instr<'m>, M> {
+        use Lx::*;
+        Ok(match self.parse_u32()? {
+            0 => Instr::V128Load(self.parse_memarg()?),
+            x @ 1 ..= 6 => {
+                let x = (x - 1) as u8;
+                Instr::V128LoadExtend((x / 2 + 1).into(), (x % 2).into(), self.parse_memarg()?)
+            }
+            x @ 7 ..= 10 => Instr::V128LoadSplat(((x - 7) as u8).into(), self.parse_memarg()?),
+            11 => Instr::V128Store(self.parse_memarg()?),
+            12 => Instr::V128Const(u128::from_le_bytes(self.parse_bytes(16)?.try_into().unwrap())),
+            13 => {
+                let lanes: [LaneIdx; 16] = self.parse_bytes(16)?.try_into().unwrap();
+                Instr::I8x16Shuffle(lanes)
+            }
+            14 => Instr::VBinOp(VBinOp::Swizzle),
+            x @ 15 ..= 20 => Instr::Splat(((x - 15) as u8).into()),
+            x @ 21 ..= 26 => {
+                let x = (x - 21) as u8;
+                let s = Shape::I((x / 3).into());
+                match x % 3 {
+                    2 => Instr::ReplaceLane(s, self.parse_byte()?),
+                    y => Instr::ExtractLane(s, Some(y.into()), self.parse_byte()?),
+                }
+            }
+            x @ 27 ..= 34 => {
+                let x = (x - 27) as u8;
+                let s = Shape::from(x / 2 + 2);
+                match x % 2 {
+                    0 => Instr::ExtractLane(s, None, self.parse_byte()?),
+                    _ => Instr::ReplaceLane(s, self.parse_byte()?),
+                }
+            }
+            x @ 35 ..= 64 => {
+                let x = (x - 35) as u8;
+                Instr::VBinOp(VBinOp::IRelOp((x / 10).into(), (x % 10).into()))
+            }
+            x @ 65 ..= 76 => {
+                let x = (x - 65) as u8;
+                Instr::VBinOp(VBinOp::FRelOp((x / 6).into(), (x % 6).into()))
+            }
+            77 => Instr::VUnOp(VUnOp::Not),
+            78 => Instr::VBinOp(VBinOp::And),
+            79 => Instr::VBinOp(VBinOp::AndNot),
+            80 => Instr::VBinOp(VBinOp::Or),
+            81 => Instr::VBinOp(VBinOp::Xor),
+            82 => Instr::V128Bitselect,
+            83 => Instr::VTestOp(VTestOp::AnyTrue),
+            x @ 84 ..= 87 => {
+                let m = self.parse_memarg()?;
+                Instr::V128LoadLane(((x - 84) as u8).into(), m, self.parse_byte()?)
+            }
+            x @ 88 ..= 91 => {
+                let m = self.parse_memarg()?;
+                Instr::V128StoreLane(((x - 88) as u8).into(), m, self.parse_byte()?)
+            }
+            x @ 92 ..= 93 => Instr::V128LoadZero(((x - 92) as u8).into(), self.parse_memarg()?),
+            94 => Instr::VUnOp(VUnOp::Demote),
+            95 => Instr::VUnOp(VUnOp::Promote),
+            x @ 96 ..= 223 => {
+                // Integer instructions are grouped by shape, with some float instructions in the
+                // holes.
+                let l = Lx::from(((x - 96) / 32) as u8);
+                let fround = |n, y| Instr::VUnOp(VUnOp::FUnOp(n, FUnOp::from(y + 2)));
+                match (((x - 96) % 32) as u8, l) {
+                    (0, _) => Instr::VUnOp(VUnOp::IAbs(l)),
+                    (1, _) => Instr::VUnOp(VUnOp::INeg(l)),
+                    (2, L8) => Instr::VUnOp(VUnOp::IPopCnt),
+                    (2, L16) => Instr::VBinOp(VBinOp::Q15MulrSatS),
+                    (3, _) => Instr::VTestOp(VTestOp::AllTrue(l)),
+                    (4, _) => Instr::VBitmask(l),
+                    (y @ 5 ..= 6, L8 | L16) => Instr::VBinOp(VBinOp::Narrow(l, (y - 5).into())),
+                    (y @ 7 ..= 10, L8) => fround(Nx::N32, y - 7),
+                    (y @ 7 ..= 10, _) => {
+                        let y = y - 7;
+                        Instr::VUnOp(VUnOp::Extend(l, (y % 2).into(), (y / 2).into()))
+                    }
+                    (11, _) => Instr::VShiftOp(l, VShiftOp::Shl),
+                    (y @ 12 ..= 13, _) => Instr::VShiftOp(l, VShiftOp::Shr((y - 12).into())),
+                    (14, _) => Instr::VBinOp(VBinOp::IAdd(l)),
+                    (y @ 15 ..= 16, L8 | L16) => Instr::VBinOp(VBinOp::IAddSat(l, (y - 15).into())),
+                    (17, _) => Instr::VBinOp(VBinOp::ISub(l)),
+                    (y @ 18 ..= 19, L8 | L16) => Instr::VBinOp(VBinOp::ISubSat(l, (y - 18).into())),
+                    (20, L8) => fround(Nx::N64, 0),
+                    (21, L8) => fround(Nx::N64, 1),
+                    (20, L16) => fround(Nx::N64, 3),
+                    (21, _) => Instr::VBinOp(VBinOp::IMul(l)),
+                    (y @ 22 ..= 23, L8 | L16 | L32) => {
+                        Instr::VBinOp(VBinOp::IMin(l, (y - 22).into()))
+                    }
+                    (y @ 24 ..= 25, L8 | L16 | L32) => {
+                        Instr::VBinOp(VBinOp::IMax(l, (y - 24).into()))
+                    }
+                    (y @ 22 ..= 27, L64) => {
+                        let op = match y - 22 {
+                            0 => IRelOp::Eq,
+                            1 => IRelOp::Ne,
+                            2 => IRelOp::Lt(Sx::S),
+                            3 => IRelOp::Gt(Sx::S),
+                            4 => IRelOp::Le(Sx::S),
+                            _ => IRelOp::Ge(Sx::S),
+                        };
+                        Instr::VBinOp(VBinOp::IRelOp(l, op))
+                    }
+                    (26, L8) => fround(Nx::N64, 2),
+                    (26, L32) => Instr::VBinOp(VBinOp::Dot),
+                    (27, L8 | L16) => Instr::VBinOp(VBinOp::IAvgrU(l)),
+                    (y @ 28 ..= 31, L8) => {
+                        let y = y - 28;
+                        Instr::VUnOp(VUnOp::ExtAddPairwise((y / 2 + 1).into(), (y % 2).into()))
+                    }
+                    (y @ 28 ..= 31, _) => {
+                        let y = y - 28;
+                        Instr::VBinOp(VBinOp::ExtMul(l, (y % 2).into(), (y / 2).into()))
+                    }
+                    _ => M::invalid()?,
+                }
+            }
+            x @ 224 ..= 247 => {
+                let n = Nx::from(((x - 224) / 12) as u8);
+                match ((x - 224) % 12) as u8 {
+                    y @ 0 ..= 1 => Instr::VUnOp(VUnOp::FUnOp(n, y.into())),
+                    2 => M::invalid()?,
+                    3 => Instr::VUnOp(VUnOp::FUnOp(n, FUnOp::Sqrt)),
+                    y @ 4 ..= 9 => Instr::VBinOp(VBinOp::FBinOp(n, (y - 4).into())),
+                    10 => Instr::VBinOp(VBinOp::FPMin(n)),
+                    _ => Instr::VBinOp(VBinOp::FPMax(n)),
+                }
+            }
+            x @ 248 ..= 255 => {
+                let x = (x - 248) as u8;
+                let (n, s) = (Nx::from(x / 4), Sx::from(x % 2));
+                match x % 4 {
+                    0 ..= 1 => Instr::VUnOp(VUnOp::TruncSat(n, s)),
+                    _ => Instr::VUnOp(VUnOp::Convert(n, s)),
+                }
+            }
             _ => M::invalid()?,
         })
     }
@@ -540,25 +792,6 @@ impl<'m, M: Mode> Parser<'m, M> {
         user.init(self.parse_bytes(len)?)
     }
 
-    pub fn skip_to_else(&mut self) -> MResult<(), M> {
-        let mut depth = 0;
-        loop {
-            let saved = self.save();
-            match self.parse_instr()? {
-                Instr::Block(_) => depth += 1,
-                Instr::Loop(_) => depth += 1,
-                Instr::If(_) => depth += 1,
-                Instr::End if depth == 0 => {
-                    unsafe { self.restore(saved) };
-                    return Ok(());
-                }
-                Instr::End => depth -= 1,
-                Instr::Else if depth == 0 => return Ok(()),
-                _ => (),
-            }
-        }
-    }
-
     pub fn skip_to_end(&mut self, l: LabelIdx) -> MResult<(), M> {
         let mut depth = l as usize + 1;
         while depth > 0 {
@@ -566,7 +799,11 @@ impl<'m, M: Mode> Parser<'m, M> {
                 Instr::Block(_) => depth += 1,
                 Instr::Loop(_) => depth += 1,
                 Instr::If(_) => depth += 1,
+                #[cfg(feature = "exception-handling")]
+                Instr::Try(_) => depth += 1,
                 Instr::End => depth -= 1,
+                #[cfg(feature = "exception-handling")]
+                Instr::Delegate(_) => depth -= 1,
                 _ => (),
             }
         }
@@ -642,6 +879,10 @@ impl<'m, M: Mode> Parser<'m, M> {
 // NOTE: This should be configurable.
 const MAX_LOCALS: usize = 100;
 
+#[cfg_attr(
+    all(feature = "multi-memory", not(feature = "threads"), not(feature = "exception-handling")),
+    allow(dead_code)
+)]
 fn check_eq<M: Mode, T: Eq>(x: T, y: T) -> MResult<(), M> {
     M::check(|| x == y)
 }
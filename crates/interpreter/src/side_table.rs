@@ -0,0 +1,274 @@
+// Copyright 2023 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Branch side-table.
+//!
+//! Each branching instruction of a function body has one entry per possible target, in the order
+//! of the instructions in the body:
+//!
+//! - `if` has one entry for the false condition. It targets after the `else` (if any) or the `end`.
+//! - `else` has one entry for the end of the true branch. It targets after the `end`.
+//! - `br` and `br_if` have one entry. It targets the label.
+//! - `br_table` has one entry per label (including the default label). They target their label.
+//! - `try` has one entry for its first clause. It targets the first `catch`, `catch_all`,
+//!   `delegate`, or `end`.
+//! - `catch` has one entry for the end of the previous body (like `else`) and one entry for the
+//!   next clause (like `try`).
+//! - `catch_all` has one entry for the end of the previous body (like `else`).
+//!
+//! Clause entries target the clause instruction itself (before its entries) because they are only
+//! used to look for a handler when an exception is thrown. A `delegate` ends its block like `end`.
+//!
+//! Branches to a block or `if` target after its `end`. Branches to a loop target the `loop`
+//! instruction itself. Branches to the function body are not used but still have an entry. An
+//! instruction which doesn't branch skips its entries.
+
+use alloc::vec::Vec;
+
+use crate::syntax::LabelIdx;
+
+/// Side-table entry of a branch.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct SideTableEntry {
+    /// Offset in bytes from the branching instruction to its target.
+    pub delta_ip: i32,
+
+    /// Offset in entries from this entry to the first entry after the target.
+    pub delta_stp: i32,
+
+    /// Number of values kept on the stack when branching.
+    pub val_count: u32,
+}
+
+/// Side-table of a module.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SideTable {
+    entries: Vec<SideTableEntry>,
+    // Index of the first entry of each function (excluding imported functions).
+    funcs: Vec<u32>,
+}
+
+impl SideTable {
+    /// Returns the index of the first entry of a function (excluding imported functions).
+    pub fn func(&self, x: u32) -> usize {
+        self.funcs[x as usize] as usize
+    }
+
+    /// Returns the number of functions (excluding imported functions).
+    pub fn num_funcs(&self) -> usize {
+        self.funcs.len()
+    }
+
+    pub fn entry(&self, stp: usize) -> SideTableEntry {
+        self.entries[stp]
+    }
+
+    /// Serializes the side-table.
+    ///
+    /// The format is the number of functions followed by the index of their first entry, then the
+    /// entries. All integers are little-endian 32-bits.
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut result = Vec::with_capacity(4 * (1 + self.funcs.len() + 3 * self.entries.len()));
+        result.extend_from_slice(&(self.funcs.len() as u32).to_le_bytes());
+        for x in &self.funcs {
+            result.extend_from_slice(&x.to_le_bytes());
+        }
+        for x in &self.entries {
+            result.extend_from_slice(&x.delta_ip.to_le_bytes());
+            result.extend_from_slice(&x.delta_stp.to_le_bytes());
+            result.extend_from_slice(&x.val_count.to_le_bytes());
+        }
+        result
+    }
+
+    /// Deserializes a side-table.
+    ///
+    /// Returns `None` if the side-table is malformed. A well-formed side-table may still not
+    /// describe the module it is used with.
+    pub fn deserialize(data: &[u8]) -> Option<Self> {
+        let mut words = data.chunks(4).map(|x| Some(u32::from_le_bytes(x.try_into().ok()?)));
+        let num_funcs = words.next()?? as usize;
+        let funcs = (&mut words).take(num_funcs).collect::<Option<Vec<_>>>()?;
+        let words = words.collect::<Option<Vec<_>>>()?;
+        if funcs.len() != num_funcs || words.len() % 3 != 0 {
+            return None;
+        }
+        let entries: Vec<_> = words
+            .chunks(3)
+            .map(|x| SideTableEntry {
+                delta_ip: x[0] as i32,
+                delta_stp: x[1] as i32,
+                val_count: x[2],
+            })
+            .collect();
+        // Functions start at increasing entries, since their entries are consecutive.
+        if funcs.windows(2).any(|x| x[1] < x[0]) {
+            return None;
+        }
+        if funcs.last().is_some_and(|&x| entries.len() < x as usize) {
+            return None;
+        }
+        Some(SideTable { entries, funcs })
+    }
+}
+
+/// Builds the side-table of a module, one function body at a time.
+#[derive(Debug, Default)]
+pub struct SideTableBuilder {
+    table: SideTable,
+    labels: Vec<Label>,
+}
+
+#[derive(Debug)]
+struct Label {
+    arity: usize,
+    kind: LabelKind,
+}
+
+#[derive(Debug)]
+enum LabelKind {
+    Block {
+        // Entry of the false condition of an `if` without `else` yet, or entry of the next clause
+        // of a `try`.
+        next: Option<Branch>,
+        // Entries of the branches to this label.
+        branches: Vec<Branch>,
+    },
+    Loop {
+        ip: usize,
+        stp: usize,
+    },
+}
+
+#[derive(Debug)]
+struct Branch {
+    stp: usize,
+    ip: usize,
+}
+
+impl SideTableBuilder {
+    /// Starts a function body whose results have the given arity.
+    pub fn func(&mut self, arity: usize) {
+        debug_assert!(self.labels.is_empty());
+        self.table.funcs.push(self.stp() as u32);
+        self.push_block(arity);
+    }
+
+    pub fn push_block(&mut self, arity: usize) {
+        let kind = LabelKind::Block { next: None, branches: Vec::new() };
+        self.labels.push(Label { arity, kind });
+    }
+
+    /// Starts a loop at its instruction.
+    pub fn push_loop(&mut self, arity: usize, instr: &[u8]) {
+        let kind = LabelKind::Loop { ip: ip(instr), stp: self.stp() };
+        self.labels.push(Label { arity, kind });
+    }
+
+    /// Starts an `if` at its instruction.
+    pub fn push_if(&mut self, arity: usize, instr: &[u8]) {
+        let next = Some(self.push_entry(instr));
+        let kind = LabelKind::Block { next, branches: Vec::new() };
+        self.labels.push(Label { arity, kind });
+    }
+
+    /// Starts a `try` at its instruction.
+    #[cfg(feature = "exception-handling")]
+    pub fn push_try(&mut self, arity: usize, instr: &[u8]) {
+        self.push_if(arity, instr);
+    }
+
+    /// Processes an `else` at its instruction and provided what follows it.
+    pub fn else_(&mut self, instr: &[u8], next: &[u8]) {
+        let branch = self.push_entry(instr);
+        let stp = self.stp();
+        match &mut self.labels.last_mut().unwrap().kind {
+            LabelKind::Block { next: if_, branches } => {
+                let if_ = if_.take().unwrap();
+                branches.push(branch);
+                self.patch(if_, ip(next), stp, 0);
+            }
+            LabelKind::Loop { .. } => unreachable!(),
+        }
+    }
+
+    /// Processes a `catch` or `catch_all` (if `all`) at its instruction.
+    #[cfg(feature = "exception-handling")]
+    pub fn catch(&mut self, instr: &[u8], all: bool) {
+        let stp = self.stp();
+        let branch = self.push_entry(instr);
+        let clause = (!all).then(|| self.push_entry(instr));
+        match &mut self.labels.last_mut().unwrap().kind {
+            LabelKind::Block { next, branches } => {
+                let prev = core::mem::replace(next, clause).unwrap();
+                branches.push(branch);
+                self.patch(prev, ip(instr), stp, 0);
+            }
+            LabelKind::Loop { .. } => unreachable!(),
+        }
+    }
+
+    /// Processes an `end` at its instruction and provided what follows it.
+    pub fn end(&mut self, instr: &[u8], next: &[u8]) {
+        let label = self.labels.pop().unwrap();
+        let stp = self.stp();
+        if let LabelKind::Block { next: entry, branches } = label.kind {
+            if let Some(entry) = entry {
+                self.patch(entry, ip(instr), stp, 0);
+            }
+            for branch in branches {
+                self.patch(branch, ip(next), stp, label.arity);
+            }
+        }
+    }
+
+    /// Processes a branch to a label at its instruction.
+    pub fn branch(&mut self, instr: &[u8], l: LabelIdx) {
+        let branch = self.push_entry(instr);
+        let n = self.labels.len();
+        let label = &mut self.labels[n - l as usize - 1];
+        let arity = label.arity;
+        match &mut label.kind {
+            LabelKind::Block { branches, .. } => branches.push(branch),
+            &mut LabelKind::Loop { ip, stp } => self.patch(branch, ip, stp, arity),
+        }
+    }
+
+    pub fn finish(self) -> SideTable {
+        debug_assert!(self.labels.is_empty());
+        self.table
+    }
+
+    fn stp(&self) -> usize {
+        self.table.entries.len()
+    }
+
+    fn push_entry(&mut self, instr: &[u8]) -> Branch {
+        let branch = Branch { stp: self.stp(), ip: ip(instr) };
+        self.table.entries.push(SideTableEntry::default());
+        branch
+    }
+
+    fn patch(&mut self, branch: Branch, ip: usize, stp: usize, val_count: usize) {
+        self.table.entries[branch.stp] = SideTableEntry {
+            delta_ip: (ip as isize - branch.ip as isize) as i32,
+            delta_stp: (stp as isize - branch.stp as isize) as i32,
+            val_count: val_count as u32,
+        };
+    }
+}
+
+fn ip(pos: &[u8]) -> usize {
+    pos.as_ptr() as usize
+}
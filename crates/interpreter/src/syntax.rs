@@ -79,7 +79,21 @@ impl Limits {
     }
 }
 
-pub type MemType = Limits;
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MemType {
+    pub limits: Limits,
+    pub shared: bool,
+}
+
+impl MemType {
+    pub fn valid(&self) -> bool {
+        self.limits.valid(MEM_MAX)
+    }
+
+    pub fn matches(self, other: MemType) -> bool {
+        self.limits.matches(other.limits) && self.shared == other.shared
+    }
+}
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct TableType {
@@ -194,6 +208,17 @@ pub enum FBinOp {
     CopySign,
 }
 
+#[cfg(feature = "threads")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RmwOp {
+    Add,
+    Sub,
+    And,
+    Or,
+    Xor,
+    Xchg,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum CvtOp {
     Wrap,
@@ -214,6 +239,88 @@ pub enum CvtOp {
     FReinterpret(Nx),
 }
 
+/// Lane width of integer vector shapes.
+#[cfg(feature = "vector-types")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Lx {
+    L8,
+    L16,
+    L32,
+    L64,
+}
+
+#[cfg(feature = "vector-types")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Shape {
+    I(Lx),
+    F(Nx),
+}
+
+#[cfg(feature = "vector-types")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Half {
+    Low,
+    High,
+}
+
+// The lane width of conversions (extend, narrow, etc) is the one of the result.
+#[cfg(feature = "vector-types")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VUnOp {
+    Not,
+    IAbs(Lx),
+    INeg(Lx),
+    IPopCnt,
+    FUnOp(Nx, FUnOp),
+    Extend(Lx, Half, Sx),
+    ExtAddPairwise(Lx, Sx),
+    TruncSat(Nx, Sx), // the float width of the source
+    Convert(Nx, Sx),  // the float width of the result
+    Demote,
+    Promote,
+}
+
+#[cfg(feature = "vector-types")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VBinOp {
+    And,
+    AndNot,
+    Or,
+    Xor,
+    IRelOp(Lx, IRelOp),
+    FRelOp(Nx, FRelOp),
+    IAdd(Lx),
+    ISub(Lx),
+    IMul(Lx),
+    IAddSat(Lx, Sx),
+    ISubSat(Lx, Sx),
+    IMin(Lx, Sx),
+    IMax(Lx, Sx),
+    IAvgrU(Lx),
+    Q15MulrSatS,
+    Dot,
+    ExtMul(Lx, Half, Sx),
+    Narrow(Lx, Sx),
+    Swizzle,
+    FBinOp(Nx, FBinOp),
+    FPMin(Nx),
+    FPMax(Nx),
+}
+
+#[cfg(feature = "vector-types")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VTestOp {
+    AnyTrue,
+    AllTrue(Lx),
+}
+
+#[cfg(feature = "vector-types")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VShiftOp {
+    Shl,
+    Shr(Sx),
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Instr<'m> {
     Unreachable,
@@ -222,6 +329,18 @@ pub enum Instr<'m> {
     Loop(BlockType),
     If(BlockType),
     Else,
+    #[cfg(feature = "exception-handling")]
+    Try(BlockType),
+    #[cfg(feature = "exception-handling")]
+    Catch(TagIdx),
+    #[cfg(feature = "exception-handling")]
+    CatchAll,
+    #[cfg(feature = "exception-handling")]
+    Delegate(LabelIdx),
+    #[cfg(feature = "exception-handling")]
+    Throw(TagIdx),
+    #[cfg(feature = "exception-handling")]
+    Rethrow(LabelIdx),
     End,
     Br(LabelIdx),
     BrIf(LabelIdx),
@@ -229,6 +348,10 @@ pub enum Instr<'m> {
     Return,
     Call(FuncIdx),
     CallIndirect(TableIdx, TypeIdx), // parsing order differs
+    #[cfg(feature = "tail-call")]
+    ReturnCall(FuncIdx),
+    #[cfg(feature = "tail-call")]
+    ReturnCallIndirect(TableIdx, TypeIdx), // parsing order differs
     Drop,
     Select(Option<ResultType<'m>>),
     LocalGet(LocalIdx),
@@ -246,8 +369,30 @@ pub enum Instr<'m> {
     #[cfg(feature = "float-types")]
     FStore(Nx, MemArg),
     IStore_(Bx, MemArg),
-    MemorySize,
-    MemoryGrow,
+    #[cfg(feature = "threads")]
+    AtomicNotify(MemArg),
+    #[cfg(feature = "threads")]
+    AtomicWait(Nx, MemArg),
+    #[cfg(feature = "threads")]
+    AtomicFence,
+    #[cfg(feature = "threads")]
+    IAtomicLoad(Nx, MemArg),
+    #[cfg(feature = "threads")]
+    IAtomicLoad_(Bx, MemArg),
+    #[cfg(feature = "threads")]
+    IAtomicStore(Nx, MemArg),
+    #[cfg(feature = "threads")]
+    IAtomicStore_(Bx, MemArg),
+    #[cfg(feature = "threads")]
+    IAtomicRmw(Nx, RmwOp, MemArg),
+    #[cfg(feature = "threads")]
+    IAtomicRmw_(Bx, RmwOp, MemArg),
+    #[cfg(feature = "threads")]
+    IAtomicCmpxchg(Nx, MemArg),
+    #[cfg(feature = "threads")]
+    IAtomicCmpxchg_(Bx, MemArg),
+    MemorySize(MemIdx),
+    MemoryGrow(MemIdx),
     I32Const(u32),
     I64Const(u64),
     #[cfg(feature = "float-types")]
@@ -269,16 +414,52 @@ pub enum Instr<'m> {
     RefNull(RefType),
     RefIsNull,
     RefFunc(FuncIdx),
-    MemoryInit(DataIdx),
+    MemoryInit(MemIdx, DataIdx), // parsing order differs
     DataDrop(DataIdx),
-    MemoryCopy,
-    MemoryFill,
+    MemoryCopy(MemIdx, MemIdx),
+    MemoryFill(MemIdx),
     TableInit(TableIdx, ElemIdx), // parsing order differs
     ElemDrop(ElemIdx),
     TableCopy(TableIdx, TableIdx),
     TableGrow(TableIdx),
     TableSize(TableIdx),
     TableFill(TableIdx),
+    #[cfg(feature = "vector-types")]
+    V128Load(MemArg),
+    #[cfg(feature = "vector-types")]
+    V128LoadExtend(Lx, Sx, MemArg),
+    #[cfg(feature = "vector-types")]
+    V128LoadSplat(Lx, MemArg),
+    #[cfg(feature = "vector-types")]
+    V128LoadZero(Nx, MemArg),
+    #[cfg(feature = "vector-types")]
+    V128LoadLane(Lx, MemArg, LaneIdx),
+    #[cfg(feature = "vector-types")]
+    V128Store(MemArg),
+    #[cfg(feature = "vector-types")]
+    V128StoreLane(Lx, MemArg, LaneIdx),
+    #[cfg(feature = "vector-types")]
+    V128Const(u128),
+    #[cfg(feature = "vector-types")]
+    I8x16Shuffle([LaneIdx; 16]),
+    #[cfg(feature = "vector-types")]
+    Splat(Shape),
+    #[cfg(feature = "vector-types")]
+    ExtractLane(Shape, Option<Sx>, LaneIdx),
+    #[cfg(feature = "vector-types")]
+    ReplaceLane(Shape, LaneIdx),
+    #[cfg(feature = "vector-types")]
+    VUnOp(VUnOp),
+    #[cfg(feature = "vector-types")]
+    VBinOp(VBinOp),
+    #[cfg(feature = "vector-types")]
+    V128Bitselect,
+    #[cfg(feature = "vector-types")]
+    VTestOp(VTestOp),
+    #[cfg(feature = "vector-types")]
+    VBitmask(Lx),
+    #[cfg(feature = "vector-types")]
+    VShiftOp(Lx, VShiftOp),
 }
 
 pub type TypeIdx = u32;
@@ -288,8 +469,12 @@ pub type MemIdx = u32;
 pub type GlobalIdx = u32;
 pub type ElemIdx = u32;
 pub type DataIdx = u32;
+#[cfg(feature = "exception-handling")]
+pub type TagIdx = u32;
 pub type LocalIdx = u32;
 pub type LabelIdx = u32;
+#[cfg(feature = "vector-types")]
+pub type LaneIdx = u8;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ExportDesc {
@@ -297,6 +482,8 @@ pub enum ExportDesc {
     Table(TableIdx),
     Mem(MemIdx),
     Global(GlobalIdx),
+    #[cfg(feature = "exception-handling")]
+    Tag(TagIdx),
 }
 
 #[derive(Debug, Clone)]
@@ -311,8 +498,11 @@ pub enum ImportDesc {
     Table(TableType),
     Mem(MemType),
     Global(GlobalType),
+    #[cfg(feature = "exception-handling")]
+    Tag(TypeIdx),
 }
 
+/// Import of a module.
 #[derive(Debug, Clone)]
 pub struct Import<'m> {
     pub module: &'m str,
@@ -326,6 +516,8 @@ pub enum ExternType<'m> {
     Table(TableType),
     Mem(MemType),
     Global(GlobalType),
+    #[cfg(feature = "exception-handling")]
+    Tag(FuncType<'m>),
 }
 
 impl<'m> ExternType<'m> {
@@ -338,6 +530,8 @@ impl<'m> ExternType<'m> {
             ) => t.matches(*s) && x == y,
             (ExternType::Mem(t), ExternType::Mem(s)) => t.matches(*s),
             (ExternType::Global(x), ExternType::Global(y)) => x == y,
+            #[cfg(feature = "exception-handling")]
+            (ExternType::Tag(x), ExternType::Tag(y)) => x == y,
             _ => false,
         }
     }
@@ -354,6 +548,7 @@ pub enum BlockType {
 pub struct MemArg {
     pub align: u32,
     pub offset: u32,
+    pub memory: MemIdx,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, TryFromPrimitive, UnsafeFromPrimitive)]
@@ -372,6 +567,7 @@ pub enum SectionId {
     Code = 10,
     Data = 11,
     DataCount = 12,
+    Tag = 13,
 }
 
 impl<'m> Deref for ResultType<'m> {
@@ -439,6 +635,18 @@ impl From<Nx> for usize {
     }
 }
 
+#[cfg(feature = "vector-types")]
+impl From<Lx> for usize {
+    fn from(x: Lx) -> Self {
+        match x {
+            Lx::L8 => 8,
+            Lx::L16 => 16,
+            Lx::L32 => 32,
+            Lx::L64 => 64,
+        }
+    }
+}
+
 impl From<Bx> for Nx {
     fn from(x: Bx) -> Self {
         match x {
@@ -654,6 +862,404 @@ mod float {
     }
 }
 
+#[cfg(feature = "vector-types")]
+trait Lane: Copy {
+    const N: usize;
+    fn get(x: u128, i: usize) -> Self;
+    fn put(self, i: usize) -> u128;
+    fn mask(x: bool) -> Self;
+}
+
+#[cfg(feature = "vector-types")]
+macro_rules! impl_lane {
+    ($u:ident) => {
+        impl Lane for $u {
+            const N: usize = 128 / $u::BITS as usize;
+            fn get(x: u128, i: usize) -> Self {
+                (x >> (i * $u::BITS as usize)) as $u
+            }
+            fn put(self, i: usize) -> u128 {
+                (self as u128) << (i * $u::BITS as usize)
+            }
+            fn mask(x: bool) -> Self {
+                match x {
+                    false => 0,
+                    true => $u::MAX,
+                }
+            }
+        }
+    };
+}
+#[cfg(feature = "vector-types")]
+impl_lane!(u8);
+#[cfg(feature = "vector-types")]
+impl_lane!(u16);
+#[cfg(feature = "vector-types")]
+impl_lane!(u32);
+#[cfg(feature = "vector-types")]
+impl_lane!(u64);
+
+/// Builds a vector from its lanes.
+#[cfg(feature = "vector-types")]
+fn lanes<T: Lane>(f: impl Fn(usize) -> T) -> u128 {
+    (0 .. T::N).fold(0, |z, i| z | f(i).put(i))
+}
+
+#[cfg(feature = "vector-types")]
+fn lanes_map<T: Lane>(x: u128, f: impl Fn(T) -> T) -> u128 {
+    lanes(|i| f(T::get(x, i)))
+}
+
+#[cfg(feature = "vector-types")]
+fn lanes_zip<T: Lane>(x: u128, y: u128, f: impl Fn(T, T) -> T) -> u128 {
+    lanes(|i| f(T::get(x, i), T::get(y, i)))
+}
+
+/// Binds the unsigned and signed lane types of an integer shape.
+#[cfg(feature = "vector-types")]
+macro_rules! with_lx {
+    ($l:expr, |$u:ident, $i:ident| $e:expr) => {
+        match $l {
+            Lx::L8 => {
+                type $u = u8;
+                #[allow(dead_code)]
+                type $i = i8;
+                $e
+            }
+            Lx::L16 => {
+                type $u = u16;
+                #[allow(dead_code)]
+                type $i = i16;
+                $e
+            }
+            Lx::L32 => {
+                type $u = u32;
+                #[allow(dead_code)]
+                type $i = i32;
+                $e
+            }
+            Lx::L64 => {
+                type $u = u64;
+                #[allow(dead_code)]
+                type $i = i64;
+                $e
+            }
+        }
+    };
+}
+
+/// Binds the lane types of an integer shape and of the shape with half the lane width.
+#[cfg(feature = "vector-types")]
+macro_rules! with_half {
+    ($l:expr, |$u:ident, $i:ident, $hu:ident, $hi:ident| $e:expr) => {
+        match $l {
+            Lx::L8 => unreachable!(),
+            Lx::L16 => {
+                type $u = u16;
+                #[allow(dead_code)]
+                type $i = i16;
+                type $hu = u8;
+                type $hi = i8;
+                $e
+            }
+            Lx::L32 => {
+                type $u = u32;
+                #[allow(dead_code)]
+                type $i = i32;
+                type $hu = u16;
+                type $hi = i16;
+                $e
+            }
+            Lx::L64 => {
+                type $u = u64;
+                #[allow(dead_code)]
+                type $i = i64;
+                type $hu = u32;
+                type $hi = i32;
+                $e
+            }
+        }
+    };
+}
+
+#[cfg(feature = "vector-types")]
+impl Shape {
+    pub fn width(self) -> usize {
+        match self {
+            Shape::I(l) => l.into(),
+            Shape::F(n) => n.into(),
+        }
+    }
+
+    pub fn lanes(self) -> usize {
+        128 / self.width()
+    }
+
+    /// Returns the type of a lane outside a vector.
+    pub fn unpacked(self) -> NumType {
+        match self {
+            Shape::I(Lx::L64) => NumType::I64,
+            Shape::I(_) => NumType::I32,
+            Shape::F(n) => NumType::f(n),
+        }
+    }
+
+    pub fn splat(self, x: u64) -> u128 {
+        (0 .. self.lanes()).fold(0, |z, i| self.replace_lane(z, i as LaneIdx, x))
+    }
+
+    pub fn extract_lane(self, x: u128, i: LaneIdx, s: Option<Sx>) -> u64 {
+        let w = self.width();
+        let z = (x >> (i as usize * w)) as u64 & (u64::MAX >> (64 - w));
+        match s {
+            Some(Sx::S) => (((z << (64 - w)) as i64) >> (64 - w)) as u64,
+            _ => z,
+        }
+    }
+
+    pub fn replace_lane(self, x: u128, i: LaneIdx, y: u64) -> u128 {
+        let w = self.width();
+        let m = (u128::MAX >> (128 - w)) << (i as usize * w);
+        (x & !m) | ((y as u128) << (i as usize * w) & m)
+    }
+}
+
+#[cfg(feature = "vector-types")]
+impl VUnOp {
+    pub fn v128(&self, x: u128) -> u128 {
+        match *self {
+            VUnOp::Not => !x,
+            VUnOp::IAbs(l) => with_lx!(l, |U, I| lanes_map(x, |a: U| (a as I).wrapping_abs() as U)),
+            VUnOp::INeg(l) => with_lx!(l, |U, I| lanes_map(x, |a: U| a.wrapping_neg())),
+            VUnOp::IPopCnt => lanes_map(x, |a: u8| a.count_ones() as u8),
+            VUnOp::FUnOp(Nx::N32, ref op) => lanes_map(x, |a| op.n32(a)),
+            VUnOp::FUnOp(Nx::N64, ref op) => lanes_map(x, |a| op.n64(a)),
+            VUnOp::Extend(l, h, s) => with_half!(l, |U, I, HU, HI| {
+                let ext = |a: HU| match s {
+                    Sx::U => a as U,
+                    Sx::S => a as HI as U,
+                };
+                let k = match h {
+                    Half::Low => 0,
+                    Half::High => U::N,
+                };
+                lanes(|i| ext(HU::get(x, k + i)))
+            }),
+            VUnOp::ExtAddPairwise(l, s) => with_half!(l, |U, I, HU, HI| {
+                let ext = |a: HU| match s {
+                    Sx::U => a as U,
+                    Sx::S => a as HI as U,
+                };
+                lanes(|i| ext(HU::get(x, 2 * i)).wrapping_add(ext(HU::get(x, 2 * i + 1))))
+            }),
+            VUnOp::TruncSat(Nx::N32, s) => lanes_map(x, |a: u32| {
+                let a = f32::from_bits(a);
+                match s {
+                    Sx::U => a as u32,
+                    Sx::S => a as i32 as u32,
+                }
+            }),
+            VUnOp::TruncSat(Nx::N64, s) => lanes(|i| match i {
+                0 | 1 => {
+                    let a = f64::from_bits(u64::get(x, i));
+                    match s {
+                        Sx::U => a as u32,
+                        Sx::S => a as i32 as u32,
+                    }
+                }
+                _ => 0,
+            }),
+            VUnOp::Convert(Nx::N32, s) => lanes_map(x, |a: u32| match s {
+                Sx::U => (a as f32).to_bits(),
+                Sx::S => (a as i32 as f32).to_bits(),
+            }),
+            VUnOp::Convert(Nx::N64, s) => lanes(|i| {
+                let a = u32::get(x, i);
+                match s {
+                    Sx::U => (a as f64).to_bits(),
+                    Sx::S => (a as i32 as f64).to_bits(),
+                }
+            }),
+            VUnOp::Demote => lanes(|i| match i {
+                0 | 1 => (f64::from_bits(u64::get(x, i)) as f32).to_bits(),
+                _ => 0,
+            }),
+            VUnOp::Promote => lanes(|i| (f32::from_bits(u32::get(x, i)) as f64).to_bits()),
+        }
+    }
+}
+
+#[cfg(feature = "vector-types")]
+impl VBinOp {
+    #[allow(clippy::unnecessary_cast)] // for the 64-bits lanes
+    pub fn v128(&self, x: u128, y: u128) -> u128 {
+        match *self {
+            VBinOp::And => x & y,
+            VBinOp::AndNot => x & !y,
+            VBinOp::Or => x | y,
+            VBinOp::Xor => x ^ y,
+            VBinOp::IRelOp(l, ref op) => with_lx!(l, |U, I| lanes_zip(x, y, |a: U, b: U| {
+                let (a, b) = match op {
+                    IRelOp::Lt(Sx::S)
+                    | IRelOp::Gt(Sx::S)
+                    | IRelOp::Le(Sx::S)
+                    | IRelOp::Ge(Sx::S) => (a as I as u64, b as I as u64),
+                    _ => (a as u64, b as u64),
+                };
+                U::mask(op.n64(a, b))
+            })),
+            VBinOp::FRelOp(Nx::N32, ref op) => lanes_zip(x, y, |a, b| u32::mask(op.n32(a, b))),
+            VBinOp::FRelOp(Nx::N64, ref op) => lanes_zip(x, y, |a, b| u64::mask(op.n64(a, b))),
+            VBinOp::IAdd(l) => with_lx!(l, |U, I| lanes_zip(x, y, |a: U, b| a.wrapping_add(b))),
+            VBinOp::ISub(l) => with_lx!(l, |U, I| lanes_zip(x, y, |a: U, b| a.wrapping_sub(b))),
+            VBinOp::IMul(l) => with_lx!(l, |U, I| lanes_zip(x, y, |a: U, b| a.wrapping_mul(b))),
+            VBinOp::IAddSat(l, s) => with_lx!(l, |U, I| lanes_zip(x, y, |a: U, b| match s {
+                Sx::U => a.saturating_add(b),
+                Sx::S => (a as I).saturating_add(b as I) as U,
+            })),
+            VBinOp::ISubSat(l, s) => with_lx!(l, |U, I| lanes_zip(x, y, |a: U, b| match s {
+                Sx::U => a.saturating_sub(b),
+                Sx::S => (a as I).saturating_sub(b as I) as U,
+            })),
+            VBinOp::IMin(l, s) => with_lx!(l, |U, I| lanes_zip(x, y, |a: U, b| match s {
+                Sx::U => a.min(b),
+                Sx::S => (a as I).min(b as I) as U,
+            })),
+            VBinOp::IMax(l, s) => with_lx!(l, |U, I| lanes_zip(x, y, |a: U, b| match s {
+                Sx::U => a.max(b),
+                Sx::S => (a as I).max(b as I) as U,
+            })),
+            VBinOp::IAvgrU(l) => with_lx!(l, |U, I| lanes_zip(x, y, |a: U, b| {
+                ((a as u128 + b as u128 + 1) / 2) as U
+            })),
+            VBinOp::Q15MulrSatS => lanes_zip(x, y, |a: u16, b| {
+                let z = (a as i16 as i32 * b as i16 as i32 + 0x4000) >> 15;
+                z.clamp(i16::MIN as i32, i16::MAX as i32) as u16
+            }),
+            VBinOp::Dot => lanes(|i| {
+                let p = |j| u16::get(x, j) as i16 as i32 * u16::get(y, j) as i16 as i32;
+                p(2 * i).wrapping_add(p(2 * i + 1)) as u32
+            }),
+            VBinOp::ExtMul(l, h, s) => with_half!(l, |U, I, HU, HI| {
+                let ext = |a: HU| match s {
+                    Sx::U => a as U,
+                    Sx::S => a as HI as U,
+                };
+                let k = match h {
+                    Half::Low => 0,
+                    Half::High => U::N,
+                };
+                lanes(|i| ext(HU::get(x, k + i)).wrapping_mul(ext(HU::get(y, k + i))))
+            }),
+            VBinOp::Narrow(l, s) => {
+                let l = match l {
+                    Lx::L8 => Lx::L16,
+                    Lx::L16 => Lx::L32,
+                    _ => unreachable!(),
+                };
+                with_half!(l, |U, I, HU, HI| lanes(|i| {
+                    let a = match i < U::N {
+                        true => U::get(x, i),
+                        false => U::get(y, i - U::N),
+                    } as I;
+                    match s {
+                        Sx::U => a.clamp(0, HU::MAX as I) as HU,
+                        Sx::S => a.clamp(HI::MIN as I, HI::MAX as I) as HU,
+                    }
+                }))
+            }
+            VBinOp::Swizzle => lanes(|i| match u8::get(y, i) as usize {
+                j @ 0 ..= 15 => u8::get(x, j),
+                _ => 0,
+            }),
+            VBinOp::FBinOp(Nx::N32, ref op) => lanes_zip(x, y, |a, b| op.n32(a, b)),
+            VBinOp::FBinOp(Nx::N64, ref op) => lanes_zip(x, y, |a, b| op.n64(a, b)),
+            VBinOp::FPMin(Nx::N32) => {
+                lanes_zip(
+                    x,
+                    y,
+                    |a: u32, b: u32| {
+                        if f32::from_bits(b) < f32::from_bits(a) {
+                            b
+                        } else {
+                            a
+                        }
+                    },
+                )
+            }
+            VBinOp::FPMin(Nx::N64) => {
+                lanes_zip(
+                    x,
+                    y,
+                    |a: u64, b: u64| {
+                        if f64::from_bits(b) < f64::from_bits(a) {
+                            b
+                        } else {
+                            a
+                        }
+                    },
+                )
+            }
+            VBinOp::FPMax(Nx::N32) => {
+                lanes_zip(
+                    x,
+                    y,
+                    |a: u32, b: u32| {
+                        if f32::from_bits(a) < f32::from_bits(b) {
+                            b
+                        } else {
+                            a
+                        }
+                    },
+                )
+            }
+            VBinOp::FPMax(Nx::N64) => {
+                lanes_zip(
+                    x,
+                    y,
+                    |a: u64, b: u64| {
+                        if f64::from_bits(a) < f64::from_bits(b) {
+                            b
+                        } else {
+                            a
+                        }
+                    },
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "vector-types")]
+impl VTestOp {
+    pub fn v128(&self, x: u128) -> bool {
+        match *self {
+            VTestOp::AnyTrue => x != 0,
+            VTestOp::AllTrue(l) => with_lx!(l, |U, I| (0 .. U::N).all(|i| U::get(x, i) != 0)),
+        }
+    }
+}
+
+#[cfg(feature = "vector-types")]
+impl VShiftOp {
+    pub fn v128(&self, l: Lx, x: u128, y: u32) -> u128 {
+        with_lx!(l, |U, I| lanes_map(x, |a: U| match self {
+            VShiftOp::Shl => a.wrapping_shl(y),
+            VShiftOp::Shr(Sx::U) => a.wrapping_shr(y),
+            VShiftOp::Shr(Sx::S) => (a as I).wrapping_shr(y) as U,
+        }))
+    }
+}
+
+#[cfg(feature = "vector-types")]
+impl Lx {
+    pub fn bitmask(self, x: u128) -> u32 {
+        with_lx!(self, |U, I| {
+            (0 .. U::N).fold(0, |z, i| z | (((U::get(x, i) as I) < 0) as u32) << i)
+        })
+    }
+}
+
 impl From<u8> for Sx {
     fn from(x: u8) -> Self {
         match x {
@@ -687,6 +1293,56 @@ impl From<u8> for Bx {
     }
 }
 
+#[cfg(feature = "threads")]
+impl From<u8> for RmwOp {
+    fn from(x: u8) -> Self {
+        match x {
+            0 => RmwOp::Add,
+            1 => RmwOp::Sub,
+            2 => RmwOp::And,
+            3 => RmwOp::Or,
+            4 => RmwOp::Xor,
+            5 => RmwOp::Xchg,
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[cfg(feature = "vector-types")]
+impl From<u8> for Lx {
+    fn from(x: u8) -> Self {
+        match x {
+            0 => Lx::L8,
+            1 => Lx::L16,
+            2 => Lx::L32,
+            3 => Lx::L64,
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[cfg(feature = "vector-types")]
+impl From<u8> for Shape {
+    fn from(x: u8) -> Self {
+        match x {
+            x @ 0 ..= 3 => Shape::I(x.into()),
+            x @ 4 ..= 5 => Shape::F((x - 4).into()),
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[cfg(feature = "vector-types")]
+impl From<u8> for Half {
+    fn from(x: u8) -> Self {
+        match x {
+            0 => Half::Low,
+            1 => Half::High,
+            _ => unreachable!(),
+        }
+    }
+}
+
 impl From<u8> for IRelOp {
     fn from(x: u8) -> Self {
         match x {
@@ -781,11 +1437,13 @@ impl From<u8> for FBinOp {
 
 impl SectionId {
     pub fn order(self) -> u8 {
-        // DataCount is actually between Element and Code.
+        // DataCount is actually between Element and Code, and Tag between Memory and Global.
         match self as u8 {
-            x @ 0 ..= 9 => x,
-            12 => 10,
-            x @ 10 ..= 11 => x + 1,
+            x @ 0 ..= 5 => x,
+            13 => 6,
+            x @ 6 ..= 9 => x + 1,
+            12 => 11,
+            x @ 10 ..= 11 => x + 2,
             _ => unreachable!(),
         }
     }
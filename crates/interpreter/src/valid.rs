@@ -18,12 +18,18 @@ use alloc::vec::Vec;
 use core::cmp::Ordering;
 
 use crate::error::*;
+use crate::side_table::{SideTable, SideTableBuilder};
 use crate::syntax::*;
 use crate::toctou::*;
 use crate::*;
 
 /// Checks whether a WASM module in binary format is valid.
 pub fn validate(binary: &[u8]) -> Result<(), Error> {
+    prepare(binary).map(drop)
+}
+
+/// Checks whether a WASM module in binary format is valid and returns its side-table.
+pub(crate) fn prepare(binary: &[u8]) -> Result<SideTable, Error> {
     Context::default().check_module(&mut Parser::new(binary))
 }
 
@@ -39,10 +45,12 @@ struct Context<'m> {
     globals: Vec<GlobalType>,
     elems: Vec<RefType>,
     datas: Option<usize>,
+    #[cfg(feature = "exception-handling")]
+    tags: Vec<FuncType<'m>>,
 }
 
 impl<'m> Context<'m> {
-    fn check_module(&mut self, parser: &mut Parser<'m>) -> CheckResult {
+    fn check_module(&mut self, parser: &mut Parser<'m>) -> MResult<SideTable, Check> {
         check(parser.parse_bytes(8)? == b"\0asm\x01\0\0\0")?;
         if let Some(mut parser) = self.check_section(parser, SectionId::Type)? {
             let n = parser.parse_vec()?;
@@ -78,7 +86,19 @@ impl<'m> Context<'m> {
             }
             check(parser.is_empty())?;
         }
+        #[cfg(not(feature = "multi-memory"))]
         check(self.mems.len() <= 1)?;
+        #[cfg(feature = "exception-handling")]
+        if let Some(mut parser) = self.check_section(parser, SectionId::Tag)? {
+            for _ in 0 .. parser.parse_vec()? {
+                self.add_tagtype(parser.parse_tagtype()?)?;
+            }
+            check(parser.is_empty())?;
+        }
+        #[cfg(not(feature = "exception-handling"))]
+        if self.check_section(parser, SectionId::Tag)?.is_some() {
+            return Err(unsupported());
+        }
         let globals_len = self.globals.len();
         if let Some(mut parser) = self.check_section(parser, SectionId::Global)? {
             for _ in 0 .. parser.parse_vec()? {
@@ -122,6 +142,7 @@ impl<'m> Context<'m> {
             self.datas = Some(parser.parse_u32()? as usize);
             check(parser.is_empty())?;
         }
+        let mut side_table = SideTableBuilder::default();
         if let Some(mut parser) = self.check_section(parser, SectionId::Code)? {
             check(self.funcs.len() == imported_funcs + parser.parse_vec()?)?;
             for x in imported_funcs .. self.funcs.len() {
@@ -130,7 +151,7 @@ impl<'m> Context<'m> {
                 let t = self.functype(x as FuncIdx).unwrap();
                 let mut locals = t.params.to_vec();
                 parser.parse_locals(&mut locals)?;
-                Expr::check_body(self, &mut parser, &refs, locals, t.results)?;
+                Expr::check_body(self, &mut parser, &refs, locals, t.results, &mut side_table)?;
                 check(parser.is_empty())?;
             }
             check(parser.is_empty())?;
@@ -146,7 +167,8 @@ impl<'m> Context<'m> {
             check(parser.is_empty())?;
         }
         self.check_section(parser, SectionId::Custom)?;
-        check(parser.is_empty())
+        check(parser.is_empty())?;
+        Ok(side_table.finish())
     }
 
     fn check_section(
@@ -184,6 +206,8 @@ impl<'m> Context<'m> {
             ImportDesc::Table(t) => self.add_tabletype(t),
             ImportDesc::Mem(m) => self.add_memtype(m),
             ImportDesc::Global(g) => self.add_globaltype(g),
+            #[cfg(feature = "exception-handling")]
+            ImportDesc::Tag(x) => self.add_tagtype(x),
         }
     }
 
@@ -200,7 +224,7 @@ impl<'m> Context<'m> {
     }
 
     fn add_memtype(&mut self, m: MemType) -> CheckResult {
-        check(m.valid(MEM_MAX))?;
+        check(m.valid())?;
         self.mems.push(m);
         Ok(())
     }
@@ -210,12 +234,22 @@ impl<'m> Context<'m> {
         Ok(())
     }
 
+    #[cfg(feature = "exception-handling")]
+    fn add_tagtype(&mut self, x: TypeIdx) -> CheckResult {
+        let t = self.type_(x)?;
+        check(t.results.is_empty())?;
+        self.tags.push(t);
+        Ok(())
+    }
+
     fn check_exportdesc(&self, desc: &ExportDesc) -> CheckResult {
         let (&x, n) = match desc {
             ExportDesc::Func(x) => (x, self.funcs.len()),
             ExportDesc::Table(x) => (x, self.tables.len()),
             ExportDesc::Mem(x) => (x, self.mems.len()),
             ExportDesc::Global(x) => (x, self.globals.len()),
+            #[cfg(feature = "exception-handling")]
+            ExportDesc::Tag(x) => (x, self.tags.len()),
         };
         check((x as usize) < n)
     }
@@ -247,6 +281,11 @@ impl<'m> Context<'m> {
     fn data(&self, x: DataIdx) -> CheckResult {
         check(self.datas.map_or(false, |n| (x as usize) < n))
     }
+
+    #[cfg(feature = "exception-handling")]
+    fn tag(&self, x: TagIdx) -> Result<FuncType<'m>, Error> {
+        self.tags.get(x as usize).cloned().ok_or_else(invalid)
+    }
 }
 
 struct ParseElem<'a, 'm> {
@@ -401,12 +440,14 @@ struct Expr<'a, 'm> {
     is_body: bool,
     locals: Vec<ValType>,
     labels: Vec<Label<'m>>,
+    /// The side-table builder, if the expression is a function body.
+    side_table: Option<&'a mut SideTableBuilder>,
 }
 
 #[derive(Debug, Default)]
 struct Label<'m> {
     type_: FuncType<'m>,
-    /// Whether an `else` is possible before `end`.
+    /// Whether an `else`, `catch`, or `catch_all` is possible before `end`.
     kind: LabelKind,
     /// Whether the bottom of the stack is polymorphic.
     polymorphic: bool,
@@ -419,6 +460,12 @@ enum LabelKind {
     Block,
     Loop,
     If,
+    #[cfg(feature = "exception-handling")]
+    Try,
+    #[cfg(feature = "exception-handling")]
+    Catch,
+    #[cfg(feature = "exception-handling")]
+    CatchAll,
 }
 
 impl<'a, 'm> Expr<'a, 'm> {
@@ -434,6 +481,7 @@ impl<'a, 'm> Expr<'a, 'm> {
             is_body: false,
             locals: vec![],
             labels: vec![Label::default()],
+            side_table: None,
         }
     }
 
@@ -449,12 +497,14 @@ impl<'a, 'm> Expr<'a, 'm> {
 
     fn check_body(
         context: &'a Context<'m>, parser: &'a mut Parser<'m>, refs: &'a [bool],
-        locals: Vec<ValType>, results: ResultType<'m>,
+        locals: Vec<ValType>, results: ResultType<'m>, side_table: &'a mut SideTableBuilder,
     ) -> CheckResult {
         let mut expr = Expr::new(context, parser, Err(refs));
         expr.is_body = true;
         expr.locals = locals;
         expr.label().type_.results = results;
+        side_table.func(results.len());
+        expr.side_table = Some(side_table);
         expr.check()
     }
 
@@ -467,9 +517,13 @@ impl<'a, 'm> Expr<'a, 'm> {
 
     fn instr(&mut self) -> CheckResult {
         use Instr::*;
+        let saved = self.parser.save();
         let instr = self.parser.parse_instr()?;
         if matches!(instr, End) {
-            return self.end_label();
+            self.end_label()?;
+            let next = self.parser.save();
+            self.side_table(|x| x.end(saved, next));
+            return Ok(());
         }
         if self.is_const.is_ok() {
             match instr {
@@ -484,6 +538,8 @@ impl<'a, 'm> Expr<'a, 'm> {
                 F32Const(_) => (),
                 #[cfg(feature = "float-types")]
                 F64Const(_) => (),
+                #[cfg(feature = "vector-types")]
+                V128Const(_) => (),
                 RefNull(_) => (),
                 RefFunc(_) => (),
                 _ => return Err(invalid()),
@@ -492,11 +548,21 @@ impl<'a, 'm> Expr<'a, 'm> {
         match instr {
             Unreachable => self.stack_polymorphic(),
             Nop => (),
-            Block(b) => self.push_label(self.blocktype(&b)?, LabelKind::Block)?,
-            Loop(b) => self.push_label(self.blocktype(&b)?, LabelKind::Loop)?,
+            Block(b) => {
+                let t = self.blocktype(&b)?;
+                self.push_label(t, LabelKind::Block)?;
+                self.side_table(|x| x.push_block(t.results.len()));
+            }
+            Loop(b) => {
+                let t = self.blocktype(&b)?;
+                self.push_label(t, LabelKind::Loop)?;
+                self.side_table(|x| x.push_loop(t.params.len(), saved));
+            }
             If(b) => {
                 self.pop_check(ValType::I32)?;
-                self.push_label(self.blocktype(&b)?, LabelKind::If)?;
+                let t = self.blocktype(&b)?;
+                self.push_label(t, LabelKind::If)?;
+                self.side_table(|x| x.push_if(t.results.len(), saved));
             }
             Else => {
                 let label = self.label();
@@ -506,26 +572,71 @@ impl<'a, 'm> Expr<'a, 'm> {
                 check(self.stack().is_empty())?;
                 self.label().polymorphic = false;
                 self.pushs(params);
+                let next = self.parser.save();
+                self.side_table(|x| x.else_(saved, next));
+            }
+            #[cfg(feature = "exception-handling")]
+            Try(b) => {
+                let t = self.blocktype(&b)?;
+                self.push_label(t, LabelKind::Try)?;
+                self.side_table(|x| x.push_try(t.results.len(), saved));
+            }
+            #[cfg(feature = "exception-handling")]
+            Catch(x) => {
+                let t = self.context.tag(x)?;
+                self.catch(LabelKind::Catch)?;
+                self.pushs(t.params);
+                self.side_table(|x| x.catch(saved, false));
+            }
+            #[cfg(feature = "exception-handling")]
+            CatchAll => {
+                self.catch(LabelKind::CatchAll)?;
+                self.side_table(|x| x.catch(saved, true));
+            }
+            #[cfg(feature = "exception-handling")]
+            Delegate(l) => {
+                check(self.label().kind == LabelKind::Try)?;
+                self.end_label()?;
+                // The label is relative to the labels outside the `try`.
+                check((l as usize) < self.labels.len())?;
+                let next = self.parser.save();
+                self.side_table(|x| x.end(saved, next));
+            }
+            #[cfg(feature = "exception-handling")]
+            Throw(x) => {
+                self.pops(self.context.tag(x)?.params)?;
+                self.stack_polymorphic();
+            }
+            #[cfg(feature = "exception-handling")]
+            Rethrow(l) => {
+                let n = self.labels.len();
+                check((l as usize) < n)?;
+                let kind = self.labels[n - l as usize - 1].kind;
+                check(matches!(kind, LabelKind::Catch | LabelKind::CatchAll))?;
+                self.stack_polymorphic();
             }
             End => unreachable!(),
             Br(l) => {
                 self.pops(self.br_label(l)?)?;
                 self.stack_polymorphic();
+                self.side_table(|x| x.branch(saved, l));
             }
             BrIf(l) => {
                 self.pop_check(ValType::I32)?;
                 self.swaps(self.br_label(l)?)?;
+                self.side_table(|x| x.branch(saved, l));
             }
             BrTable(ls, ln) => {
                 self.pop_check(ValType::I32)?;
                 let tn = self.br_label(ln)?;
                 self.peeks(tn)?;
-                for l in ls {
+                for &l in &ls {
                     let t = self.br_label(l)?;
                     check(tn.len() == t.len())?;
                     self.peeks(t)?;
                 }
                 self.stack_polymorphic();
+                self.side_table(|x| ls.iter().chain([&ln]).for_each(|&l| x.branch(saved, l)));
             }
             Return => {
                 check(self.is_body)?;
@@ -538,6 +649,14 @@ impl<'a, 'm> Expr<'a, 'm> {
                 self.pop_check(ValType::I32)?;
                 self.call(self.context.type_(y)?)?;
             }
+            #[cfg(feature = "tail-call")]
+            ReturnCall(x) => self.return_call(self.context.functype(x)?)?,
+            #[cfg(feature = "tail-call")]
+            ReturnCallIndirect(x, y) => {
+                check(self.context.table(x)?.item == RefType::FuncRef)?;
+                self.pop_check(ValType::I32)?;
+                self.return_call(self.context.type_(y)?)?;
+            }
             Drop => drop(self.pop()?),
             Select(None) => {
                 self.pop_check(ValType::I32)?;
@@ -574,12 +693,55 @@ impl<'a, 'm> Expr<'a, 'm> {
             #[cfg(feature = "float-types")]
             FStore(n, m) => self.store(NumType::f(n), n.into(), m)?,
             IStore_(b, m) => self.store(NumType::i(b.into()), b.into(), m)?,
-            MemorySize => {
-                check(!self.context.mems.is_empty())?;
+            #[cfg(feature = "threads")]
+            AtomicNotify(m) => {
+                self.atomic(32, m)?;
+                self.pops([ValType::I32, ValType::I32][..].into())?;
                 self.push(OpdType::I32);
             }
-            MemoryGrow => {
-                check(!self.context.mems.is_empty())?;
+            #[cfg(feature = "threads")]
+            AtomicWait(n, m) => {
+                self.atomic(n.into(), m)?;
+                let t = NumType::i(n).into();
+                self.pops([ValType::I32, t, ValType::I64][..].into())?;
+                self.push(OpdType::I32);
+            }
+            #[cfg(feature = "threads")]
+            AtomicFence => (),
+            #[cfg(feature = "threads")]
+            IAtomicLoad(n, m) => {
+                self.atomic(n.into(), m)?;
+                self.load(NumType::i(n), n.into(), m)?;
+            }
+            #[cfg(feature = "threads")]
+            IAtomicLoad_(b, m) => {
+                self.atomic(b.into(), m)?;
+                self.load(NumType::i(b.into()), b.into(), m)?;
+            }
+            #[cfg(feature = "threads")]
+            IAtomicStore(n, m) => {
+                self.atomic(n.into(), m)?;
+                self.store(NumType::i(n), n.into(), m)?;
+            }
+            #[cfg(feature = "threads")]
+            IAtomicStore_(b, m) => {
+                self.atomic(b.into(), m)?;
+                self.store(NumType::i(b.into()), b.into(), m)?;
+            }
+            #[cfg(feature = "threads")]
+            IAtomicRmw(n, _, m) => self.rmw(NumType::i(n), n.into(), m, 1)?,
+            #[cfg(feature = "threads")]
+            IAtomicRmw_(b, _, m) => self.rmw(NumType::i(b.into()), b.into(), m, 1)?,
+            #[cfg(feature = "threads")]
+            IAtomicCmpxchg(n, m) => self.rmw(NumType::i(n), n.into(), m, 2)?,
+            #[cfg(feature = "threads")]
+            IAtomicCmpxchg_(b, m) => self.rmw(NumType::i(b.into()), b.into(), m, 2)?,
+            MemorySize(x) => {
+                self.context.mem(x)?;
+                self.push(OpdType::I32);
+            }
+            MemoryGrow(x) => {
+                self.context.mem(x)?;
                 self.swap(ValType::I32)?;
             }
             I32Const(_) => self.push(OpdType::I32),
@@ -613,14 +775,19 @@ impl<'a, 'm> Expr<'a, 'm> {
                 }
                 self.push(OpdType::FuncRef);
             }
-            MemoryInit(x) => {
-                check(!self.context.mems.is_empty())?;
-                self.context.data(x)?;
+            MemoryInit(x, y) => {
+                self.context.mem(x)?;
+                self.context.data(y)?;
                 self.pops([ValType::I32; 3][..].into())?;
             }
             DataDrop(x) => self.context.data(x)?,
-            MemoryCopy | MemoryFill => {
-                check(!self.context.mems.is_empty())?;
+            MemoryCopy(x, y) => {
+                self.context.mem(x)?;
+                self.context.mem(y)?;
+                self.pops([ValType::I32; 3][..].into())?;
+            }
+            MemoryFill(x) => {
+                self.context.mem(x)?;
                 self.pops([ValType::I32; 3][..].into())?;
             }
             TableInit(x, y) => {
@@ -645,6 +812,67 @@ impl<'a, 'm> Expr<'a, 'm> {
                 let t = self.context.table(x)?.item;
                 self.pops([ValType::I32, t.into(), ValType::I32][..].into())?;
             }
+            #[cfg(feature = "vector-types")]
+            V128Load(m) => self.vload(128, m, None)?,
+            #[cfg(feature = "vector-types")]
+            V128LoadExtend(_, _, m) => self.vload(64, m, None)?,
+            #[cfg(feature = "vector-types")]
+            V128LoadSplat(l, m) => self.vload(l.into(), m, None)?,
+            #[cfg(feature = "vector-types")]
+            V128LoadZero(n, m) => self.vload(n.into(), m, None)?,
+            #[cfg(feature = "vector-types")]
+            V128LoadLane(l, m, i) => self.vload(l.into(), m, Some(i))?,
+            #[cfg(feature = "vector-types")]
+            V128Store(m) => self.vstore(128, m, None)?,
+            #[cfg(feature = "vector-types")]
+            V128StoreLane(l, m, i) => self.vstore(l.into(), m, Some(i))?,
+            #[cfg(feature = "vector-types")]
+            V128Const(_) => self.push(OpdType::V128),
+            #[cfg(feature = "vector-types")]
+            I8x16Shuffle(lanes) => {
+                check(lanes.iter().all(|&i| i < 32))?;
+                self.pop_check(ValType::V128)?;
+                self.swap(ValType::V128)?;
+            }
+            #[cfg(feature = "vector-types")]
+            Splat(s) => {
+                self.pop_check(s.unpacked().into())?;
+                self.push(OpdType::V128);
+            }
+            #[cfg(feature = "vector-types")]
+            ExtractLane(s, _, i) => {
+                check((i as usize) < s.lanes())?;
+                self.pop_check(ValType::V128)?;
+                self.push(s.unpacked().into());
+            }
+            #[cfg(feature = "vector-types")]
+            ReplaceLane(s, i) => {
+                check((i as usize) < s.lanes())?;
+                self.pop_check(s.unpacked().into())?;
+                self.swap(ValType::V128)?;
+            }
+            #[cfg(feature = "vector-types")]
+            VUnOp(_) => self.swap(ValType::V128)?,
+            #[cfg(feature = "vector-types")]
+            VBinOp(_) => {
+                self.pop_check(ValType::V128)?;
+                self.swap(ValType::V128)?;
+            }
+            #[cfg(feature = "vector-types")]
+            V128Bitselect => {
+                self.pops([ValType::V128; 2][..].into())?;
+                self.swap(ValType::V128)?;
+            }
+            #[cfg(feature = "vector-types")]
+            VTestOp(_) | VBitmask(_) => {
+                self.pop_check(ValType::V128)?;
+                self.push(OpdType::I32);
+            }
+            #[cfg(feature = "vector-types")]
+            VShiftOp(_, _) => {
+                self.pop_check(ValType::I32)?;
+                self.swap(ValType::V128)?;
+            }
         }
         Ok(())
     }
@@ -657,6 +885,12 @@ impl<'a, 'm> Expr<'a, 'm> {
         })
     }
 
+    fn side_table(&mut self, f: impl FnOnce(&mut SideTableBuilder)) {
+        if let Some(x) = &mut self.side_table {
+            f(x);
+        }
+    }
+
     fn local(&self, x: LocalIdx) -> Result<ValType, Error> {
         self.locals.get(x as usize).cloned().ok_or_else(invalid)
     }
@@ -753,14 +987,27 @@ impl<'a, 'm> Expr<'a, 'm> {
         Ok(())
     }
 
+    /// Ends the body of a `try` or the previous clause, and starts a clause.
+    #[cfg(feature = "exception-handling")]
+    fn catch(&mut self, kind: LabelKind) -> CheckResult {
+        let label = self.label();
+        let prev = core::mem::replace(&mut label.kind, kind);
+        check(matches!(prev, LabelKind::Try | LabelKind::Catch))?;
+        let results = label.type_.results;
+        self.pops(results)?;
+        check(self.stack().is_empty())?;
+        self.label().polymorphic = false;
+        Ok(())
+    }
+
     fn br_label(&self, l: LabelIdx) -> Result<ResultType<'m>, Error> {
         let l = l as usize;
         let n = self.labels.len();
         check(l < n)?;
         let label = &self.labels[n - l - 1];
         Ok(match label.kind {
-            LabelKind::Block | LabelKind::If => label.type_.results,
             LabelKind::Loop => label.type_.params,
+            _ => label.type_.results,
         })
     }
 
@@ -770,6 +1017,15 @@ impl<'a, 'm> Expr<'a, 'm> {
         Ok(())
     }
 
+    #[cfg(feature = "tail-call")]
+    fn return_call(&mut self, t: FuncType) -> CheckResult {
+        check(self.is_body)?;
+        check(t.results == self.labels[0].type_.results)?;
+        self.pops(t.params)?;
+        self.stack_polymorphic();
+        Ok(())
+    }
+
     fn stack_polymorphic(&mut self) {
         let label = self.label();
         label.stack.clear();
@@ -777,7 +1033,7 @@ impl<'a, 'm> Expr<'a, 'm> {
     }
 
     fn load(&mut self, t: NumType, n: usize, m: MemArg) -> CheckResult {
-        check(!self.context.mems.is_empty())?;
+        self.context.mem(m.memory)?;
         check(1 << m.align <= n / 8)?;
         self.pop_check(ValType::I32)?;
         self.push(t.into());
@@ -785,13 +1041,57 @@ impl<'a, 'm> Expr<'a, 'm> {
     }
 
     fn store(&mut self, t: NumType, n: usize, m: MemArg) -> CheckResult {
-        check(!self.context.mems.is_empty())?;
+        self.context.mem(m.memory)?;
         check(1 << m.align <= n / 8)?;
         self.pop_check(t.into())?;
         self.pop_check(ValType::I32)?;
         Ok(())
     }
 
+    /// Checks that an atomic access has natural alignment.
+    #[cfg(feature = "threads")]
+    fn atomic(&mut self, n: usize, m: MemArg) -> CheckResult {
+        self.context.mem(m.memory)?;
+        check(1 << m.align == n / 8)
+    }
+
+    /// Checks a read-modify-write taking `k` operands.
+    #[cfg(feature = "threads")]
+    fn rmw(&mut self, t: NumType, n: usize, m: MemArg, k: usize) -> CheckResult {
+        self.atomic(n, m)?;
+        for _ in 0 .. k {
+            self.pop_check(t.into())?;
+        }
+        self.pop_check(ValType::I32)?;
+        self.push(t.into());
+        Ok(())
+    }
+
+    #[cfg(feature = "vector-types")]
+    fn vload(&mut self, n: usize, m: MemArg, lane: Option<LaneIdx>) -> CheckResult {
+        self.context.mem(m.memory)?;
+        check(1 << m.align <= n / 8)?;
+        if let Some(i) = lane {
+            check((i as usize) < 128 / n)?;
+            self.pop_check(ValType::V128)?;
+        }
+        self.pop_check(ValType::I32)?;
+        self.push(OpdType::V128);
+        Ok(())
+    }
+
+    #[cfg(feature = "vector-types")]
+    fn vstore(&mut self, n: usize, m: MemArg, lane: Option<LaneIdx>) -> CheckResult {
+        self.context.mem(m.memory)?;
+        check(1 << m.align <= n / 8)?;
+        if let Some(i) = lane {
+            check((i as usize) < 128 / n)?;
+        }
+        self.pop_check(ValType::V128)?;
+        self.pop_check(ValType::I32)?;
+        Ok(())
+    }
+
     fn testop(&mut self, t: NumType) -> CheckResult {
         self.pop_check(t.into())?;
         self.push(OpdType::I32);
@@ -25,4 +25,4 @@ cargo fmt -- --check
 cargo clippy -- --deny=warnings
 [ -e ../../third_party/WebAssembly/spec/.git ] \
   || git submodule update --init ../../third_party/WebAssembly/spec
-cargo test --features=debug,toctou,float-types,vector-types
+cargo test --features=debug,toctou,float-types,vector-types,tail-call,multi-memory,threads,exception-handling,profile,debugger
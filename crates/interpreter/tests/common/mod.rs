@@ -0,0 +1,51 @@
+// Copyright 2023 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Helpers shared by the integration tests.
+
+// Each test crate only uses some of the helpers.
+#![allow(dead_code)]
+
+use wasefire_interpreter::*;
+use wast::{parser, Wat};
+
+/// Linear memory with the alignment required by the interpreter.
+#[repr(align(16))]
+pub struct Memory<const N: usize>(pub [u8; N]);
+
+/// Encodes a module from its text format.
+pub fn encode(wat: &str) -> Vec<u8> {
+    let buffer = parser::ParseBuffer::new(wat).unwrap();
+    let mut wat: Wat = parser::parse(&buffer).unwrap();
+    wat.encode().unwrap()
+}
+
+/// Returns a linker with host functions in the `env` module.
+///
+/// Functions are given by name, number of parameters, and number of results. They must be sorted.
+pub fn linker(funcs: &[(&'static str, usize, usize)]) -> Linker<'static> {
+    let mut linker = Linker::default();
+    for &(name, params, results) in funcs {
+        linker.link_func("env", name, params, results).unwrap();
+    }
+    linker
+}
+
+/// Returns the host call of a run result.
+pub fn host<'a, 'm>(result: RunResult<'a, 'm>) -> Call<'a, 'm> {
+    match result {
+        RunResult::Host(x) => x,
+        _ => unreachable!(),
+    }
+}
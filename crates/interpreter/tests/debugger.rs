@@ -0,0 +1,84 @@
+// Copyright 2023 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(feature = "debugger")]
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use common::{encode, Memory};
+use wasefire_interpreter::*;
+
+mod common;
+
+const STORE: &str = r#"
+(module
+  (memory 1)
+  (func $store (param i32 i32)
+    (i32.store (local.get 0) (local.get 1)))
+  (func (export "main")
+    (call $store (i32.const 16) (i32.const 42))
+    (call $store (i32.const 20) (i32.const 7))))
+"#;
+
+/// Breaks at the `i32.store` instruction and records the stored values.
+#[derive(Default)]
+struct Recorder {
+    pauses: usize,
+    offset: usize,
+    stored: Vec<(Val, Val, u8)>,
+}
+
+struct Handle(Rc<RefCell<Recorder>>);
+
+impl Debugger for Handle {
+    fn pause(&mut self, mut paused: Paused) {
+        let mut recorder = self.0.borrow_mut();
+        recorder.pauses += 1;
+        let frames = paused.backtrace();
+        let inst = frames[0].inst;
+        if recorder.pauses == 1 {
+            // Execution pauses before the first instruction.
+            assert_eq!(frames.len(), 1);
+            paused.set_step(false);
+            paused.set_breakpoint(inst, recorder.offset).unwrap();
+            return;
+        }
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].offset, recorder.offset);
+        let locals = paused.locals(0).unwrap();
+        let (addr, value) = (locals[0], locals[1]);
+        assert!(paused.locals(2).is_none());
+        let byte = paused.mem(inst).unwrap()[16];
+        recorder.stored.push((addr, value, byte));
+    }
+}
+
+#[test]
+fn breakpoint() {
+    let wasm = encode(STORE);
+    // The `i32.store` instruction is right after the two `local.get` of the first function.
+    let offset = wasm.windows(3).position(|x| x == [0x36, 0x02, 0x00]).unwrap();
+    let linker = Linker::default();
+    let mut memory = Memory([0; 0x10000]);
+    let mut store = Store::new(&linker);
+    let inst = store.instantiate(Module::new(&wasm).unwrap(), &mut memory.0).unwrap();
+    let recorder = Rc::new(RefCell::new(Recorder { offset, ..Default::default() }));
+    store.set_debugger(Some(Box::new(Handle(recorder.clone()))));
+    let result = store.invoke(inst, "main", vec![]).unwrap();
+    assert!(matches!(result, RunResult::Done(x) if x.is_empty()));
+    let recorder = recorder.borrow();
+    assert_eq!(recorder.pauses, 3);
+    assert_eq!(recorder.stored, [(Val::I32(16), Val::I32(42), 0), (Val::I32(20), Val::I32(7), 42)]);
+}
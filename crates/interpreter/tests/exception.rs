@@ -0,0 +1,125 @@
+// Copyright 2023 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(feature = "exception-handling")]
+
+use common::{encode, host};
+use wasefire_interpreter::*;
+
+mod common;
+
+const UNWIND: &str = r#"
+(module
+  (import "env" "read" (func $read (param i32) (result i32)))
+  (import "env" "error" (tag $error (param i32)))
+  (tag $small (param i32))
+  (tag $large (param i32 i32))
+  (func $check (param i32) (result i32)
+    (if (i32.lt_u (local.get 0) (i32.const 10))
+      (then (throw $small (local.get 0))))
+    (if (i32.gt_u (local.get 0) (i32.const 100))
+      (then (throw $large (local.get 0) (i32.const 100))))
+    (local.get 0))
+  (func (export "catch") (param i32) (result i32)
+    (try (result i32)
+      (do (call $check (local.get 0)))
+      (catch $small (i32.add (i32.const 1000)))
+      (catch $large (i32.sub))))
+  (func (export "catch_all") (param i32) (result i32)
+    (try (result i32)
+      (do (call $check (local.get 0)))
+      (catch $large (drop) (drop) (i32.const -1))
+      (catch_all (i32.const -2))))
+  (func (export "rethrow") (param i32) (result i32)
+    (try (result i32)
+      (do
+        (try (result i32)
+          (do (call $check (local.get 0)))
+          (catch_all (rethrow 0))))
+      (catch $small)))
+  (func (export "delegate") (param i32) (result i32)
+    (try (result i32)
+      (do
+        (try (result i32)
+          (do
+            (try (result i32)
+              (do (call $check (local.get 0)))
+              (delegate 1)))
+          (catch $small (drop) (i32.const -1))))
+      (catch $small)))
+  (func (export "host") (param i32) (result i32)
+    (try (result i32)
+      (do (call $read (local.get 0)))
+      (catch $error (i32.const 1000) (i32.add)))))
+"#;
+
+fn linker() -> Linker<'static> {
+    let mut linker = common::linker(&[("read", 1, 1)]);
+    linker.link_tag("env", "error", 1).unwrap();
+    linker
+}
+
+fn invoke(store: &mut Store, inst: InstId, name: &str, arg: u32) -> Result<Val, Error> {
+    match store.invoke(inst, name, vec![Val::I32(arg)])? {
+        RunResult::Done(x) => Ok(x[0]),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn catch() {
+    let wasm = encode(UNWIND);
+    let linker = linker();
+    let mut store = Store::new(&linker);
+    let inst = store.instantiate(Module::new(&wasm).unwrap(), &mut []).unwrap();
+    assert_eq!(invoke(&mut store, inst, "catch", 42), Ok(Val::I32(42)));
+    assert_eq!(invoke(&mut store, inst, "catch", 3), Ok(Val::I32(1003)));
+    assert_eq!(invoke(&mut store, inst, "catch", 142), Ok(Val::I32(42)));
+    assert_eq!(invoke(&mut store, inst, "catch_all", 142), Ok(Val::I32(-1i32 as u32)));
+    assert_eq!(invoke(&mut store, inst, "catch_all", 3), Ok(Val::I32(-2i32 as u32)));
+    assert_eq!(invoke(&mut store, inst, "rethrow", 3), Ok(Val::I32(3)));
+    // The exception skips the handlers of the block between the `delegate` and its label.
+    assert_eq!(invoke(&mut store, inst, "delegate", 3), Ok(Val::I32(3)));
+}
+
+#[test]
+fn uncaught() {
+    let wasm = encode(UNWIND);
+    let linker = linker();
+    let mut store = Store::new(&linker);
+    let inst = store.instantiate(Module::new(&wasm).unwrap(), &mut []).unwrap();
+    let result = invoke(&mut store, inst, "rethrow", 142);
+    assert_eq!(result, Err(Error::Trap(TrapReason::UncaughtException)));
+    // The core dump is taken where the exception was rethrown.
+    assert_eq!(store.core_dump().unwrap().frames.len(), 1);
+}
+
+#[test]
+fn host_throw() {
+    let wasm = encode(UNWIND);
+    let linker = linker();
+    let mut store = Store::new(&linker);
+    let inst = store.instantiate(Module::new(&wasm).unwrap(), &mut []).unwrap();
+    let call = host(store.invoke(inst, "host", vec![Val::I32(7)]).unwrap());
+    let result = call.throw(0, &[Val::I32(7)]).unwrap();
+    assert!(matches!(result, RunResult::Done(x) if x == [Val::I32(1007)]));
+}
+
+#[test]
+fn invalid() {
+    let wasm = encode("(module (func (block (rethrow 0))))");
+    assert_eq!(Module::new(&wasm).err(), Some(Error::Invalid));
+    let wasm = encode("(module (tag (param i64)) (func (throw 0 (i32.const 0))))");
+    assert_eq!(Module::new(&wasm).err(), Some(Error::Invalid));
+}
@@ -0,0 +1,67 @@
+// Copyright 2023 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use common::encode;
+use wasefire_interpreter::*;
+
+mod common;
+
+const SUM: &str = r#"
+(module
+  (func (export "sum") (param i32) (result i32) (local i32)
+    (block
+      (loop
+        (br_if 1 (i32.eqz (local.get 0)))
+        (local.set 1 (i32.add (local.get 1) (local.get 0)))
+        (local.set 0 (i32.sub (local.get 0) (i32.const 1)))
+        (br 0)))
+    (local.get 1)))
+"#;
+
+#[test]
+fn unlimited() {
+    let wasm = encode(SUM);
+    let mut store = Store::default();
+    let inst = store.instantiate(Module::new(&wasm).unwrap(), &mut []).unwrap();
+    assert_eq!(store.fuel(), None);
+    let result = store.invoke(inst, "sum", vec![Val::I32(100)]).unwrap();
+    assert!(matches!(result, RunResult::Done(x) if x == [Val::I32(5050)]));
+}
+
+#[test]
+fn out_of_fuel() {
+    let wasm = encode(SUM);
+    let mut store = Store::default();
+    let inst = store.instantiate(Module::new(&wasm).unwrap(), &mut []).unwrap();
+    store.set_fuel(Some(100));
+    let result = store.invoke(inst, "sum", vec![Val::I32(100)]).unwrap();
+    assert!(matches!(result, RunResult::OutOfFuel));
+    assert_eq!(store.fuel(), Some(0));
+    assert!(store.is_out_of_fuel());
+    assert!(store.last_call().is_none());
+    // Nothing else can run until the thread is resumed.
+    assert_eq!(store.invoke(inst, "sum", vec![Val::I32(1)]).err(), Some(Error::Invalid));
+    let mut count = 1;
+    let result = loop {
+        store.set_fuel(Some(100));
+        match store.resume().unwrap() {
+            RunResult::OutOfFuel => count += 1,
+            x => break x.forget(),
+        }
+    };
+    assert!(matches!(result, RunAnswer::Done(x) if x == [Val::I32(5050)]));
+    assert!(count > 10);
+    assert!(!store.is_out_of_fuel());
+    assert_eq!(store.resume().err(), Some(Error::Invalid));
+}
@@ -0,0 +1,119 @@
+// Copyright 2023 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use common::{encode, host, Memory};
+use wasefire_interpreter::*;
+
+mod common;
+
+const COUNTER: &str = r#"
+(module
+  (import "env" "count" (global $count (mut i32)))
+  (import "env" "mem" (memory 1))
+  (import "env" "table" (table 2 funcref))
+  (import "env" "log" (func $log (param i32)))
+  (elem (i32.const 1) $log)
+  (func (export "main") (result i32)
+    (global.set $count (i32.add (global.get $count) (i32.const 1)))
+    (i32.store8 (i32.const 3) (global.get $count))
+    (call_indirect (param i32) (global.get $count) (i32.const 1))
+    (i32.load8_u (i32.const 3))))
+"#;
+
+fn linker() -> Linker<'static> {
+    let mut linker = common::linker(&[("log", 1, 0)]);
+    let type_ = GlobalType { mutable: Mut::Var, value: ValType::I32 };
+    linker.link_global("env", "count", type_, Val::I32(41)).unwrap();
+    linker.link_mem("env", "mem", Limits { min: 1, max: 1 }).unwrap();
+    let limits = Limits { min: 2, max: 2 };
+    linker.link_table("env", "table", TableType { limits, item: RefType::FuncRef }).unwrap();
+    linker
+}
+
+fn run_main(store: &mut Store, inst: InstId) -> Val {
+    let call = host(store.invoke(inst, "main", vec![]).unwrap());
+    assert_eq!(call.index(), 0);
+    assert_eq!(call.args(), [Val::I32(42)]);
+    match call.resume(&[]).unwrap() {
+        RunResult::Done(x) => x[0],
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn shared_linker() {
+    let wasm = encode(COUNTER);
+    let linker = linker();
+    let mut memories = [Memory([0; 16]), Memory([0; 16])];
+    let [mem1, mem2] = &mut memories;
+    let mut store1 = Store::new(&linker);
+    let mut store2 = Store::new(&linker);
+    store1.set_host_mem("env", "mem", &mut mem1.0).unwrap();
+    store2.set_host_mem("env", "mem", &mut mem2.0).unwrap();
+    let inst1 = store1.instantiate(Module::new(&wasm).unwrap(), &mut []).unwrap();
+    let inst2 = store2.instantiate(Module::new(&wasm).unwrap(), &mut []).unwrap();
+    // Each store has its own instance of the host definitions.
+    assert_eq!(run_main(&mut store1, inst1), Val::I32(42));
+    assert_eq!(run_main(&mut store2, inst2), Val::I32(42));
+    drop((store1, store2));
+    assert_eq!(memories[0].0[3], 42);
+    assert_eq!(memories[1].0[3], 42);
+}
+
+#[test]
+fn missing_host_mem() {
+    let wasm = encode(COUNTER);
+    let linker = linker();
+    let mut store = Store::new(&linker);
+    let inst = store.instantiate(Module::new(&wasm).unwrap(), &mut []).unwrap();
+    let result = store.invoke(inst, "main", vec![]);
+    assert_eq!(result.err(), Some(Error::Trap(TrapReason::MemoryOutOfBounds)));
+}
+
+#[test]
+fn link_order() {
+    let mut linker = Linker::default();
+    linker.link_func("env", "foo", 0, 0).unwrap();
+    assert_eq!(linker.link_func("env", "bar", 0, 0), Err(Error::Invalid));
+    // Each kind has its own order.
+    linker.link_mem("env", "bar", Limits { min: 0, max: 1 }).unwrap();
+    let type_ = GlobalType { mutable: Mut::Const, value: ValType::I64 };
+    assert_eq!(linker.link_global("env", "baz", type_, Val::I32(0)), Err(Error::Invalid));
+}
+
+#[cfg(feature = "multi-memory")]
+#[test]
+fn shared_host_mem() {
+    const MAILBOX: &str = r#"
+(module
+  (import "env" "shared" (memory $shared 1 1))
+  (memory $private 1)
+  (func (export "main") (param i32)
+    (i32.store8 $private (i32.const 0) (local.get 0))
+    (memory.copy $shared $private (i32.const 2) (i32.const 0) (i32.const 1))))
+"#;
+    let wasm = encode(MAILBOX);
+    let mut linker = Linker::default();
+    linker.link_mem("env", "shared", Limits { min: 1, max: 1 }).unwrap();
+    let mut shared = Memory([0; 16]);
+    let mut private = Memory([0; 16]);
+    let mut store = Store::new(&linker);
+    store.set_host_mem("env", "shared", &mut shared.0).unwrap();
+    let inst = store.instantiate(Module::new(&wasm).unwrap(), &mut private.0).unwrap();
+    let result = store.invoke(inst, "main", vec![Val::I32(42)]).unwrap();
+    assert!(matches!(result, RunResult::Done(x) if x.is_empty()));
+    drop(store);
+    assert_eq!(shared.0[2], 42);
+    assert_eq!(private.0[0], 42);
+}
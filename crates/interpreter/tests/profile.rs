@@ -0,0 +1,60 @@
+// Copyright 2023 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(feature = "profile")]
+
+use common::{encode, host, linker};
+use wasefire_interpreter::*;
+
+mod common;
+
+const CALLS: &str = r#"
+(module
+  (import "env" "wait" (func $wait))
+  (func $leaf (result i32)
+    (i32.const 1))
+  (func $twice (result i32)
+    (i32.add (call $leaf) (call $leaf)))
+  (func (export "main") (result i32)
+    (call $wait)
+    (i32.add (call $twice) (call $leaf))))
+"#;
+
+#[test]
+fn counts() {
+    let wasm = encode(CALLS);
+    let linker = linker(&[("wait", 0, 0)]);
+    let mut store = Store::new(&linker);
+    let inst = store.instantiate(Module::new(&wasm).unwrap(), &mut []).unwrap();
+    assert_eq!(store.profile(), None);
+    store.set_profile(true);
+    let call = host(store.invoke(inst, "main", vec![]).unwrap());
+    let result = call.resume(&[]).unwrap();
+    assert!(matches!(result, RunResult::Done(x) if x == [Val::I32(3)]));
+    let profile = store.profile().unwrap();
+    let count = |stack: &[u32]| {
+        let stack: Vec<_> = stack.iter().map(|&x| (inst, x)).collect();
+        profile.iter().find(|x| x.stack == stack).map(|x| x.count)
+    };
+    // The function indices include the imported function.
+    assert_eq!(profile.len(), 4);
+    assert_eq!(count(&[3]), Some(5));
+    assert_eq!(count(&[3, 2]), Some(4));
+    assert_eq!(count(&[3, 2, 1]), Some(4));
+    assert_eq!(count(&[3, 1]), Some(2));
+    // Stopping keeps the profile.
+    store.set_profile(false);
+    store.invoke(inst, "main", vec![]).unwrap();
+    assert_eq!(store.profile().unwrap(), profile);
+}
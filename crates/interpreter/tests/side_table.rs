@@ -0,0 +1,77 @@
+// Copyright 2023 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use common::encode;
+use wasefire_interpreter::*;
+
+mod common;
+
+const FACT: &str = r#"
+(module
+  (func (export "fact") (param i32) (result i32)
+    (local i32)
+    (local.set 1 (i32.const 1))
+    (block
+      (loop
+        (br_if 1 (i32.eqz (local.get 0)))
+        (local.set 1 (i32.mul (local.get 0) (local.get 1)))
+        (local.set 0 (i32.sub (local.get 0) (i32.const 1)))
+        (br 0)))
+    (local.get 1)))
+"#;
+
+#[test]
+fn round_trip() {
+    let wasm = encode(FACT);
+    let side_table = Module::new(&wasm).unwrap().serialize_side_table();
+    // SAFETY: The module is valid and the side-table is its own.
+    let module = unsafe { Module::new_with_side_table(&wasm, &side_table) }.unwrap();
+    let mut store = Store::default();
+    let inst = store.instantiate(module, &mut []).unwrap();
+    let result = store.invoke(inst, "fact", vec![Val::I32(5)]).unwrap();
+    assert!(matches!(result, RunResult::Done(x) if x == [Val::I32(120)]));
+}
+
+#[test]
+fn malformed() {
+    let wasm = encode(FACT);
+    let mut side_table = Module::new(&wasm).unwrap().serialize_side_table();
+    side_table.pop();
+    // SAFETY: The side-table is malformed and thus rejected.
+    let module = unsafe { Module::new_with_side_table(&wasm, &side_table) };
+    assert_eq!(module.err(), Some(Error::Invalid));
+}
+
+#[test]
+fn non_monotonic() {
+    let wasm = encode(r#"(module (func (block (br 0))) (func (block (br 0))))"#);
+    let mut side_table = Module::new(&wasm).unwrap().serialize_side_table();
+    // Swap the first entries of the 2 functions.
+    let (first, second) = side_table[4 .. 12].split_at_mut(4);
+    first.swap_with_slice(second);
+    assert_ne!(side_table[4 .. 8], side_table[8 .. 12]);
+    // SAFETY: The side-table is malformed and thus rejected.
+    let module = unsafe { Module::new_with_side_table(&wasm, &side_table) };
+    assert_eq!(module.err(), Some(Error::Invalid));
+}
+
+#[test]
+fn wrong_module() {
+    let wasm = encode(FACT);
+    let other = encode(r#"(module (func) (func))"#);
+    let side_table = Module::new(&other).unwrap().serialize_side_table();
+    // SAFETY: The side-table doesn't have one entry per function and is thus rejected.
+    let module = unsafe { Module::new_with_side_table(&wasm, &side_table) };
+    assert_eq!(module.err(), Some(Error::Invalid));
+}
@@ -0,0 +1,79 @@
+// Copyright 2023 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use common::{encode, host, linker, Memory};
+use wasefire_interpreter::*;
+
+mod common;
+
+const COUNTER: &str = r#"
+(module
+  (import "env" "wait" (func $wait (param i32) (result i32)))
+  (memory 1)
+  (global $count (mut i32) (i32.const 0))
+  (func (export "main") (result i32)
+    (local $i i32)
+    (loop $loop
+      (global.set $count (i32.add (global.get $count) (i32.const 1)))
+      (i32.store (i32.const 16) (global.get $count))
+      (local.set $i (call $wait (global.get $count)))
+      (br_if $loop (local.get $i)))
+    (i32.load (i32.const 16))))
+"#;
+
+#[test]
+fn round_trip() {
+    let wasm = encode(COUNTER);
+    let linker = linker(&[("wait", 1, 1)]);
+    let mut memory = Memory([0; 0x10000]);
+    let mut store = Store::new(&linker);
+    let inst = store.instantiate(Module::new(&wasm).unwrap(), &mut memory.0).unwrap();
+    let mut call = host(store.invoke(inst, "main", vec![]).unwrap());
+    for i in 1 .. 3 {
+        assert_eq!(call.args(), [Val::I32(i)]);
+        call = host(call.resume(&[Val::I32(1)]).unwrap());
+    }
+    let snapshot = store.snapshot();
+    drop(store);
+
+    // Restore in a fresh store after a power cycle.
+    let mut memory = Memory([0; 0x10000]);
+    let mut store = Store::new(&linker);
+    store.instantiate(Module::new(&wasm).unwrap(), &mut memory.0).unwrap();
+    assert!(store.last_call().is_none());
+    unsafe { store.restore(&snapshot) }.unwrap();
+    let call = store.last_call().unwrap();
+    assert_eq!(call.args(), [Val::I32(3)]);
+    assert_eq!(call.backtrace().len(), 1);
+    let result = call.resume(&[Val::I32(0)]).unwrap();
+    assert!(matches!(result, RunResult::Done(x) if x == [Val::I32(3)]));
+    drop(store);
+    assert_eq!(memory.0[16], 3);
+}
+
+#[test]
+fn mismatch() {
+    let wasm = encode(COUNTER);
+    let linker = linker(&[("wait", 1, 1)]);
+    let mut memory = Memory([0; 0x10000]);
+    let mut store = Store::new(&linker);
+    store.instantiate(Module::new(&wasm).unwrap(), &mut memory.0).unwrap();
+    let snapshot = store.snapshot();
+    let mut other = Store::new(&linker);
+    // The store has no instance.
+    assert_eq!(unsafe { other.restore(&snapshot) }, Err(Error::Invalid));
+    // The snapshot is truncated.
+    let snapshot = &snapshot[.. snapshot.len() - 1];
+    assert_eq!(unsafe { store.restore(snapshot) }, Err(Error::Invalid));
+}
@@ -51,7 +51,7 @@ fn test(name: &str) {
             WastDirective::AssertReturn { exec, results, .. } => {
                 assert_return(&mut env, exec, results)
             }
-            WastDirective::AssertTrap { exec, .. } => assert_trap(&mut env, exec),
+            WastDirective::AssertTrap { exec, message, .. } => assert_trap(&mut env, exec, message),
             WastDirective::Invoke(invoke) => assert_invoke(&mut env, invoke),
             WastDirective::AssertExhaustion { call, .. } => assert_exhaustion(&mut env, call),
             WastDirective::Register { name, module, .. } => env.register_name(name, module),
@@ -74,6 +74,12 @@ fn pool_size(name: &str) -> usize {
         "memory_grow" => 0x400000,
         "memory_init" => 0x400000,
         "memory_trap" => 0x200000,
+        "simd_address" => 0x200000,
+        "simd_const" => 0x400000,
+        "simd_load_extend" => 0x200000,
+        "simd_load_splat" => 0x200000,
+        "simd_load_zero" => 0x200000,
+        _ if name.starts_with("proposals/multi-memory/") => 0x2000000,
         _ => 0x100000,
     }
 }
@@ -90,7 +96,14 @@ fn mem_size(name: &str) -> usize {
         "memory_grow" => 0x80000,
         "memory_init" => 0x20000,
         "memory_trap" => 0x10000,
+        "simd_address" => 0x10000,
+        "simd_load_extend" => 0x10000,
+        "simd_load_splat" => 0x10000,
+        "simd_load_zero" => 0x10000,
         "spectest" => 0x10000,
+        "proposals/multi-memory/linking3" => 0x60000,
+        "proposals/multi-memory/memory_grow" => 0x80000,
+        _ if name.starts_with("proposals/multi-memory/") => 0x40000,
         _ => 0x1000,
     }
 }
@@ -145,7 +158,7 @@ impl<'m> Env<'m> {
     fn invoke(&mut self, inst_id: InstId, name: &str, args: Vec<Val>) -> Result<Vec<Val>, Error> {
         Ok(match self.store.invoke(inst_id, name, args)? {
             RunResult::Done(x) => x,
-            RunResult::Host { .. } => unreachable!(),
+            RunResult::Host { .. } | RunResult::OutOfFuel => unreachable!(),
         })
     }
 
@@ -275,7 +288,7 @@ fn assert_return(env: &mut Env, exec: WastExecute, expected: Vec<WastRet>) {
             #[cfg(feature = "float-types")]
             (F64(x), C(W::F64(_))) => assert!(f64::from_bits(x).is_nan()),
             #[cfg(feature = "vector-types")]
-            (V128(_), _) => unimplemented!(),
+            (V128(x), C(W::V128(y))) => assert_v128(x, y),
             (Null(RefType::ExternRef), C(W::RefNull(None | Some(HeapType::Extern)))) => (),
             (Null(RefType::FuncRef), C(W::RefNull(None | Some(HeapType::Func)))) => (),
             (Ref(_), _) => unimplemented!(),
@@ -285,8 +298,41 @@ fn assert_return(env: &mut Env, exec: WastExecute, expected: Vec<WastRet>) {
     }
 }
 
-fn assert_trap(env: &mut Env, exec: WastExecute) {
-    assert_eq!(wast_execute(env, exec), Err(Error::Trap));
+#[cfg(feature = "vector-types")]
+fn assert_v128(x: u128, y: wast::core::V128Pattern) {
+    use wast::core::{NanPattern as NP, V128Const, V128Pattern as P};
+    let x = x.to_le_bytes();
+    match y {
+        P::I8x16(y) => assert_eq!(x, V128Const::I8x16(y).to_le_bytes()),
+        P::I16x8(y) => assert_eq!(x, V128Const::I16x8(y).to_le_bytes()),
+        P::I32x4(y) => assert_eq!(x, V128Const::I32x4(y).to_le_bytes()),
+        P::I64x2(y) => assert_eq!(x, V128Const::I64x2(y).to_le_bytes()),
+        P::F32x4(y) => {
+            for (x, y) in x.chunks(4).zip(y) {
+                let x = u32::from_le_bytes(x.try_into().unwrap());
+                match y {
+                    NP::Value(y) => assert_eq!(x, y.bits),
+                    _ => assert!(f32::from_bits(x).is_nan()),
+                }
+            }
+        }
+        P::F64x2(y) => {
+            for (x, y) in x.chunks(8).zip(y) {
+                let x = u64::from_le_bytes(x.try_into().unwrap());
+                match y {
+                    NP::Value(y) => assert_eq!(x, y.bits),
+                    _ => assert!(f64::from_bits(x).is_nan()),
+                }
+            }
+        }
+    }
+}
+
+fn assert_trap(env: &mut Env, exec: WastExecute, message: &str) {
+    match wast_execute(env, exec) {
+        Err(Error::Trap(reason)) => assert!(message.starts_with(&reason.to_string()), "{message}"),
+        x => panic!("{x:?} is not a trap"),
+    }
 }
 
 fn assert_invoke(env: &mut Env, invoke: WastInvoke) {
@@ -313,7 +359,7 @@ fn assert_invalid(mut wat: QuoteWat) {
 fn assert_exhaustion(env: &mut Env, call: WastInvoke) {
     let result = wast_invoke(env, call);
     if !matches!(result, Err(Error::Unsupported)) {
-        assert_eq!(result, Err(Error::Trap));
+        assert_eq!(result, Err(Error::Trap(TrapReason::StackExhausted)));
     }
 }
 
@@ -363,6 +409,8 @@ fn wast_arg_core(core: WastArgCore) -> Val {
         WastArgCore::F32(x) => Val::F32(x.bits),
         #[cfg(feature = "float-types")]
         WastArgCore::F64(x) => Val::F64(x.bits),
+        #[cfg(feature = "vector-types")]
+        WastArgCore::V128(x) => Val::V128(u128::from_le_bytes(x.to_le_bytes())),
         WastArgCore::RefNull(HeapType::Func) => Val::Null(RefType::FuncRef),
         WastArgCore::RefNull(HeapType::Extern) => Val::Null(RefType::ExternRef),
         WastArgCore::RefExtern(x) => Val::RefExtern(x as usize),
@@ -382,7 +430,11 @@ macro_rules! test {
 
 test!(address);
 test!(align);
-test!(binary);
+// The multi-memory proposal has its own version of this test.
+test!(
+    #[cfg(not(feature = "multi-memory"))]
+    binary
+);
 test!(binary_leb128, "binary-leb128");
 test!(block);
 test!(br);
@@ -398,6 +450,29 @@ test!(custom);
 test!(data);
 test!(elem);
 test!(endianness);
+// The other tests of the exception-handling proposal use the `try_table` and `exnref` syntax, while
+// the interpreter implements the `try` instructions. The multi-memory proposal allows multiple
+// memories, which the binary and imports tests reject.
+test!(
+    #[cfg(all(feature = "exception-handling", not(feature = "multi-memory")))]
+    exception_handling_binary,
+    "proposals/exception-handling/binary"
+);
+test!(
+    #[cfg(feature = "exception-handling")]
+    exception_handling_exports,
+    "proposals/exception-handling/exports"
+);
+test!(
+    #[cfg(all(feature = "exception-handling", not(feature = "multi-memory")))]
+    exception_handling_imports,
+    "proposals/exception-handling/imports"
+);
+test!(
+    #[cfg(feature = "exception-handling")]
+    exception_handling_tag,
+    "proposals/exception-handling/tag"
+);
 test!(exports);
 test!(f32);
 test!(f32_bitwise);
@@ -417,7 +492,11 @@ test!(global);
 test!(i32);
 test!(i64);
 test!(if_, "if");
-test!(imports);
+// The multi-memory proposal has its own version of this test.
+test!(
+    #[cfg(not(feature = "multi-memory"))]
+    imports
+);
 test!(inline_module, "inline-module");
 test!(int_exprs);
 test!(int_literals);
@@ -429,7 +508,11 @@ test!(local_get);
 test!(local_set);
 test!(local_tee);
 test!(loop_, "loop");
-test!(memory);
+// The multi-memory proposal has its own version of this test.
+test!(
+    #[cfg(not(feature = "multi-memory"))]
+    memory
+);
 test!(memory_copy);
 test!(memory_fill);
 test!(memory_grow);
@@ -439,11 +522,313 @@ test!(memory_size);
 test!(memory_trap);
 test!(names);
 test!(nop);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_address0,
+    "proposals/multi-memory/address0"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_address1,
+    "proposals/multi-memory/address1"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_align0,
+    "proposals/multi-memory/align0"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_binary,
+    "proposals/multi-memory/binary"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_binary0,
+    "proposals/multi-memory/binary0"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_data,
+    "proposals/multi-memory/data"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_data0,
+    "proposals/multi-memory/data0"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_data1,
+    "proposals/multi-memory/data1"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_data_drop0,
+    "proposals/multi-memory/data_drop0"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_exports0,
+    "proposals/multi-memory/exports0"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_float_exprs0,
+    "proposals/multi-memory/float_exprs0"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_float_exprs1,
+    "proposals/multi-memory/float_exprs1"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_float_memory0,
+    "proposals/multi-memory/float_memory0"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_imports,
+    "proposals/multi-memory/imports"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_imports0,
+    "proposals/multi-memory/imports0"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_imports1,
+    "proposals/multi-memory/imports1"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_imports2,
+    "proposals/multi-memory/imports2"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_imports3,
+    "proposals/multi-memory/imports3"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_imports4,
+    "proposals/multi-memory/imports4"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_linking0,
+    "proposals/multi-memory/linking0"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_linking1,
+    "proposals/multi-memory/linking1"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_linking2,
+    "proposals/multi-memory/linking2"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_linking3,
+    "proposals/multi-memory/linking3"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_load,
+    "proposals/multi-memory/load"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_load0,
+    "proposals/multi-memory/load0"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_load1,
+    "proposals/multi-memory/load1"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_load2,
+    "proposals/multi-memory/load2"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_memory,
+    "proposals/multi-memory/memory"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_memory_multi,
+    "proposals/multi-memory/memory-multi"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_memory_copy0,
+    "proposals/multi-memory/memory_copy0"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_memory_copy1,
+    "proposals/multi-memory/memory_copy1"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_memory_fill0,
+    "proposals/multi-memory/memory_fill0"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_memory_grow,
+    "proposals/multi-memory/memory_grow"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_memory_init0,
+    "proposals/multi-memory/memory_init0"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_memory_size,
+    "proposals/multi-memory/memory_size"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_memory_size0,
+    "proposals/multi-memory/memory_size0"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_memory_size1,
+    "proposals/multi-memory/memory_size1"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_memory_size2,
+    "proposals/multi-memory/memory_size2"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_memory_size3,
+    "proposals/multi-memory/memory_size3"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_memory_trap0,
+    "proposals/multi-memory/memory_trap0"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_memory_trap1,
+    "proposals/multi-memory/memory_trap1"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_simd_memory_multi,
+    "proposals/multi-memory/simd_memory-multi"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_start0,
+    "proposals/multi-memory/start0"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_store,
+    "proposals/multi-memory/store"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_store0,
+    "proposals/multi-memory/store0"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_store1,
+    "proposals/multi-memory/store1"
+);
+test!(
+    #[cfg(feature = "multi-memory")]
+    multi_memory_traps0,
+    "proposals/multi-memory/traps0"
+);
 test!(ref_func);
 test!(ref_is_null);
 test!(ref_null);
 test!(return_, "return");
+test!(
+    #[cfg(feature = "tail-call")]
+    return_call,
+    "proposals/tail-call/return_call"
+);
+test!(
+    #[cfg(feature = "tail-call")]
+    return_call_indirect,
+    "proposals/tail-call/return_call_indirect"
+);
 test!(select);
+test!(simd_address);
+test!(simd_align);
+test!(simd_bit_shift);
+test!(simd_bitwise);
+test!(simd_boolean);
+test!(simd_const);
+test!(simd_conversions);
+test!(simd_f32x4);
+test!(simd_f32x4_arith);
+test!(simd_f32x4_cmp);
+test!(simd_f32x4_pmin_pmax);
+test!(simd_f32x4_rounding);
+test!(simd_f64x2);
+test!(simd_f64x2_arith);
+test!(simd_f64x2_cmp);
+test!(simd_f64x2_pmin_pmax);
+test!(simd_f64x2_rounding);
+test!(simd_i16x8_arith);
+test!(simd_i16x8_arith2);
+test!(simd_i16x8_cmp);
+test!(simd_i16x8_extadd_pairwise_i8x16);
+test!(simd_i16x8_extmul_i8x16);
+test!(simd_i16x8_q15mulr_sat_s);
+test!(simd_i16x8_sat_arith);
+test!(simd_i32x4_arith);
+test!(simd_i32x4_arith2);
+test!(simd_i32x4_cmp);
+test!(simd_i32x4_dot_i16x8);
+test!(simd_i32x4_extadd_pairwise_i16x8);
+test!(simd_i32x4_extmul_i16x8);
+test!(simd_i32x4_trunc_sat_f32x4);
+test!(simd_i32x4_trunc_sat_f64x2);
+test!(simd_i64x2_arith);
+test!(simd_i64x2_arith2);
+test!(simd_i64x2_cmp);
+test!(simd_i64x2_extmul_i32x4);
+test!(simd_i8x16_arith);
+test!(simd_i8x16_arith2);
+test!(simd_i8x16_cmp);
+test!(simd_i8x16_sat_arith);
+test!(simd_int_to_int_extend);
+test!(simd_lane);
+test!(simd_linking);
+test!(simd_load);
+test!(simd_load16_lane);
+test!(simd_load32_lane);
+test!(simd_load64_lane);
+test!(simd_load8_lane);
+test!(simd_load_extend);
+test!(simd_load_splat);
+test!(simd_load_zero);
+test!(simd_splat);
+test!(simd_store);
+test!(simd_store16_lane);
+test!(simd_store32_lane);
+test!(simd_store64_lane);
+test!(simd_store8_lane);
 test!(skip_stack_guard_page, "skip-stack-guard-page");
 test!(stack);
 test!(start);
@@ -464,6 +849,23 @@ test!(
     #[ignore]
     tokens
 );
+test!(
+    #[cfg(feature = "threads")]
+    threads_atomic,
+    "proposals/threads/atomic"
+);
+test!(
+    #[cfg(feature = "threads")]
+    threads_exports,
+    "proposals/threads/exports"
+);
+// The threads imports test needs a shared memory in the spectest module.
+// The multi-memory proposal allows multiple memories, which this test rejects.
+test!(
+    #[cfg(all(feature = "threads", not(feature = "multi-memory")))]
+    threads_memory,
+    "proposals/threads/memory"
+);
 test!(traps);
 test!(type_, "type");
 test!(unreachable);
@@ -0,0 +1,150 @@
+// Copyright 2023 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use common::{encode, host, linker};
+use wasefire_interpreter::*;
+
+mod common;
+
+const DIV: &str = r#"
+(module
+  (func $div (param i32 i32) (result i32)
+    (i32.div_u (local.get 0) (local.get 1)))
+  (func (export "main") (param i32) (result i32)
+    (call $div (i32.const 1) (local.get 0))))
+"#;
+
+const ABORT: &str = r#"
+(module
+  (import "env" "abort" (func $abort))
+  (func $fail
+    (call $abort))
+  (func (export "main")
+    (call $fail)))
+"#;
+
+const DEPTH: &str = r#"
+(module
+  (func $depth (export "main") (param i32) (result i32)
+    (if (result i32) (local.get 0)
+      (then (i32.add (i32.const 1) (call $depth (i32.sub (local.get 0) (i32.const 1)))))
+      (else (i32.const 0)))))
+"#;
+
+#[test]
+fn no_trap() {
+    let wasm = encode(DIV);
+    let mut store = Store::default();
+    let inst = store.instantiate(Module::new(&wasm).unwrap(), &mut []).unwrap();
+    let result = store.invoke(inst, "main", vec![Val::I32(1)]).unwrap();
+    assert!(matches!(result, RunResult::Done(x) if x == [Val::I32(1)]));
+    assert_eq!(store.core_dump(), None);
+}
+
+#[test]
+fn core_dump() {
+    let wasm = encode(DIV);
+    let mut store = Store::default();
+    let inst = store.instantiate(Module::new(&wasm).unwrap(), &mut []).unwrap();
+    let result = store.invoke(inst, "main", vec![Val::I32(0)]);
+    assert_eq!(result.err(), Some(Error::Trap(TrapReason::IntegerDivideByZero)));
+    let dump = store.core_dump().unwrap();
+    assert_eq!(dump.reason, TrapReason::IntegerDivideByZero);
+    let frames: Vec<_> = dump.frames.iter().map(|x| (x.func, wasm[x.offset])).collect();
+    // The innermost frame is at i32.div_u and the outermost frame is after the call.
+    assert_eq!(frames, [(0, 0x6e), (1, 0x0b)]);
+    assert!(dump.frames.iter().all(|x| x.inst == dump.frames[0].inst));
+}
+
+#[test]
+fn func_name() {
+    let wasm = encode(DIV);
+    let mut store = Store::default();
+    let inst = store.instantiate(Module::new(&wasm).unwrap(), &mut []).unwrap();
+    assert_eq!(store.func_name(inst, 0), Some("div"));
+    assert_eq!(store.func_name(inst, 1), None);
+}
+
+#[test]
+fn host_backtrace() {
+    let wasm = encode(ABORT);
+    let linker = linker(&[("abort", 0, 0)]);
+    let mut store = Store::new(&linker);
+    let inst = store.instantiate(Module::new(&wasm).unwrap(), &mut []).unwrap();
+    let call = host(store.invoke(inst, "main", vec![]).unwrap());
+    let frames: Vec<_> = call.backtrace().iter().map(|x| (x.func, wasm[x.offset])).collect();
+    // Both frames are after their call.
+    assert_eq!(frames, [(1, 0x0b), (2, 0x0b)]);
+    assert_eq!(store.func_name(inst, 1), Some("fail"));
+}
+
+#[test]
+fn stack_limit() {
+    let wasm = encode(DEPTH);
+    let mut store = Store::default();
+    assert_eq!(store.stack_limit(), DEFAULT_STACK_LIMIT);
+    let inst = store.instantiate(Module::new(&wasm).unwrap(), &mut []).unwrap();
+    let result = store.invoke(inst, "main", vec![Val::I32(10)]).unwrap();
+    assert!(matches!(result, RunResult::Done(x) if x == [Val::I32(10)]));
+    store.set_stack_limit(1000);
+    let result = store.invoke(inst, "main", vec![Val::I32(10)]);
+    assert_eq!(result.err(), Some(Error::Trap(TrapReason::StackExhausted)));
+    let result = store.invoke(inst, "main", vec![Val::I32(1)]).unwrap();
+    assert!(matches!(result, RunResult::Done(x) if x == [Val::I32(1)]));
+}
+
+#[cfg(feature = "tail-call")]
+#[test]
+fn tail_call_host_backtrace() {
+    const DOUBLE: &str = r#"
+(module
+  (import "env" "double" (func $double (param i32) (result i32)))
+  (func $count (param i32 i32) (result i32)
+    (if (result i32) (i32.eqz (local.get 0))
+      (then (local.get 1))
+      (else (return_call $count
+        (i32.sub (local.get 0) (i32.const 1))
+        (i32.add (local.get 1) (i32.const 1))))))
+  (func (export "main") (param i32) (result i32)
+    (return_call $double (call $count (local.get 0) (i32.const 0)))))
+"#;
+    let wasm = encode(DOUBLE);
+    let linker = linker(&[("double", 1, 1)]);
+    let mut store = Store::new(&linker);
+    let inst = store.instantiate(Module::new(&wasm).unwrap(), &mut []).unwrap();
+    let call = host(store.invoke(inst, "main", vec![Val::I32(21)]).unwrap());
+    assert_eq!(call.args(), [Val::I32(21)]);
+    // The tail calling frame is kept while the host runs.
+    assert_eq!(call.backtrace().len(), 1);
+    let x = call.args()[0].unwrap_i32() * 2;
+    let result = call.resume(&[Val::I32(x)]).unwrap();
+    assert!(matches!(result, RunResult::Done(x) if x == [Val::I32(42)]));
+}
+
+#[cfg(feature = "threads")]
+#[test]
+fn atomic_wait_unshared() {
+    const WAIT: &str = r#"
+(module
+  (memory 1 1)
+  (func (export "main") (result i32)
+    (memory.atomic.wait32 (i32.const 0) (i32.const 0) (i64.const 0))))
+"#;
+    let wasm = encode(WAIT);
+    let mut memory = common::Memory([0; 0x10000]);
+    let mut store = Store::default();
+    let inst = store.instantiate(Module::new(&wasm).unwrap(), &mut memory.0).unwrap();
+    let result = store.invoke(inst, "main", vec![]);
+    assert_eq!(result.err(), Some(Error::Trap(TrapReason::UnsharedMemory)));
+}
@@ -0,0 +1,70 @@
+// Copyright 2023 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use common::{encode, linker, Memory};
+use wasefire_interpreter::*;
+
+mod common;
+
+const LIB: &str = r#"
+(module
+  (table (export "table") 1 funcref)
+  (memory 1))
+"#;
+
+const APP: &str = r#"
+(module
+  (import "env" "wait" (func $wait))
+  (import "lib" "table" (table 1 funcref))
+  (memory 1)
+  (func $main (export "main")
+    (call $wait))
+  (func (export "register")
+    (table.set 0 (i32.const 0) (ref.func $main))))
+"#;
+
+#[test]
+fn unload() {
+    let lib_wasm = encode(LIB);
+    let app_wasm = encode(APP);
+    let linker = linker(&[("wait", 0, 0)]);
+    let mut lib_memory = Memory([0; 0x10000]);
+    let mut app_memory = Memory([0; 0x10000]);
+    let app_ptr = app_memory.0.as_ptr();
+    let mut store = Store::new(&linker);
+    let lib = store.instantiate(Module::new(&lib_wasm).unwrap(), &mut lib_memory.0).unwrap();
+    store.set_name(lib, "lib").unwrap();
+    let app = store.instantiate(Module::new(&app_wasm).unwrap(), &mut app_memory.0).unwrap();
+    // Only the last instance may be dropped.
+    assert_eq!(store.unload(lib).err(), Some(Error::Invalid));
+    // The instance may not be dropped while a thread executes in it.
+    assert!(matches!(store.invoke(app, "main", vec![]).unwrap(), RunResult::Host(_)));
+    assert_eq!(store.unload(app).err(), Some(Error::Invalid));
+    assert_eq!(store.reset().err(), Some(Error::Invalid));
+    let result = store.last_call().unwrap().resume(&[]).unwrap();
+    assert!(matches!(result, RunResult::Done(x) if x.is_empty()));
+    // The instance may not be dropped while the table of the library references it.
+    assert!(matches!(store.invoke(app, "register", vec![]).unwrap(), RunResult::Done(_)));
+    assert_eq!(store.unload(app).err(), Some(Error::Invalid));
+    // The memories are given back in order of instantiation.
+    let memories = store.reset().unwrap();
+    assert_eq!(memories.len(), 2);
+    assert_eq!(memories[1].as_ptr(), app_ptr);
+    assert_eq!(memories[1].len(), 0x10000);
+    // The store may be reused.
+    let memory = memories.into_iter().next().unwrap();
+    let lib = store.instantiate(Module::new(&lib_wasm).unwrap(), memory).unwrap();
+    let memory = store.unload(lib).unwrap();
+    assert_eq!(memory.len(), 0x10000);
+}
@@ -0,0 +1,150 @@
+// Copyright 2023 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Provides API for message passing between applets.
+//!
+//! An applet only receives messages from the applets it declares with [`senders!()`].
+//!
+//! [`senders!()`]: crate::senders
+
+use alloc::boxed::Box;
+use alloc::string::String;
+
+use wasefire_applet_api::ipc as api;
+/// Errors returned by message passing operations.
+pub use wasefire_applet_api::ipc::Error;
+
+/// Message received from another applet.
+#[derive(Debug)]
+pub struct Message {
+    /// Name of the sending applet.
+    pub sender: String,
+
+    /// Content of the message.
+    pub data: Box<[u8]>,
+}
+
+/// Sends a message to the applet with a given name.
+pub fn send(applet: &str, data: &[u8]) -> Result<(), Error> {
+    let params = api::send::Params {
+        applet_ptr: applet.as_ptr(),
+        applet_len: applet.len(),
+        ptr: data.as_ptr(),
+        len: data.len(),
+    };
+    let api::send::Results { res } = unsafe { api::send(params) };
+    Error::to_result(res)?;
+    Ok(())
+}
+
+/// Receives the oldest message of the mailbox, if any.
+pub fn receive() -> Result<Option<Message>, Error> {
+    let mut applet_ptr = core::ptr::null_mut();
+    let mut applet_len = 0;
+    let mut ptr = core::ptr::null_mut();
+    let mut len = 0;
+    let params = api::receive::Params {
+        applet_ptr: &mut applet_ptr,
+        applet_len: &mut applet_len,
+        ptr: &mut ptr,
+        len: &mut len,
+    };
+    let api::receive::Results { res } = unsafe { api::receive(params) };
+    match Error::to_result(res)? {
+        0 => Ok(None),
+        1 => {
+            let sender = unsafe { core::slice::from_raw_parts_mut(applet_ptr, applet_len) };
+            let sender = unsafe { Box::from_raw(sender) };
+            // The scheduler only accepts applet names in UTF-8.
+            let sender = String::from_utf8(sender.into_vec()).unwrap();
+            let data = unsafe { core::slice::from_raw_parts_mut(ptr, len) };
+            Ok(Some(Message { sender, data: unsafe { Box::from_raw(data) } }))
+        }
+        _ => unreachable!(),
+    }
+}
+
+/// Provides callback support for messages.
+pub trait Handler: 'static {
+    /// Called when the mailbox is not empty.
+    ///
+    /// Messages should be received with [`receive()`] until the mailbox is empty.
+    fn event(&self);
+}
+
+impl<F: Fn() + 'static> Handler for F {
+    fn event(&self) {
+        self()
+    }
+}
+
+/// Provides listening support for messages.
+#[must_use]
+pub struct Listener<H: Handler> {
+    handler: *const H,
+}
+
+impl<H: Handler> Listener<H> {
+    /// Starts listening for messages.
+    ///
+    /// The `handler` argument is the callback to be called when the mailbox is not empty. Note
+    /// that it may be an `Fn()` closure.
+    ///
+    /// The listener stops listening when dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// Listener::new(|| {
+    ///     while let Some(message) = ipc::receive().unwrap() {
+    ///         debug!("Received {:?} from {}", message.data, message.sender);
+    ///     }
+    /// })
+    /// ```
+    pub fn new(handler: H) -> Self {
+        let handler_func = Self::call;
+        let handler = Box::into_raw(Box::new(handler));
+        let handler_data = handler as *const u8;
+        unsafe { api::register(api::register::Params { handler_func, handler_data }) };
+        Listener { handler }
+    }
+
+    /// Stops listening.
+    ///
+    /// This is equivalent to calling `core::mem::drop()`.
+    pub fn stop(self) {
+        core::mem::drop(self);
+    }
+
+    /// Drops the listener but continues listening.
+    ///
+    /// This is equivalent to calling `core::mem::forget()`. This can be useful if the listener is
+    /// created deeply in the stack but the callback must continue processing messages until the
+    /// applet exits or traps.
+    pub fn leak(self) {
+        core::mem::forget(self);
+    }
+
+    extern "C" fn call(data: *const u8) {
+        let handler = unsafe { &*(data as *const H) };
+        handler.event();
+    }
+}
+
+impl<H: Handler> Drop for Listener<H> {
+    fn drop(&mut self) {
+        unsafe { api::unregister() };
+        drop(unsafe { Box::from_raw(self.handler as *mut H) });
+    }
+}
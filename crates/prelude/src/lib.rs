@@ -43,6 +43,7 @@ mod callback;
 pub mod clock;
 pub mod crypto;
 pub mod debug;
+pub mod ipc;
 pub mod led;
 pub mod rng;
 pub mod scheduling;
@@ -102,6 +103,74 @@ macro_rules! applet {
     };
 }
 
+/// Declares the parts of the API used by the applet.
+///
+/// The scheduler refuses to load the applet if it imports functions outside those parts. Each part
+/// is the path of a module (like `"store"` or `"usb::serial"`) or function (like `"syscall"`).
+/// Without this declaration, the applet may use the whole API.
+///
+/// # Examples
+///
+/// An applet only using LEDs and timers (and the panic handler) would declare:
+///
+/// ```ignore
+/// wasefire::capabilities!("clock", "debug", "led", "scheduling");
+/// ```
+#[cfg(not(feature = "native"))]
+#[macro_export]
+macro_rules! capabilities {
+    ($($path:literal),* $(,)?) => {
+        #[link_section = "wasefire-capabilities"]
+        #[used]
+        static CAPABILITIES: [u8; concat!($($path, "\n"),*).len()] =
+            $crate::__bytes(concat!($($path, "\n"),*));
+    };
+}
+#[cfg(feature = "native")]
+#[macro_export]
+macro_rules! capabilities {
+    ($($path:literal),* $(,)?) => {};
+}
+
+/// Declares the applets allowed to send messages to the applet.
+///
+/// The scheduler refuses messages from other applets. Without this declaration, the applet doesn't
+/// receive messages.
+///
+/// # Examples
+///
+/// An applet only accepting messages from the `ctap` applet would declare:
+///
+/// ```ignore
+/// wasefire::senders!("ctap");
+/// ```
+#[cfg(not(feature = "native"))]
+#[macro_export]
+macro_rules! senders {
+    ($($name:literal),* $(,)?) => {
+        #[link_section = "wasefire-ipc"]
+        #[used]
+        static SENDERS: [u8; concat!($($name, "\n"),*).len()] =
+            $crate::__bytes(concat!($($name, "\n"),*));
+    };
+}
+#[cfg(feature = "native")]
+#[macro_export]
+macro_rules! senders {
+    ($($name:literal),* $(,)?) => {};
+}
+
+#[doc(hidden)]
+pub const fn __bytes<const N: usize>(data: &str) -> [u8; N] {
+    let mut result = [0; N];
+    let mut i = 0;
+    while i < N {
+        result[i] = data.as_bytes()[i];
+        i += 1;
+    }
+    result
+}
+
 #[cfg(not(feature = "native"))]
 #[panic_handler]
 fn handle_panic(info: &core::panic::PanicInfo) -> ! {
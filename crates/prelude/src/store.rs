@@ -25,9 +25,10 @@ pub use wasefire_applet_api::store::Error;
 
 /// Inserts an entry in the store.
 ///
-/// The `key` argument must be a small integer (currently less than 4096). The `value` argument is
-/// the slice to associate with this key. If there was already a value, it is overwritten.
-/// Overwritten values are zeroized from flash.
+/// The `key` argument must be a small integer (currently less than 2047). Keys are private to the
+/// applet. The `value` argument is the slice to associate with this key. If there was already a
+/// value, it is overwritten. Overwritten values are zeroized from flash. The platform may limit the
+/// total size of the values of an applet.
 pub fn insert(key: usize, value: &[u8]) -> Result<(), Error> {
     let params = api::insert::Params { key, ptr: value.as_ptr(), len: value.len() };
     let api::insert::Results { res } = unsafe { api::insert(params) };
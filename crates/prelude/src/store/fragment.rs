@@ -23,7 +23,7 @@ use wasefire_applet_api::store::{fragment as api, Error};
 ///
 /// The entry will be fragmented over multiple keys within the provided range as needed.
 ///
-/// The range must be non-empty and end before 4096. The `value` argument is the slice to associate
+/// The range must be non-empty and end before 2047. The `value` argument is the slice to associate
 /// with this key. If there was already a value, it is overwritten. Overwritten values are zeroized
 /// from flash.
 pub fn insert(keys: Range<usize>, value: &[u8]) -> Result<(), Error> {
@@ -0,0 +1,319 @@
+// Copyright 2023 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Wasefire platform protocol.
+//!
+//! This crate defines the messages exchanged between a host tool (like the `wasefire` CLI) and the
+//! platform. The host sends a [`Request`] and the platform answers with a [`Response`]. Messages
+//! are serialized to bytes. Framing those bytes over a transport is the responsibility of the
+//! transport (see [`frame()`]).
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+/// Requests from the host to the platform.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Request<'a> {
+    /// Installs an applet, replacing the applet with the same name if any.
+    Install { name: &'a str, wasm: &'a [u8] },
+
+    /// Lists the installed applets.
+    List,
+
+    /// Uninstalls an applet.
+    Uninstall { name: &'a str },
+
+    /// Returns (and consumes) the buffered applet logs.
+    Logs,
+}
+
+/// Responses from the platform to the host.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Response<'a> {
+    /// The request succeeded without output.
+    Ok,
+
+    /// The installed applets.
+    List(Vec<AppletInfo<'a>>),
+
+    /// The buffered applet logs.
+    Logs(&'a [u8]),
+
+    /// The request failed.
+    Error(Error),
+}
+
+/// Information about an installed applet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppletInfo<'a> {
+    /// The name of the applet.
+    pub name: &'a str,
+
+    /// The size of the applet module in bytes.
+    pub size: u32,
+}
+
+/// Errors of the protocol.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The message could not be decoded.
+    InvalidMessage,
+
+    /// The applet module is invalid.
+    InvalidApplet,
+
+    /// The applet is not installed.
+    NotFound,
+
+    /// The request is not supported by the platform.
+    Unsupported,
+
+    /// The platform failed to process the request.
+    Internal,
+
+    /// There is no free applet slot.
+    NoSpace,
+
+    /// The applet is not signed by the platform trust anchor.
+    InvalidSignature,
+}
+
+impl Error {
+    fn encode(self) -> u8 {
+        match self {
+            Error::InvalidMessage => 0,
+            Error::InvalidApplet => 1,
+            Error::NotFound => 2,
+            Error::Unsupported => 3,
+            Error::Internal => 4,
+            Error::NoSpace => 5,
+            Error::InvalidSignature => 6,
+        }
+    }
+
+    fn decode(x: u8) -> Result<Self, Error> {
+        Ok(match x {
+            0 => Error::InvalidMessage,
+            1 => Error::InvalidApplet,
+            2 => Error::NotFound,
+            3 => Error::Unsupported,
+            4 => Error::Internal,
+            5 => Error::NoSpace,
+            6 => Error::InvalidSignature,
+            _ => return Err(Error::InvalidMessage),
+        })
+    }
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Error::InvalidMessage => write!(f, "invalid message"),
+            Error::InvalidApplet => write!(f, "invalid applet"),
+            Error::NotFound => write!(f, "applet not found"),
+            Error::Unsupported => write!(f, "unsupported request"),
+            Error::Internal => write!(f, "internal platform error"),
+            Error::NoSpace => write!(f, "no free applet slot"),
+            Error::InvalidSignature => write!(f, "invalid applet signature"),
+        }
+    }
+}
+
+impl<'a> Request<'a> {
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut writer = Writer::default();
+        match self {
+            Request::Install { name, wasm } => {
+                writer.u8(0);
+                writer.bytes(name.as_bytes());
+                writer.bytes(wasm);
+            }
+            Request::List => writer.u8(1),
+            Request::Uninstall { name } => {
+                writer.u8(2);
+                writer.bytes(name.as_bytes());
+            }
+            Request::Logs => writer.u8(3),
+        }
+        writer.0
+    }
+
+    pub fn deserialize(data: &'a [u8]) -> Result<Self, Error> {
+        let mut reader = Reader(data);
+        let request = match reader.u8()? {
+            0 => Request::Install { name: reader.str()?, wasm: reader.bytes()? },
+            1 => Request::List,
+            2 => Request::Uninstall { name: reader.str()? },
+            3 => Request::Logs,
+            _ => return Err(Error::InvalidMessage),
+        };
+        reader.finish()?;
+        Ok(request)
+    }
+}
+
+impl<'a> Response<'a> {
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut writer = Writer::default();
+        match self {
+            Response::Ok => writer.u8(0),
+            Response::List(applets) => {
+                writer.u8(1);
+                writer.u32(applets.len() as u32);
+                for AppletInfo { name, size } in applets {
+                    writer.bytes(name.as_bytes());
+                    writer.u32(*size);
+                }
+            }
+            Response::Logs(logs) => {
+                writer.u8(2);
+                writer.bytes(logs);
+            }
+            Response::Error(error) => {
+                writer.u8(3);
+                writer.u8(error.encode());
+            }
+        }
+        writer.0
+    }
+
+    pub fn deserialize(data: &'a [u8]) -> Result<Self, Error> {
+        let mut reader = Reader(data);
+        let response = match reader.u8()? {
+            0 => Response::Ok,
+            1 => {
+                let len = reader.u32()? as usize;
+                let mut applets = Vec::new();
+                for _ in 0 .. len {
+                    applets.push(AppletInfo { name: reader.str()?, size: reader.u32()? });
+                }
+                Response::List(applets)
+            }
+            2 => Response::Logs(reader.bytes()?),
+            3 => Response::Error(Error::decode(reader.u8()?)?),
+            _ => return Err(Error::InvalidMessage),
+        };
+        reader.finish()?;
+        Ok(response)
+    }
+}
+
+/// Frames a message for stream transports.
+///
+/// The message is prefixed with its length as a little-endian 32-bits integer.
+pub fn frame(message: &[u8]) -> Vec<u8> {
+    let mut writer = Writer::default();
+    writer.bytes(message);
+    writer.0
+}
+
+#[derive(Default)]
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn u8(&mut self, x: u8) {
+        self.0.push(x);
+    }
+
+    fn u32(&mut self, x: u32) {
+        self.0.extend_from_slice(&x.to_le_bytes());
+    }
+
+    fn bytes(&mut self, x: &[u8]) {
+        self.u32(x.len() as u32);
+        self.0.extend_from_slice(x);
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.0.len() < len {
+            return Err(Error::InvalidMessage);
+        }
+        let (result, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(result)
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8], Error> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+
+    fn str(&mut self) -> Result<&'a str, Error> {
+        core::str::from_utf8(self.bytes()?).map_err(|_| Error::InvalidMessage)
+    }
+
+    fn finish(self) -> Result<(), Error> {
+        match self.0.is_empty() {
+            true => Ok(()),
+            false => Err(Error::InvalidMessage),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    #[test]
+    fn request_round_trip() {
+        let requests = [
+            Request::Install { name: "hello", wasm: b"\0asm\x01\0\0\0" },
+            Request::List,
+            Request::Uninstall { name: "hello" },
+            Request::Logs,
+        ];
+        for request in requests {
+            assert_eq!(Request::deserialize(&request.serialize()), Ok(request));
+        }
+    }
+
+    #[test]
+    fn response_round_trip() {
+        let responses = [
+            Response::Ok,
+            Response::List(vec![AppletInfo { name: "hello", size: 42 }]),
+            Response::Logs(b"hello\n"),
+            Response::Error(Error::NotFound),
+            Response::Error(Error::InvalidSignature),
+        ];
+        for response in responses {
+            assert_eq!(Response::deserialize(&response.serialize()), Ok(response));
+        }
+    }
+
+    #[test]
+    fn invalid_message() {
+        assert_eq!(Request::deserialize(&[]), Err(Error::InvalidMessage));
+        assert_eq!(Request::deserialize(&[1, 0]), Err(Error::InvalidMessage));
+        assert_eq!(Request::deserialize(&[2, 5, 0, 0, 0]), Err(Error::InvalidMessage));
+        assert_eq!(Response::deserialize(&[3, 9]), Err(Error::InvalidMessage));
+    }
+}
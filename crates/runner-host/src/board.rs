@@ -15,6 +15,7 @@
 pub mod button;
 mod debug;
 mod led;
+pub mod protocol;
 mod rng;
 mod storage;
 pub mod timer;
@@ -22,11 +23,16 @@ pub mod uart;
 #[cfg(feature = "usb")]
 pub mod usb;
 
+use std::path::PathBuf;
+
 use tokio::sync::mpsc::Sender;
-use wasefire_board_api::{Api, Event, Unsupported};
+use wasefire_board_api::{
+    Api, AppletExit, AppletMemory, Event, RestartPolicy, TrustAnchor, Unsupported,
+};
+use wasefire_logger as log;
 use wasefire_store::FileStorage;
 
-use crate::RECEIVER;
+use crate::{with_state, RECEIVER};
 
 pub struct State {
     pub sender: Sender<Event<Board>>,
@@ -34,9 +40,15 @@ pub struct State {
     pub led: bool,
     pub timers: timer::Timers,
     pub uarts: uart::Uarts,
+    pub protocol: protocol::Protocol,
     #[cfg(feature = "usb")]
     pub usb: usb::Usb,
     pub storage: Option<FileStorage>,
+    pub applet: Option<FileStorage>,
+    pub restart: RestartPolicy,
+    pub profile: Option<PathBuf>,
+    pub trust_anchor: Option<TrustAnchor>,
+    pub store_quota: Option<usize>,
     #[cfg(feature = "web")]
     pub web: web_server::Client,
 }
@@ -60,12 +72,42 @@ impl Api for Board {
         }
     }
 
+    fn applet_exited(name: &str, exit: AppletExit) {
+        log::warn!("Applet {} exited with {:?}.", name, exit);
+    }
+
+    unsafe fn applet_memory() -> AppletMemory {
+        #[repr(align(16))]
+        struct Pool([u8; 0x20000]);
+        static mut POOL: Pool = Pool([0; 0x20000]);
+        // SAFETY: This function is called at most once by contract.
+        AppletMemory { pool: unsafe { &mut POOL.0 }, quota: 0x10000 }
+    }
+
+    fn restart_policy() -> RestartPolicy {
+        with_state(|state| state.restart)
+    }
+
+    fn applet_trust_anchor() -> Option<TrustAnchor> {
+        with_state(|state| state.trust_anchor)
+    }
+
+    fn applet_rejected(name: &str) {
+        log::warn!("Applet {} rejected: invalid signature.", name);
+    }
+
+    fn applet_store_quota() -> Option<usize> {
+        with_state(|state| state.store_quota)
+    }
+
+    type Applet = storage::Impl<storage::Applet>;
     type Button = button::Impl;
     type Crypto = Unsupported;
     type Debug = debug::Impl;
     type Led = led::Impl;
+    type Protocol = protocol::Impl;
     type Rng = rng::Impl;
-    type Storage = storage::Impl;
+    type Storage = storage::Impl<storage::Store>;
     type Timer = timer::Impl;
     type Uart = uart::Impl;
     #[cfg(feature = "usb")]
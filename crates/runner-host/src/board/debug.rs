@@ -12,11 +12,15 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::fs::OpenOptions;
+use std::io::Write;
 use std::sync::OnceLock;
 use std::time::Instant;
 
 use wasefire_board_api as board;
 
+use crate::with_state;
+
 pub enum Impl {}
 
 impl board::debug::Api for Impl {
@@ -26,7 +30,7 @@ impl board::debug::Api for Impl {
     fn println(line: &str) {
         let time = Self::time();
         let message = format!("{}.{:06}: {}", time / 1000000, time % 1000000, line);
-        crate::with_state(|state| state.web.println(message))
+        with_state(|state| state.web.println(message))
     }
 
     fn time() -> u64 {
@@ -36,6 +40,12 @@ impl board::debug::Api for Impl {
         now.duration_since(*origin).as_micros() as u64
     }
 
+    fn profile(stacks: &str) {
+        let Some(path) = with_state(|state| state.profile.clone()) else { return };
+        let mut file = OpenOptions::new().append(true).open(path).unwrap();
+        file.write_all(stacks.as_bytes()).unwrap();
+    }
+
     fn exit(success: bool) -> ! {
         std::process::exit(if success { 0 } else { 1 })
     }
@@ -0,0 +1,165 @@
+// Copyright 2023 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::VecDeque;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::UnixListener;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use wasefire_board_api::protocol::{Api, Event};
+use wasefire_board_api::{Error, Supported};
+use wasefire_logger as log;
+
+use crate::board::State;
+use crate::with_state;
+
+pub enum Impl {}
+
+impl Supported for Impl {}
+
+impl Api for Impl {
+    fn read() -> Result<Option<Box<[u8]>>, Error> {
+        with_state(|state| Ok(state.protocol.requests.pop_front()))
+    }
+
+    fn write(response: &[u8]) -> Result<(), Error> {
+        let frame = wasefire_protocol::frame(response);
+        with_state(|state| match &mut state.protocol.transport {
+            Transport::Disconnected => Err(Error::World),
+            Transport::Unix(sender) => sender.send(frame).map_err(|_| Error::World),
+            #[cfg(feature = "usb")]
+            Transport::Usb(output) => {
+                output.extend(frame);
+                Ok(())
+            }
+        })
+    }
+
+    fn enable() -> Result<(), Error> {
+        with_state(|state| {
+            state.protocol.enabled = true;
+            if !state.protocol.requests.is_empty() {
+                let _ = state.sender.try_send(Event.into());
+            }
+        });
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct Protocol {
+    enabled: bool,
+    requests: VecDeque<Box<[u8]>>,
+    transport: Transport,
+    decoder: Decoder,
+}
+
+#[derive(Default)]
+enum Transport {
+    #[default]
+    Disconnected,
+    Unix(UnboundedSender<Vec<u8>>),
+    #[cfg(feature = "usb")]
+    Usb(VecDeque<u8>),
+}
+
+impl Protocol {
+    /// Listens for host connections on a Unix socket.
+    pub fn init_unix() {
+        const PROTOCOL: &str = "../../target/wasefire/protocol";
+        let _ = std::fs::remove_file(PROTOCOL);
+        let listener = UnixListener::bind(PROTOCOL).unwrap();
+        tokio::spawn(async move {
+            loop {
+                let stream = match listener.accept().await {
+                    Ok((stream, _)) => stream,
+                    Err(e) => {
+                        log::warn!("Failed to accept a protocol connection: {:?}", e);
+                        continue;
+                    }
+                };
+                log::info!("Protocol connected.");
+                let (mut reader, mut writer) = stream.into_split();
+                let (sender, mut receiver) = unbounded_channel::<Vec<u8>>();
+                with_state(|state| {
+                    state.protocol.transport = Transport::Unix(sender);
+                    state.protocol.decoder = Decoder::default();
+                });
+                tokio::spawn(async move {
+                    while let Some(frame) = receiver.recv().await {
+                        if writer.write_all(&frame).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+                tokio::spawn(async move {
+                    let mut buffer = [0; 1024];
+                    loop {
+                        match reader.read(&mut buffer).await {
+                            Ok(0) | Err(_) => break,
+                            Ok(len) => with_state(|state| push(state, &buffer[.. len])),
+                        }
+                    }
+                    log::info!("Protocol disconnected.");
+                });
+            }
+        });
+    }
+
+    /// Uses the USB serial protocol port for host connections.
+    #[cfg(feature = "usb")]
+    pub fn init_usb(&mut self) {
+        self.transport = Transport::Usb(VecDeque::new());
+    }
+
+    /// Returns the pending output of the USB serial transport.
+    #[cfg(feature = "usb")]
+    pub fn usb_output(&mut self) -> Option<&mut VecDeque<u8>> {
+        match &mut self.transport {
+            Transport::Usb(x) => Some(x),
+            _ => None,
+        }
+    }
+}
+
+/// Pushes data received from the transport.
+pub fn push(state: &mut State, data: &[u8]) {
+    let State { sender, protocol, .. } = state;
+    protocol.decoder.push(data, |request| {
+        protocol.requests.push_back(request);
+        if protocol.enabled {
+            let _ = sender.try_send(Event.into());
+        }
+    });
+}
+
+/// Decodes length-prefixed frames from a stream.
+#[derive(Default)]
+struct Decoder {
+    buffer: Vec<u8>,
+}
+
+impl Decoder {
+    fn push(&mut self, data: &[u8], mut frame: impl FnMut(Box<[u8]>)) {
+        self.buffer.extend_from_slice(data);
+        while self.buffer.len() >= 4 {
+            let len = u32::from_le_bytes(self.buffer[.. 4].try_into().unwrap()) as usize;
+            if self.buffer.len() < 4 + len {
+                break;
+            }
+            frame(self.buffer[4 .. 4 + len].into());
+            self.buffer.drain(.. 4 + len);
+        }
+    }
+}
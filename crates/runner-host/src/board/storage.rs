@@ -13,21 +13,46 @@
 // limitations under the License.
 
 use std::borrow::Cow;
+use std::marker::PhantomData;
 
 use wasefire_board_api::Singleton;
 use wasefire_store::{FileStorage, Storage, StorageIndex, StorageResult};
 
+use crate::board::State;
 use crate::with_state;
 
-pub struct Impl(FileStorage);
+/// File storage backing either the store or the applet.
+pub struct Impl<T: Kind>(FileStorage, PhantomData<T>);
 
-impl Singleton for Impl {
+pub trait Kind {
+    fn take(state: &mut State) -> Option<FileStorage>;
+}
+
+/// The storage of the store.
+pub enum Store {}
+
+/// The storage of the applet.
+pub enum Applet {}
+
+impl Kind for Store {
+    fn take(state: &mut State) -> Option<FileStorage> {
+        state.storage.take()
+    }
+}
+
+impl Kind for Applet {
+    fn take(state: &mut State) -> Option<FileStorage> {
+        state.applet.take()
+    }
+}
+
+impl<T: Kind> Singleton for Impl<T> {
     fn take() -> Option<Self> {
-        with_state(|state| state.storage.take().map(Self))
+        with_state(|state| T::take(state).map(|x| Self(x, PhantomData)))
     }
 }
 
-impl Storage for Impl {
+impl<T: Kind> Storage for Impl<T> {
     fn word_size(&self) -> usize {
         self.0.word_size()
     }
@@ -24,7 +24,7 @@ use usbip_device::UsbIpBus;
 use wasefire_board_api::usb::serial::{HasSerial, Serial, WithSerial};
 use wasefire_board_api::usb::Api;
 
-use crate::board::State;
+use crate::board::{protocol, State};
 use crate::with_state;
 
 pub enum Impl {}
@@ -43,22 +43,24 @@ impl HasSerial for Impl {
 
 pub struct Usb {
     pub serial: Serial<'static, UsbIpBus>,
+    /// Serial port for the platform protocol, if enabled.
+    pub protocol: Option<SerialPort<'static, UsbIpBus>>,
     pub usb_dev: UsbDevice<'static, UsbIpBus>,
 }
 
-impl Default for Usb {
-    fn default() -> Self {
+impl Usb {
+    pub fn new(protocol: bool) -> Self {
         let usb_bus = Box::leak(Box::new(UsbBusAllocator::new(UsbIpBus::new())));
         let serial = Serial::new(SerialPort::new(usb_bus));
-        let usb_dev = UsbDeviceBuilder::new(usb_bus, UsbVidPid(0x16c0, 0x27dd))
-            .product("Serial port")
-            .device_class(USB_CLASS_CDC)
-            .build();
-        Self { serial, usb_dev }
+        let protocol = protocol.then(|| SerialPort::new(usb_bus));
+        let builder = UsbDeviceBuilder::new(usb_bus, UsbVidPid(0x16c0, 0x27dd));
+        let usb_dev = match protocol {
+            None => builder.product("Serial port").device_class(USB_CLASS_CDC).build(),
+            Some(_) => builder.product("Serial ports").composite_with_iads().build(),
+        };
+        Self { serial, protocol, usb_dev }
     }
-}
 
-impl Usb {
     pub fn init() -> Result<()> {
         ensure!(
             spawn(&["sudo", "modprobe", "vhci-hcd"]).wait().unwrap().code() == Some(0),
@@ -85,6 +87,7 @@ impl Usb {
                             );
                         let State { sender, usb: Usb { serial, .. }, .. } = state;
                         serial.tick(polled, |event| drop(sender.try_send(event.into())));
+                        tick_protocol(state);
                     });
                 }
             }
@@ -93,8 +96,31 @@ impl Usb {
     }
 
     pub fn poll(&mut self) -> bool {
-        self.usb_dev.poll(&mut [self.serial.port()])
+        match &mut self.protocol {
+            None => self.usb_dev.poll(&mut [self.serial.port()]),
+            Some(protocol) => self.usb_dev.poll(&mut [self.serial.port(), protocol]),
+        }
+    }
+}
+
+/// Moves data between the protocol serial port and the protocol state.
+fn tick_protocol(state: &mut State) -> Option<()> {
+    let State { usb: Usb { protocol: port, .. }, protocol, .. } = state;
+    let port = port.as_mut()?;
+    let output = protocol.usb_output()?;
+    while !output.is_empty() {
+        match port.write(output.make_contiguous()) {
+            Ok(len) => drop(output.drain(.. len)),
+            Err(_) => break,
+        }
+    }
+    let mut buffer = [0; 64];
+    let mut input = Vec::new();
+    while let Ok(len) = port.read(&mut buffer) {
+        input.extend_from_slice(&buffer[.. len]);
     }
+    protocol::push(state, &input);
+    Some(())
 }
 
 fn spawn(cmd: &[&str]) -> Child {
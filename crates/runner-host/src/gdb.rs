@@ -0,0 +1,266 @@
+// Copyright 2023 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! GDB remote serial protocol stub for applets.
+//!
+//! The stub debugs the first applet that starts. Execution pauses before its first instruction
+//! until a debugger connects. Addresses follow the LLDB convention for WebAssembly: code addresses
+//! have the [`CODE`] bit set and are offsets in the module binary, other addresses are offsets in
+//! the linear memory. The LLDB `qWasmCallStack`, `qWasmLocal`, and `qWasmMem` packets are
+//! supported.
+
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use wasefire_interpreter::{Debugger, Paused, Val};
+
+/// Bit of code addresses.
+const CODE: u64 = 0x4000_0000_0000_0000;
+
+/// Stop reply when execution pauses.
+const STOPPED: &str = "T05thread:1;";
+
+static LISTENER: Mutex<Option<TcpListener>> = Mutex::new(None);
+
+/// Listens for a debugger on a local port.
+pub fn init(port: u16) -> Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port)).context("binding GDB stub")?;
+    println!("GDB stub listening on 127.0.0.1:{port}.");
+    *LISTENER.lock().unwrap() = Some(listener);
+    Ok(())
+}
+
+/// Returns the debugger of an applet (only for the first applet).
+pub fn debugger(name: &str) -> Option<Box<dyn Debugger>> {
+    let listener = LISTENER.lock().unwrap().take()?;
+    let name = name.to_string();
+    Some(Box::new(Stub { listener, name, stream: None, running: false, ack: true }))
+}
+
+struct Stub {
+    listener: TcpListener,
+    name: String,
+    stream: Option<BufReader<TcpStream>>,
+    /// Whether the debugger waits for a stop reply.
+    running: bool,
+    /// Whether packets are acknowledged.
+    ack: bool,
+}
+
+enum Action {
+    Reply(String),
+    Resume { step: bool },
+    Detach,
+}
+
+impl Debugger for Stub {
+    fn pause(&mut self, mut paused: Paused) {
+        if self.stream.is_none() {
+            println!("Applet {} waits for GDB.", self.name);
+            let Ok((stream, _)) = self.listener.accept() else { return detach(&mut paused) };
+            self.stream = Some(BufReader::new(stream));
+            self.running = false;
+            self.ack = true;
+        }
+        if std::mem::replace(&mut self.running, false) {
+            self.send(STOPPED);
+        }
+        while let Some(packet) = self.recv() {
+            match handle(&mut paused, &self.name, &packet) {
+                Action::Reply(reply) => {
+                    self.send(&reply);
+                    self.ack &= packet != "QStartNoAckMode";
+                }
+                Action::Resume { step } => {
+                    paused.set_step(step);
+                    self.running = true;
+                    return;
+                }
+                Action::Detach => {
+                    self.send("OK");
+                    break;
+                }
+            }
+        }
+        self.stream = None;
+        detach(&mut paused);
+    }
+}
+
+impl Stub {
+    /// Receives a packet (without framing).
+    ///
+    /// Returns `None` if the connection is closed.
+    fn recv(&mut self) -> Option<String> {
+        let stream = self.stream.as_mut()?;
+        loop {
+            let mut byte = [0];
+            stream.read_exact(&mut byte).ok()?;
+            // Acknowledgements and interrupts are ignored.
+            if byte[0] == b'$' {
+                break;
+            }
+        }
+        let mut packet = Vec::new();
+        stream.read_until(b'#', &mut packet).ok()?;
+        packet.pop();
+        let mut checksum = [0; 2];
+        stream.read_exact(&mut checksum).ok()?;
+        if self.ack {
+            stream.get_mut().write_all(b"+").ok()?;
+        }
+        String::from_utf8(packet).ok()
+    }
+
+    /// Sends a packet (adding framing).
+    fn send(&mut self, data: &str) {
+        let Some(stream) = self.stream.as_mut() else { return };
+        let checksum = data.bytes().fold(0u8, |x, y| x.wrapping_add(y));
+        let packet = format!("${data}#{checksum:02x}");
+        if stream.get_mut().write_all(packet.as_bytes()).is_err() {
+            self.stream = None;
+        }
+    }
+}
+
+impl Drop for Stub {
+    fn drop(&mut self) {
+        // The applet is unloaded.
+        if self.running {
+            self.send("W00");
+        }
+    }
+}
+
+/// Removes the breakpoints and resumes execution.
+fn detach(paused: &mut Paused) {
+    paused.clear_breakpoints();
+    paused.set_step(false);
+}
+
+fn handle(paused: &mut Paused, name: &str, packet: &str) -> Action {
+    let reply = match packet.split_once(|x| x == ':' || x == ',') {
+        _ if packet == "c" => return Action::Resume { step: false },
+        _ if packet == "s" => return Action::Resume { step: true },
+        _ if packet == "D" || packet.starts_with("D;") || packet == "k" => return Action::Detach,
+        _ if packet == "?" => Some(STOPPED.to_string()),
+        _ if packet == "qC" => Some("QC1".to_string()),
+        _ if packet == "qfThreadInfo" => Some("m1".to_string()),
+        _ if packet == "qsThreadInfo" => Some("l".to_string()),
+        _ if packet == "qAttached" => Some("1".to_string()),
+        _ if packet == "QStartNoAckMode" || packet.starts_with('H') => Some("OK".to_string()),
+        _ if packet == "qHostInfo" => Some(format!("triple:{};{INFO}", hex(TRIPLE.as_bytes()))),
+        _ if packet == "qProcessInfo" => {
+            Some(format!("pid:1;parent-pid:1;triple:{};{INFO}", hex(TRIPLE.as_bytes())))
+        }
+        _ if packet == "qRegisterInfo0" => Some(REGISTER.to_string()),
+        _ if packet.starts_with("qRegisterInfo") => Some("E45".to_string()),
+        _ if packet == "g" || packet == "p0" => pc(paused, 0).map(|x| hex(&x.to_le_bytes())),
+        Some(("qSupported", _)) => Some("PacketSize=4000;qXfer:libraries:read+".to_string()),
+        Some(("qXfer", args)) => libraries(name, args),
+        Some(("qWasmCallStack", _)) => {
+            let frames = (0 .. paused.backtrace().len()).flat_map(|i| pc(paused, i));
+            Some(frames.map(|x| hex(&x.to_le_bytes())).collect())
+        }
+        Some(("qWasmLocal", args)) => local(paused, args),
+        Some(("qWasmMem", args)) => {
+            let mut args = args.split(';');
+            let frame = number(args.next());
+            let addr = number(args.next());
+            let len = number(args.next());
+            frame.zip(addr).zip(len).and_then(|((f, a), l)| read(paused, f as usize, a, l))
+        }
+        Some((x, args)) if x.starts_with('m') => {
+            number(Some(&x[1 ..])).zip(number(Some(args))).and_then(|(a, l)| read(paused, 0, a, l))
+        }
+        Some((x, args)) if x == "Z0" || x == "z0" => breakpoint(paused, x == "Z0", args),
+        _ => Some(String::new()),
+    };
+    Action::Reply(reply.unwrap_or_else(|| "E01".to_string()))
+}
+
+const TRIPLE: &str = "wasm32-unknown-unknown-wasm";
+
+const INFO: &str = "ptrsize:4;endian:little;";
+
+const REGISTER: &str = "name:pc;alt-name:pc;bitsize:64;offset:0;encoding:uint;format:hex;set:\
+                        General Purpose Registers;gcc:16;dwarf:16;generic:pc;";
+
+/// Returns the code address of a frame (from innermost).
+fn pc(paused: &Paused, frame: usize) -> Option<u64> {
+    Some(CODE | paused.backtrace().get(frame)?.offset as u64)
+}
+
+fn libraries(name: &str, args: &str) -> Option<String> {
+    let (_, range) = args.strip_prefix("libraries:read:")?.split_once(':')?;
+    let (offset, len) = range.split_once(',')?;
+    let (offset, len) = (number(Some(offset))? as usize, number(Some(len))? as usize);
+    let xml = format!(
+        "<library-list><library name=\"{name}.wasm\"><section \
+         address=\"{CODE:#x}\"/></library></library-list>"
+    );
+    let data = xml.get(offset.min(xml.len()) ..)?;
+    Some(match data.len() <= len {
+        true => format!("l{data}"),
+        false => format!("m{}", &data[.. len]),
+    })
+}
+
+fn local(paused: &Paused, args: &str) -> Option<String> {
+    let (frame, index) = args.split_once(';')?;
+    let (frame, index) = (number(Some(frame))? as usize, number(Some(index))? as usize);
+    Some(match *paused.locals(frame)?.get(index)? {
+        Val::I32(x) => hex(&x.to_le_bytes()),
+        Val::I64(x) => hex(&x.to_le_bytes()),
+        _ => return None,
+    })
+}
+
+/// Reads code or memory bytes from the instance of a frame.
+fn read(paused: &mut Paused, frame: usize, addr: u64, len: u64) -> Option<String> {
+    let inst = paused.backtrace().get(frame)?.inst;
+    let (addr, len) = (addr as usize, len as usize);
+    if addr & CODE as usize != 0 {
+        let mut code = b"\0asm\x01\0\0\0".to_vec();
+        code.extend_from_slice(paused.binary(inst)?);
+        let start = (addr & !(CODE as usize)).min(code.len());
+        return Some(hex(&code[start ..][.. len.min(code.len() - start)]));
+    }
+    let mem = paused.mem(inst)?;
+    Some(hex(mem.get(addr .. addr.checked_add(len)?)?))
+}
+
+fn breakpoint(paused: &mut Paused, insert: bool, args: &str) -> Option<String> {
+    let addr = number(args.split(',').next())?;
+    let inst = paused.backtrace().first()?.inst;
+    let offset = (addr & !CODE) as usize;
+    match insert {
+        true => paused.set_breakpoint(inst, offset).ok()?,
+        false => paused.clear_breakpoint(inst, offset).ok()?,
+    }
+    Some("OK".to_string())
+}
+
+fn number(x: Option<&str>) -> Option<u64> {
+    u64::from_str_radix(x?, 16).ok()
+}
+
+fn hex(data: &[u8]) -> String {
+    let mut result = String::with_capacity(2 * data.len());
+    data.iter().for_each(|x| write!(result, "{x:02x}").unwrap());
+    result
+}
@@ -15,18 +15,21 @@
 #![feature(core_intrinsics)]
 #![feature(try_blocks)]
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 
-use anyhow::Result;
+use anyhow::{ensure, Context, Result};
 use board::Board;
+use clap::{Parser, ValueEnum};
 use tokio::runtime::Handle;
 use tokio::sync::mpsc::{channel, Receiver};
-use wasefire_board_api::Event;
-use wasefire_scheduler::Scheduler;
+use wasefire_board_api::{Event, RestartPolicy, TrustAnchor};
+use wasefire_scheduler::{persist, Scheduler, MAX_APPLETS};
 use wasefire_store::{FileOptions, FileStorage};
 
 mod board;
+#[cfg(feature = "debug")]
+mod gdb;
 
 static STATE: Mutex<Option<board::State>> = Mutex::new(None);
 static RECEIVER: Mutex<Option<Receiver<Event<Board>>>> = Mutex::new(None);
@@ -35,13 +38,124 @@ fn with_state<R>(f: impl FnOnce(&mut board::State) -> R) -> R {
     f(STATE.lock().unwrap().as_mut().unwrap())
 }
 
+#[derive(Parser)]
+struct Flags {
+    /// Installs these applets before starting the scheduler.
+    ///
+    /// The applets are installed in order starting from the first slot. They are persisted in
+    /// target/wasefire/applet.bin and loaded at each start.
+    applets: Vec<PathBuf>,
+
+    /// Transport used by the platform protocol (e.g. for the wasefire CLI).
+    #[arg(long, value_enum, default_value_t)]
+    protocol: Protocol,
+
+    /// How applets are restarted after they trap.
+    #[arg(long, value_enum, default_value_t)]
+    restart: Restart,
+
+    /// Writes the profile of applets to this file when they exit or are unloaded.
+    ///
+    /// This requires the profile feature. The profile is in folded-stack format, which flamegraph
+    /// tools accept. It counts the executed instructions per call stack.
+    #[arg(long)]
+    profile: Option<PathBuf>,
+
+    /// Serves a GDB remote serial protocol stub on this local port.
+    ///
+    /// This requires the debug feature. The first applet to start waits for a debugger (e.g. LLDB
+    /// with WebAssembly support) before executing.
+    #[arg(long)]
+    gdb: Option<u16>,
+
+    /// Only runs applets signed by the public key in this file.
+    ///
+    /// The file contains the 64 bytes of a P-256 public key: the x coordinate followed by the y
+    /// coordinate, both in big-endian. Applets that are not signed are rejected.
+    #[arg(long)]
+    trust_anchor: Option<PathBuf>,
+
+    /// Limits the number of bytes each applet may store.
+    #[arg(long)]
+    store_quota: Option<usize>,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, ValueEnum)]
+enum Protocol {
+    /// Unix socket at target/wasefire/protocol.
+    #[default]
+    Unix,
+
+    /// Second serial port of the USB/IP device.
+    Usb,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, ValueEnum)]
+enum Restart {
+    /// Applets are not restarted.
+    #[default]
+    Never,
+
+    /// Applets are restarted immediately.
+    Immediately,
+
+    /// Applets are restarted after a delay (from 1 second up to 1 minute).
+    Backoff,
+}
+
+impl From<Restart> for RestartPolicy {
+    fn from(restart: Restart) -> Self {
+        match restart {
+            Restart::Never => RestartPolicy::Never,
+            Restart::Immediately => RestartPolicy::Immediately,
+            Restart::Backoff => RestartPolicy::Backoff { initial_ms: 1000, max_ms: 60000 },
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     env_logger::init();
+    let flags = Flags::parse();
+    ensure!(
+        cfg!(feature = "usb") || flags.protocol != Protocol::Usb,
+        "the USB protocol transport requires the usb feature"
+    );
+    ensure!(
+        cfg!(feature = "profile") || flags.profile.is_none(),
+        "the profile flag requires the profile feature"
+    );
+    ensure!(
+        cfg!(feature = "debug") || flags.gdb.is_none(),
+        "the gdb flag requires the debug feature"
+    );
+    if let Some(path) = &flags.profile {
+        std::fs::write(path, "").with_context(|| format!("truncating {}", path.display()))?;
+    }
+    let trust_anchor = match &flags.trust_anchor {
+        None => None,
+        Some(path) => {
+            let key = std::fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+            ensure!(key.len() == 64, "the trust anchor must be 64 bytes");
+            let (x, y) = key.split_at(32);
+            Some(TrustAnchor { x: x.try_into().unwrap(), y: y.try_into().unwrap() })
+        }
+    };
     // TODO: Should be a flag controlled by xtask (value is duplicated there).
     const STORAGE: &str = "../../target/wasefire/storage.bin";
     let options = FileOptions { word_size: 4, page_size: 4096, num_pages: 16 };
     let storage = Some(FileStorage::new(Path::new(STORAGE), options).unwrap());
+    const APPLET: &str = "../../target/wasefire/applet.bin";
+    let options = FileOptions { word_size: 4, page_size: 4096, num_pages: 64 };
+    let mut applet = FileStorage::new(Path::new(APPLET), options).unwrap();
+    ensure!(flags.applets.len() <= MAX_APPLETS, "at most {MAX_APPLETS} applets are supported");
+    for (slot, path) in flags.applets.iter().enumerate() {
+        let name = path.file_stem().and_then(|x| x.to_str()).context("invalid applet name")?;
+        let wasm = std::fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+        persist::write(&mut applet, slot, name, &wasm, None)
+            .ok()
+            .context("writing applet storage")?;
+    }
     let (sender, receiver) = channel(10);
     *RECEIVER.lock().unwrap() = Some(receiver);
     #[cfg(feature = "web")]
@@ -65,13 +179,26 @@ async fn main() -> Result<()> {
         led: false,
         timers: board::timer::Timers::default(),
         uarts: board::uart::Uarts::new(),
+        protocol: board::protocol::Protocol::default(),
         #[cfg(feature = "usb")]
-        usb: board::usb::Usb::default(),
+        usb: board::usb::Usb::new(flags.protocol == Protocol::Usb),
         storage,
+        applet: Some(applet),
+        restart: flags.restart.into(),
+        profile: flags.profile,
+        trust_anchor,
+        store_quota: flags.store_quota,
         #[cfg(feature = "web")]
         web,
     });
     board::uart::Uarts::init();
+    match flags.protocol {
+        Protocol::Unix => board::protocol::Protocol::init_unix(),
+        #[cfg(feature = "usb")]
+        Protocol::Usb => with_state(|state| state.protocol.init_usb()),
+        #[cfg(not(feature = "usb"))]
+        Protocol::Usb => unreachable!(),
+    }
     #[cfg(feature = "usb")]
     board::usb::Usb::init()?;
     #[cfg(not(feature = "web"))]
@@ -92,7 +219,16 @@ async fn main() -> Result<()> {
             with_state(|state| board::button::event(state, pressed));
         }
     });
+    #[cfg(feature = "debug")]
+    if let Some(port) = flags.gdb {
+        gdb::init(port)?;
+    }
     println!("Board initialized. Starting scheduler.");
-    const WASM: &[u8] = include_bytes!("../../../target/wasefire/applet.wasm");
-    Handle::current().spawn_blocking(|| Scheduler::<board::Board>::run(WASM)).await?
+    Handle::current()
+        .spawn_blocking(move || match flags.gdb {
+            #[cfg(feature = "debug")]
+            Some(_) => Scheduler::<board::Board>::run_with_debugger(gdb::debugger),
+            _ => Scheduler::<board::Board>::run(),
+        })
+        .await?
 }
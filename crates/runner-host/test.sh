@@ -15,12 +15,9 @@
 
 set -ex
 
-if [ ! -e ../../target/wasefire/applet.wasm ]; then
-  mkdir -p ../../target/wasefire
-  touch ../../target/wasefire/applet.wasm
-fi
 cargo check --features=debug
 cargo check --features=debug,web
+cargo check --features=debug,profile
 cargo check --features=release
 cargo check --no-default-features --features=debug
 cargo fmt -- --check
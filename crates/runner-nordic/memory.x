@@ -1,15 +1,18 @@
 /* nrf52840-dk */
 
 __stack_size = 0x10000;
+__applet_size = 0x40000;
 __store_size = 0x10000;
 
 MEMORY
 {
-  FLASH : ORIGIN = 0x00000000, LENGTH = 0x00100000 - __store_size
+  FLASH : ORIGIN = 0x00000000, LENGTH = 0x00100000 - __applet_size - __store_size
   RAM   : ORIGIN = 0x20000000 + __stack_size, LENGTH = 0x00040000 - __stack_size
 }
 
 _stack_start = ORIGIN(RAM);
 __eheap = ORIGIN(RAM) + LENGTH(RAM);
-__sstore = ORIGIN(FLASH) + LENGTH(FLASH);
+__sapplet = ORIGIN(FLASH) + LENGTH(FLASH);
+__eapplet = __sapplet + __applet_size;
+__sstore = __eapplet;
 __estore = __sstore + __store_size;
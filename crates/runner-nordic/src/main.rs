@@ -77,7 +77,8 @@ struct State {
     ccm: Ccm,
     leds: [Pin<Output<PushPull>>; <led::Impl as Support<usize>>::SUPPORT],
     rng: Rng,
-    storage: Option<Storage>,
+    storage: Option<Storage<storage::Store>>,
+    applet: Option<Storage<storage::Applet>>,
     uarts: Uarts,
     usb_dev: UsbDevice<'static, Usb>,
 }
@@ -129,7 +130,11 @@ fn main() -> ! {
         .build();
     let rng = Rng::new(p.RNG);
     let ccm = Ccm::init(p.CCM, p.AAR, DataRate::_1Mbit);
-    let storage = Some(Storage::new(p.NVMC));
+    // SAFETY: The store and applet regions are disjoint and this is the only place creating them.
+    // The applet storage steals the NVMC peripheral, which is fine because the scheduler never
+    // accesses both storages concurrently.
+    let storage = Some(unsafe { Storage::new(p.NVMC) });
+    let applet = Some(unsafe { Storage::new(nrf52840_hal::pac::Peripherals::steal().NVMC) });
     let pins = uarte::Pins {
         txd: port0.p0_06.into_push_pull_output(gpio::Level::High).degrade(),
         rxd: port0.p0_08.into_floating_input().degrade(),
@@ -138,8 +143,20 @@ fn main() -> ! {
     };
     let uarts = Uarts::new(p.UARTE0, pins, p.UARTE1);
     let events = Events::default();
-    let state =
-        State { events, buttons, gpiote, serial, timers, ccm, leds, rng, storage, uarts, usb_dev };
+    let state = State {
+        events,
+        buttons,
+        gpiote,
+        serial,
+        timers,
+        ccm,
+        leds,
+        rng,
+        storage,
+        applet,
+        uarts,
+        usb_dev,
+    };
     // We first set the board and then enable interrupts so that interrupts may assume the board is
     // always present.
     critical_section::with(|cs| STATE.replace(cs, Some(state)));
@@ -147,8 +164,7 @@ fn main() -> ! {
         unsafe { NVIC::unmask(interrupt) };
     }
     logger::debug!("Runner is initialized.");
-    const WASM: &[u8] = include_bytes!("../../../target/wasefire/applet.wasm");
-    Scheduler::<Board>::run(WASM)
+    Scheduler::<Board>::run()
 }
 
 macro_rules! interrupts {
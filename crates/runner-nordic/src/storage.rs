@@ -15,6 +15,7 @@
 use alloc::borrow::Cow;
 use alloc::vec;
 use core::cell::RefCell;
+use core::marker::PhantomData;
 use core::slice;
 
 use embedded_storage::nor_flash::{
@@ -26,32 +27,66 @@ use wasefire_store::{self as store, StorageError, StorageIndex, StorageResult};
 
 const PAGE_SIZE: usize = <Nvmc<NVMC>>::ERASE_SIZE;
 
-pub struct Storage(RefCell<Nvmc<NVMC>>);
+/// Flash storage of a given region.
+pub struct Storage<R: Region>(RefCell<Nvmc<NVMC>>, PhantomData<R>);
 
-impl Storage {
-    pub fn new(nvmc: NVMC) -> Self {
-        // SAFETY: We assume only one NVMC instance can exist, so this function is called at most
-        // once, and so we call inner at most once.
-        Storage(RefCell::new(Nvmc::new(nvmc, unsafe { Self::inner() })))
-    }
+/// Flash region defined by the linker script.
+pub trait Region {
+    /// Returns the start and end of the region.
+    fn bounds() -> (*mut u32, *mut u32);
+}
 
-    // SAFETY: Must be called at most once.
-    unsafe fn inner() -> &'static mut [u8] {
+/// The region of the store.
+pub enum Store {}
+
+/// The region of the applet.
+pub enum Applet {}
+
+impl Region for Store {
+    fn bounds() -> (*mut u32, *mut u32) {
         extern "C" {
             static mut __sstore: u32;
             static mut __estore: u32;
         }
-        let start = &mut __sstore as *mut u32 as *mut u8;
-        let sstore = start as usize;
-        let estore = &mut __estore as *mut u32 as usize;
-        assert!(sstore < estore);
-        let length = estore - sstore;
+        unsafe { (&mut __sstore, &mut __estore) }
+    }
+}
+
+impl Region for Applet {
+    fn bounds() -> (*mut u32, *mut u32) {
+        extern "C" {
+            static mut __sapplet: u32;
+            static mut __eapplet: u32;
+        }
+        unsafe { (&mut __sapplet, &mut __eapplet) }
+    }
+}
+
+impl<R: Region> Storage<R> {
+    /// Creates the storage of a region.
+    ///
+    /// # Safety
+    ///
+    /// Must be called at most once per region. Regions are disjoint, so multiple NVMC instances
+    /// may coexist as long as they are not used concurrently (the scheduler is single-threaded).
+    pub unsafe fn new(nvmc: NVMC) -> Self {
+        Storage(RefCell::new(Nvmc::new(nvmc, unsafe { Self::inner() })), PhantomData)
+    }
+
+    // SAFETY: Must be called at most once.
+    unsafe fn inner() -> &'static mut [u8] {
+        let (start, end) = R::bounds();
+        let start = start as *mut u8;
+        let sregion = start as usize;
+        let eregion = end as usize;
+        assert!(sregion < eregion);
+        let length = eregion - sregion;
         assert_eq!(length % PAGE_SIZE, 0);
         slice::from_raw_parts_mut(start, length)
     }
 }
 
-impl store::Storage for Storage {
+impl<R: Region> store::Storage for Storage<R> {
     fn word_size(&self) -> usize {
         <Nvmc<NVMC>>::WRITE_SIZE
     }
@@ -91,7 +126,9 @@ impl store::Storage for Storage {
     }
 }
 
-fn offset(storage: &Storage, length: usize, index: StorageIndex) -> StorageResult<u32> {
+fn offset<R: Region>(
+    storage: &Storage<R>, length: usize, index: StorageIndex,
+) -> StorageResult<u32> {
     Ok(index.range(length, storage)?.start as u32)
 }
 
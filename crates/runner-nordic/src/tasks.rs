@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use wasefire_board_api::{self as board, Event, Singleton};
+use wasefire_board_api::{self as board, Event, Singleton, Unsupported};
 use wasefire_scheduler as scheduler;
 
 use crate::{with_state, Board};
@@ -40,23 +40,31 @@ impl board::Api for Board {
         }
     }
 
+    type Applet = crate::storage::Storage<crate::storage::Applet>;
     type Button = button::Impl;
     type Crypto = crypto::Impl;
     type Debug = debug::Impl;
     type Led = led::Impl;
+    type Protocol = Unsupported;
     type Rng = rng::Impl;
-    type Storage = crate::storage::Storage;
+    type Storage = crate::storage::Storage<crate::storage::Store>;
     type Timer = clock::Impl;
     type Uart = uart::Impl;
     type Usb = usb::Impl;
 }
 
-impl Singleton for crate::storage::Storage {
+impl Singleton for crate::storage::Storage<crate::storage::Store> {
     fn take() -> Option<Self> {
         with_state(|state| state.storage.take())
     }
 }
 
+impl Singleton for crate::storage::Storage<crate::storage::Applet> {
+    fn take() -> Option<Self> {
+        with_state(|state| state.applet.take())
+    }
+}
+
 #[derive(Default)]
 pub struct Events(scheduler::Events<Board>);
 
@@ -15,10 +15,6 @@
 
 set -ex
 
-if [ ! -e ../../target/wasefire/applet.wasm ]; then
-  mkdir -p ../../target/wasefire
-  touch ../../target/wasefire/applet.wasm
-fi
 cargo check --target=thumbv7em-none-eabi --features=debug
 DEFMT_LOG=trace cargo check --target=thumbv7em-none-eabi --features=debug
 cargo check --target=thumbv7em-none-eabi --features=release
@@ -21,6 +21,7 @@ mod button;
 mod clock;
 mod crypto;
 mod debug;
+mod ipc;
 mod led;
 mod rng;
 mod scheduling;
@@ -34,6 +35,7 @@ pub fn process<B: Board>(call: Api<DispatchSchedulerCall<B>>) {
         Api::Clock(call) => clock::process(call),
         Api::Crypto(call) => crypto::process(call),
         Api::Debug(call) => debug::process(call),
+        Api::Ipc(call) => ipc::process(call),
         Api::Led(call) => led::process(call),
         Api::Rng(call) => rng::process(call),
         Api::Scheduling(call) => scheduling::process(call),
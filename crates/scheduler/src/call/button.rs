@@ -39,7 +39,7 @@ fn register<B: Board>(mut call: SchedulerCall<B, api::register::Sig>) {
     let inst = call.inst();
     let results = try {
         let button = Id::new(*button as usize).ok_or(Trap)?;
-        call.scheduler().applet.enable(Handler {
+        call.scheduler().enable_event(Handler {
             key: Key { button }.into(),
             inst,
             func: *handler_func,
@@ -33,10 +33,11 @@ fn allocate<B: Board>(mut call: SchedulerCall<B, api::allocate::Sig>) {
     let api::allocate::Params { handler_func, handler_data } = call.read();
     let inst = call.inst();
     let results = try {
-        let timers = &mut call.scheduler().timers;
-        let timer = timers.iter().position(|x| x.is_none()).ok_or(Trap)?;
-        timers[timer] = Some(Timer {});
-        call.scheduler().applet.enable(Handler {
+        let scheduler = call.scheduler();
+        let applet = scheduler.applets.current_id();
+        let timer = scheduler.timers.iter().position(|x| x.is_none()).ok_or(Trap)?;
+        scheduler.timers[timer] = Some(Timer { applet, restart: false });
+        scheduler.enable_event(Handler {
             key: Key { timer: Id::new(timer).unwrap() }.into(),
             inst,
             func: *handler_func,
@@ -84,13 +85,13 @@ fn free<B: Board>(mut call: SchedulerCall<B, api::free::Sig>) {
     call.reply(results);
 }
 
-// TODO: Should also check that the timer belongs to the calling applet.
+/// Returns the timer if it belongs to the calling applet.
 fn get_timer<B: Board>(
     scheduler: &Scheduler<B>, timer: usize,
 ) -> Result<Id<board::Timer<B>>, Trap> {
     let id = Id::new(timer).ok_or(Trap)?;
-    if scheduler.timers[timer].is_none() {
-        return Err(Trap);
+    match &scheduler.timers[timer] {
+        Some(x) if x.applet == scheduler.applets.current_id() && !x.restart => Ok(id),
+        _ => Err(Trap),
     }
-    Ok(id)
 }
@@ -12,9 +12,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use alloc::string::String;
+
 use wasefire_applet_api::debug::{self as api, Api, Perf};
 use wasefire_board_api::debug::Api as _;
-use wasefire_board_api::{self as board, Api as Board};
+use wasefire_board_api::{self as board, Api as Board, Support};
 
 use crate::{DispatchSchedulerCall, SchedulerCall, Trap};
 
@@ -30,11 +32,18 @@ pub fn process<B: Board>(call: Api<DispatchSchedulerCall<B>>) {
 fn println<B: Board>(mut call: SchedulerCall<B, api::println::Sig>) {
     let api::println::Params { ptr, len } = call.read();
     let memory = call.memory();
+    let mut logged = None;
     let results = try {
         let line = core::str::from_utf8(memory.get(*ptr, *len)?).map_err(|_| Trap)?;
         board::Debug::<B>::println(line);
+        if board::Protocol::<B>::SUPPORT {
+            logged = Some(String::from(line));
+        }
         api::println::Results {}
     };
+    if let Some(line) = logged {
+        call.scheduler().log(&line);
+    }
     call.reply(results)
 }
 
@@ -69,5 +78,13 @@ fn perf<B: Board>(mut call: SchedulerCall<B, api::perf::Sig>) {
 
 fn exit<B: Board>(call: SchedulerCall<B, api::exit::Sig>) {
     let api::exit::Params { code } = call.read();
+    #[cfg(feature = "profile")]
+    {
+        let mut call = call;
+        let scheduler = call.scheduler();
+        for (id, applet) in scheduler.applets.iter() {
+            scheduler.report_profile(id, &applet.binary.as_ref().unwrap().name);
+        }
+    }
     board::Debug::<B>::exit(*code == 0);
 }
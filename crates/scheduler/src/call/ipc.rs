@@ -0,0 +1,146 @@
+// Copyright 2023 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::boxed::Box;
+use alloc::string::String;
+
+use wasefire_applet_api::ipc::{self as api, Api};
+use wasefire_board_api::Api as Board;
+
+use crate::event::ipc::{Event, Key};
+use crate::event::Handler;
+use crate::stores::Message;
+use crate::{DispatchSchedulerCall, Scheduler, SchedulerCall, Trap};
+
+/// Maximum number of messages in the mailbox of an applet.
+const MAX_MESSAGES: usize = 4;
+
+/// Maximum length of a message in bytes.
+const MAX_LEN: usize = 256;
+
+pub fn process<B: Board>(call: Api<DispatchSchedulerCall<B>>) {
+    match call {
+        Api::Send(call) => send(call),
+        Api::Receive(call) => receive(call),
+        Api::Register(call) => register(call),
+        Api::Unregister(call) => unregister(call),
+    }
+}
+
+fn send<B: Board>(mut call: SchedulerCall<B, api::send::Sig>) {
+    let api::send::Params { applet_ptr, applet_len, ptr, len } = call.read();
+    let scheduler = call.scheduler();
+    let memory = scheduler.applets.current().memory();
+    let results = try {
+        let receiver = memory.get(*applet_ptr, *applet_len)?;
+        let receiver = core::str::from_utf8(receiver).ok().map(String::from);
+        let data = Box::<[u8]>::from(memory.get(*ptr, *len)?);
+        let res = match deliver(scheduler, receiver, data) {
+            Ok(()) => 0.into(),
+            Err(e) => e.into(),
+        };
+        api::send::Results { res }
+    };
+    call.reply(results);
+}
+
+/// Queues a message from the current applet in the mailbox of another applet.
+fn deliver<B: Board>(
+    scheduler: &mut Scheduler<B>, receiver: Option<String>, data: Box<[u8]>,
+) -> Result<(), api::Error> {
+    let receiver = receiver.ok_or(api::Error::InvalidArgument)?;
+    if data.is_empty() || MAX_LEN < data.len() {
+        return Err(api::Error::InvalidArgument);
+    }
+    let sender = scheduler.applets.current().binary.as_ref().unwrap().name.clone();
+    let id = scheduler.applets.find(&receiver).ok_or(api::Error::NotFound)?;
+    let applet = scheduler.applets.get_mut(id);
+    if !applet.senders.contains(&sender) {
+        return Err(api::Error::NotAllowed);
+    }
+    if MAX_MESSAGES <= applet.mailbox.len() {
+        return Err(api::Error::NoCapacity);
+    }
+    applet.mailbox.push_back(Message { sender, data });
+    if applet.get(Key { applet: id }.into()).is_some() {
+        applet.push(Event { applet: id }.into());
+    }
+    Ok(())
+}
+
+fn receive<B: Board>(mut call: SchedulerCall<B, api::receive::Sig>) {
+    let api::receive::Params { applet_ptr, applet_len, ptr, len } = call.read();
+    let scheduler = call.scheduler();
+    let applet = scheduler.applets.current();
+    let mailbox = &mut applet.mailbox;
+    let mut memory = applet.store.memory();
+    let results = try {
+        let mut results = api::receive::Results::default();
+        // The message is only removed once copied, such that it is not lost if copying fails.
+        if let Some(Message { sender, data }) = mailbox.front() {
+            // Senders and messages are never empty, so neither allocation has zero size. Both
+            // buffers are allocated before writing anything, and the applet traps on any failure,
+            // such that no buffer is leaked.
+            let values = [(sender.as_bytes(), applet_ptr, applet_len), (&data[..], ptr, len)];
+            let ptrs = values.map(|(value, _, _)| memory.alloc(value.len() as u32, 1));
+            if ptrs.contains(&0) {
+                // This API doesn't support failing allocation.
+                Err(Trap)?;
+            }
+            for ((value, ptr_ptr, len_ptr), ptr) in values.into_iter().zip(ptrs) {
+                let len = value.len() as u32;
+                memory.get_mut(ptr, len)?.copy_from_slice(value);
+                memory.get_mut(*ptr_ptr, 4)?.copy_from_slice(&ptr.to_le_bytes());
+                memory.get_mut(*len_ptr, 4)?.copy_from_slice(&len.to_le_bytes());
+            }
+            mailbox.pop_front();
+            results.res = 1.into();
+        }
+        results
+    };
+    call.reply(results);
+}
+
+fn register<B: Board>(mut call: SchedulerCall<B, api::register::Sig>) {
+    let api::register::Params { handler_func, handler_data } = call.read();
+    let inst = call.inst();
+    let scheduler = call.scheduler();
+    let results = try {
+        let id = scheduler.applets.current_id();
+        scheduler.enable_event(Handler {
+            key: Key { applet: id }.into(),
+            inst,
+            func: *handler_func,
+            data: *handler_data,
+        })?;
+        // Messages may already be waiting in the mailbox.
+        let applet = scheduler.applets.current();
+        if !applet.mailbox.is_empty() {
+            applet.push(Event { applet: id }.into());
+        }
+        api::register::Results {}
+    };
+    call.reply(results);
+}
+
+fn unregister<B: Board>(mut call: SchedulerCall<B, api::unregister::Sig>) {
+    let api::unregister::Params {} = call.read();
+    let scheduler = call.scheduler();
+    let results = try {
+        let applet = scheduler.applets.current_id();
+        scheduler.disable_event(Key { applet }.into())?;
+        api::unregister::Results {}
+    };
+    call.reply(results);
+}
@@ -27,7 +27,7 @@ pub fn process<B: Board>(call: Api<DispatchSchedulerCall<B>>) {
 fn fill_bytes<B: Board>(mut call: SchedulerCall<B, api::fill_bytes::Sig>) {
     let api::fill_bytes::Params { ptr, len } = call.read();
     let scheduler = call.scheduler();
-    let memory = scheduler.applet.memory();
+    let memory = scheduler.applets.current().memory();
     let results = try {
         let output = memory.get_mut(*ptr, *len)?;
         let res = match board::Rng::<B>::fill_bytes(output) {
@@ -16,7 +16,7 @@ use wasefire_applet_api::store::{self as api, Api};
 use wasefire_board_api::Api as Board;
 use wasefire_store::StoreError;
 
-use crate::{DispatchSchedulerCall, SchedulerCall, Trap};
+use crate::{namespace, DispatchSchedulerCall, SchedulerCall, Trap};
 
 mod fragment;
 
@@ -32,10 +32,16 @@ pub fn process<B: Board>(call: Api<DispatchSchedulerCall<B>>) {
 fn insert<B: Board>(mut call: SchedulerCall<B, api::insert::Sig>) {
     let api::insert::Params { key, ptr, len } = call.read();
     let scheduler = call.scheduler();
-    let memory = scheduler.applet.memory();
+    let id = scheduler.applets.current_id();
+    let memory = scheduler.applets.current().memory();
     let results = try {
         let value = memory.get(*ptr, *len)?;
-        let res = match scheduler.store.insert(*key as usize, value) {
+        let store = &mut scheduler.store;
+        let usage = &mut scheduler.usage;
+        let res = match namespace::key(id, *key as usize).and_then(|x| {
+            usage.check(store, id, &(x .. x + 1), value.len(), B::applet_store_quota())?;
+            store.insert(x, value)
+        }) {
             Ok(()) => 0.into(),
             Err(e) => convert(e).into(),
         };
@@ -46,7 +52,9 @@ fn insert<B: Board>(mut call: SchedulerCall<B, api::insert::Sig>) {
 
 fn remove<B: Board>(mut call: SchedulerCall<B, api::remove::Sig>) {
     let api::remove::Params { key } = call.read();
-    let res = match call.scheduler().store.remove(*key as usize) {
+    let scheduler = call.scheduler();
+    let id = scheduler.applets.current_id();
+    let res = match namespace::key(id, *key as usize).and_then(|x| scheduler.store.remove(x)) {
         Ok(()) => 0.into(),
         Err(e) => convert(e).into(),
     };
@@ -59,10 +67,11 @@ fn find<B: Board>(mut call: SchedulerCall<B, api::find::Sig>) {
     #[cfg(not(feature = "multivalue"))]
     let api::find::Params { key, ptr: ptr_ptr, len: len_ptr } = call.read();
     let scheduler = call.scheduler();
-    let mut memory = scheduler.applet.memory();
+    let id = scheduler.applets.current_id();
+    let mut memory = scheduler.applets.current().memory();
     let results = try {
         let mut results = api::find::Results::default();
-        match scheduler.store.find(*key as usize) {
+        match namespace::key(id, *key as usize).and_then(|x| scheduler.store.find(x)) {
             Ok(None) => (),
             Ok(Some(value)) => {
                 let len = value.len() as u32;
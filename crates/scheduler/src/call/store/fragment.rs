@@ -19,7 +19,7 @@ use wasefire_board_api::Api as Board;
 use wasefire_store::fragment;
 
 use super::convert;
-use crate::{DispatchSchedulerCall, SchedulerCall, Trap};
+use crate::{namespace, DispatchSchedulerCall, SchedulerCall, Trap};
 
 pub fn process<B: Board>(call: Api<DispatchSchedulerCall<B>>) {
     match call {
@@ -32,11 +32,17 @@ pub fn process<B: Board>(call: Api<DispatchSchedulerCall<B>>) {
 fn insert<B: Board>(mut call: SchedulerCall<B, api::insert::Sig>) {
     let api::insert::Params { keys, ptr, len } = call.read();
     let scheduler = call.scheduler();
-    let memory = scheduler.applet.memory();
+    let id = scheduler.applets.current_id();
+    let memory = scheduler.applets.current().memory();
     let results = try {
         let keys = decode_keys(keys)?;
         let value = memory.get(*ptr, *len)?;
-        let res = match fragment::write(&mut scheduler.store, &keys, value) {
+        let store = &mut scheduler.store;
+        let usage = &mut scheduler.usage;
+        let res = match namespace::keys(id, keys).and_then(|x| {
+            usage.check(store, id, &x, value.len(), B::applet_store_quota())?;
+            fragment::write(store, &x, value)
+        }) {
             Ok(()) => 0.into(),
             Err(e) => convert(e).into(),
         };
@@ -48,7 +54,11 @@ fn insert<B: Board>(mut call: SchedulerCall<B, api::insert::Sig>) {
 fn remove<B: Board>(mut call: SchedulerCall<B, api::remove::Sig>) {
     let api::remove::Params { keys } = call.read();
     let results = try {
-        let res = match fragment::delete(&mut call.scheduler().store, &decode_keys(keys)?) {
+        let keys = decode_keys(keys)?;
+        let scheduler = call.scheduler();
+        let id = scheduler.applets.current_id();
+        let store = &mut scheduler.store;
+        let res = match namespace::keys(id, keys).and_then(|x| fragment::delete(store, &x)) {
             Ok(()) => 0.into(),
             Err(e) => convert(e).into(),
         };
@@ -60,10 +70,12 @@ fn remove<B: Board>(mut call: SchedulerCall<B, api::remove::Sig>) {
 fn find<B: Board>(mut call: SchedulerCall<B, api::find::Sig>) {
     let api::find::Params { keys, ptr: ptr_ptr, len: len_ptr } = call.read();
     let scheduler = call.scheduler();
-    let mut memory = scheduler.applet.memory();
+    let id = scheduler.applets.current_id();
+    let mut memory = scheduler.applets.current().memory();
     let results = try {
         let mut results = api::find::Results::default();
-        match fragment::read(&scheduler.store, &decode_keys(keys)?) {
+        let keys = namespace::keys(id, decode_keys(keys)?);
+        match keys.and_then(|x| fragment::read(&scheduler.store, &x)) {
             Ok(None) => (),
             Ok(Some(value)) => {
                 let len = value.len() as u32;
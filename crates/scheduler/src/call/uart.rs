@@ -39,7 +39,7 @@ fn count<B: Board>(call: SchedulerCall<B, api::count::Sig>) {
 fn read<B: Board>(mut call: SchedulerCall<B, api::read::Sig>) {
     let api::read::Params { uart, ptr, len } = call.read();
     let scheduler = call.scheduler();
-    let memory = scheduler.applet.memory();
+    let memory = scheduler.applets.current().memory();
     let results = try {
         let uart = Id::new(*uart as usize).ok_or(Trap)?;
         let output = memory.get_mut(*ptr, *len)?;
@@ -55,7 +55,7 @@ fn read<B: Board>(mut call: SchedulerCall<B, api::read::Sig>) {
 fn write<B: Board>(mut call: SchedulerCall<B, api::write::Sig>) {
     let api::write::Params { uart, ptr, len } = call.read();
     let scheduler = call.scheduler();
-    let memory = scheduler.applet.memory();
+    let memory = scheduler.applets.current().memory();
     let results = try {
         let uart = Id::new(*uart as usize).ok_or(Trap)?;
         let input = memory.get(*ptr, *len)?;
@@ -75,7 +75,7 @@ fn register<B: Board>(mut call: SchedulerCall<B, api::register::Sig>) {
     let results = try {
         let uart = Id::new(*uart as usize).ok_or(Trap)?;
         let event = convert_event(uart, *event)?;
-        scheduler.applet.enable(Handler {
+        scheduler.enable_event(Handler {
             key: Key::from(&event).into(),
             inst,
             func: *handler_func,
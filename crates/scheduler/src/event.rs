@@ -16,13 +16,14 @@ use alloc::vec;
 use core::borrow::Borrow;
 
 use derivative::Derivative;
-use wasefire_board_api::{Api as Board, Event};
+use wasefire_board_api::{self as board, Api as Board};
 use wasefire_interpreter::InstId;
 use wasefire_logger as logger;
 
-use crate::Scheduler;
+use crate::{Scheduler, Trap};
 
 pub mod button;
+pub mod ipc;
 pub mod timer;
 pub mod uart;
 pub mod usb;
@@ -34,6 +35,7 @@ pub mod usb;
 #[derivative(Ord = "feature_allow_slow_enum")]
 pub enum Key<B: Board> {
     Button(button::Key<B>),
+    Ipc(ipc::Key),
     Timer(timer::Key<B>),
     Uart(uart::Key<B>),
     Usb(usb::Key),
@@ -53,17 +55,58 @@ impl<B: Board> PartialOrd for Key<B> {
     }
 }
 
+impl<B: Board> Key<B> {
+    /// Disables the board event associated with this key.
+    pub fn disable(&self) -> Result<(), Trap> {
+        match self {
+            Key::Button(x) => x.disable(),
+            // Messages don't involve the board.
+            Key::Ipc(_) => Ok(()),
+            Key::Timer(x) => x.disable(),
+            Key::Uart(x) => x.disable(),
+            Key::Usb(x) => x.disable::<B>(),
+        }
+    }
+}
+
+impl<'a, B: Board> From<&'a board::Event<B>> for Key<B> {
+    fn from(event: &'a board::Event<B>) -> Self {
+        match event {
+            board::Event::Button(event) => Key::Button(event.into()),
+            // Protocol events are handled by the scheduler and never reach applets.
+            board::Event::Protocol(_) => unreachable!(),
+            board::Event::Timer(event) => Key::Timer(event.into()),
+            board::Event::Uart(event) => Key::Uart(event.into()),
+            board::Event::Usb(event) => Key::Usb(event.into()),
+        }
+    }
+}
+
 impl<'a, B: Board> From<&'a Event<B>> for Key<B> {
     fn from(event: &'a Event<B>) -> Self {
         match event {
-            Event::Button(event) => Key::Button(event.into()),
-            Event::Timer(event) => Key::Timer(event.into()),
-            Event::Uart(event) => Key::Uart(event.into()),
-            Event::Usb(event) => Key::Usb(event.into()),
+            Event::Board(event) => event.into(),
+            Event::Ipc(event) => Key::Ipc(event.into()),
         }
     }
 }
 
+/// Events of applets.
+///
+/// Besides board events, applets receive events from the scheduler itself.
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""), PartialEq(bound = ""), Eq(bound = ""))]
+pub enum Event<B: Board> {
+    Board(board::Event<B>),
+    Ipc(ipc::Event),
+}
+
+impl<B: Board> From<board::Event<B>> for Event<B> {
+    fn from(event: board::Event<B>) -> Self {
+        Event::Board(event)
+    }
+}
+
 #[derive(Derivative)]
 #[derivative(Debug(bound = ""), Clone(bound = ""))]
 #[derivative(PartialEq(bound = ""), Eq(bound = ""), Ord(bound = ""))]
@@ -89,25 +132,28 @@ impl<B: Board> Borrow<Key<B>> for Handler<B> {
 }
 
 pub fn process<B: Board>(scheduler: &mut Scheduler<B>, event: Event<B>) {
-    let Handler { inst, func, data, .. } = match scheduler.applet.get(Key::from(&event)) {
-        Some(x) => x,
+    let applet = scheduler.applets.current();
+    let Handler { inst, func, data, .. } = match applet.get(Key::from(&event)) {
+        Some(x) => x.clone(),
         None => {
             // This should not happen because we remove pending events when disabling an event.
             logger::error!("Missing handler for event.");
             return;
         }
     };
-    let mut params = vec![*func, *data];
+    let mut params = vec![func, data];
     match event {
-        Event::Button(event) => button::process(event, &mut params),
-        Event::Timer(_) => timer::process(),
-        Event::Uart(_) => uart::process(),
-        Event::Usb(event) => usb::process(event),
+        Event::Board(board::Event::Button(event)) => button::process(event, &mut params),
+        Event::Board(board::Event::Protocol(_)) => unreachable!(),
+        Event::Board(board::Event::Timer(_)) => timer::process(),
+        Event::Board(board::Event::Uart(_)) => uart::process(),
+        Event::Board(board::Event::Usb(event)) => usb::process(event),
+        Event::Ipc(_) => ipc::process(),
     }
     let name = match params.len() - 2 {
         0 => "cb0",
         1 => "cb1",
         _ => unimplemented!(),
     };
-    scheduler.call(*inst, name, &params);
+    scheduler.call(inst, name, &params);
 }
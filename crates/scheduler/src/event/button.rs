@@ -15,9 +15,11 @@
 use alloc::vec::Vec;
 
 use derivative::Derivative;
-use wasefire_board_api::button::Event;
+use wasefire_board_api::button::{Api as _, Event};
 use wasefire_board_api::{self as board, Api as Board, Id};
 
+use crate::Trap;
+
 #[derive(Derivative)]
 #[derivative(Debug(bound = ""), Copy(bound = ""), Hash(bound = ""))]
 #[derivative(PartialEq(bound = ""), Eq(bound = ""), Ord(bound = ""))]
@@ -45,6 +47,12 @@ impl<B: Board> From<Key<B>> for crate::event::Key<B> {
     }
 }
 
+impl<B: Board> Key<B> {
+    pub fn disable(&self) -> Result<(), Trap> {
+        board::Button::<B>::disable(self.button).map_err(|_| Trap)
+    }
+}
+
 impl<'a, B: Board> From<&'a Event<B>> for Key<B> {
     fn from(event: &'a Event<B>) -> Self {
         Key { button: event.button }
@@ -0,0 +1,51 @@
+// Copyright 2023 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use wasefire_board_api::Api as Board;
+
+use crate::stores::AppletId;
+
+/// The mailbox of an applet is not empty.
+///
+/// This event doesn't come from the board. The scheduler pushes it when an applet sends a message.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Event {
+    /// The receiving applet.
+    pub applet: AppletId,
+}
+
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Key {
+    pub applet: AppletId,
+}
+
+impl<B: Board> From<Key> for crate::event::Key<B> {
+    fn from(key: Key) -> Self {
+        crate::event::Key::Ipc(key)
+    }
+}
+
+impl<B: Board> From<Event> for crate::event::Event<B> {
+    fn from(event: Event) -> Self {
+        crate::event::Event::Ipc(event)
+    }
+}
+
+impl<'a> From<&'a Event> for Key {
+    fn from(event: &'a Event) -> Self {
+        Key { applet: event.applet }
+    }
+}
+
+pub fn process() {}
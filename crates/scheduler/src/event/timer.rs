@@ -13,9 +13,11 @@
 // limitations under the License.
 
 use derivative::Derivative;
-use wasefire_board_api::timer::Event;
+use wasefire_board_api::timer::{Api as _, Event};
 use wasefire_board_api::{self as board, Api as Board, Id};
 
+use crate::Trap;
+
 #[derive(Derivative)]
 #[derivative(Debug(bound = ""), Copy(bound = ""), Hash(bound = ""))]
 #[derivative(PartialEq(bound = ""), Eq(bound = ""), Ord(bound = ""))]
@@ -43,6 +45,12 @@ impl<B: Board> From<Key<B>> for crate::event::Key<B> {
     }
 }
 
+impl<B: Board> Key<B> {
+    pub fn disable(&self) -> Result<(), Trap> {
+        board::Timer::<B>::disarm(self.timer).map_err(|_| Trap)
+    }
+}
+
 impl<'a, B: Board> From<&'a Event<B>> for Key<B> {
     fn from(event: &'a Event<B>) -> Self {
         Key { timer: event.timer }
@@ -13,9 +13,11 @@
 // limitations under the License.
 
 use derivative::Derivative;
-use wasefire_board_api::uart::{Direction, Event};
+use wasefire_board_api::uart::{Api as _, Direction, Event};
 use wasefire_board_api::{self as board, Api as Board, Id};
 
+use crate::Trap;
+
 #[derive(Derivative)]
 #[derivative(Debug(bound = ""), Copy(bound = ""), Hash(bound = ""))]
 #[derivative(PartialEq(bound = ""), Eq(bound = ""), Ord(bound = ""))]
@@ -44,6 +46,12 @@ impl<B: Board> From<Key<B>> for crate::event::Key<B> {
     }
 }
 
+impl<B: Board> Key<B> {
+    pub fn disable(&self) -> Result<(), Trap> {
+        board::Uart::<B>::disable(self.uart, self.direction).map_err(|_| Trap)
+    }
+}
+
 impl<'a, B: Board> From<&'a Event<B>> for Key<B> {
     fn from(event: &'a Event<B>) -> Self {
         Key { uart: event.uart, direction: event.direction }
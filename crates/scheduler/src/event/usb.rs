@@ -17,6 +17,8 @@ pub mod serial;
 use wasefire_board_api::usb::Event;
 use wasefire_board_api::Api as Board;
 
+use crate::Trap;
+
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Key {
     Serial(serial::Key),
@@ -28,6 +30,14 @@ impl<B: Board> From<Key> for crate::event::Key<B> {
     }
 }
 
+impl Key {
+    pub fn disable<B: Board>(&self) -> Result<(), Trap> {
+        match self {
+            Key::Serial(x) => x.disable::<B>(),
+        }
+    }
+}
+
 impl<'a> From<&'a Event> for Key {
     fn from(event: &'a Event) -> Self {
         match event {
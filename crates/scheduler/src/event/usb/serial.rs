@@ -12,8 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use wasefire_board_api::usb::serial::Event;
-use wasefire_board_api::Api as Board;
+use wasefire_board_api::usb::serial::{Api as _, Event};
+use wasefire_board_api::{self as board, Api as Board};
+
+use crate::Trap;
 
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Key {
@@ -27,6 +29,16 @@ impl<B: Board> From<Key> for crate::event::Key<B> {
     }
 }
 
+impl Key {
+    pub fn disable<B: Board>(&self) -> Result<(), Trap> {
+        let event = match self {
+            Key::Read => Event::Read,
+            Key::Write => Event::Write,
+        };
+        board::usb::Serial::<B>::disable(&event).map_err(|_| Trap)
+    }
+}
+
 impl<'a> From<&'a Event> for Key {
     fn from(event: &'a Event) -> Self {
         match event {
@@ -18,6 +18,7 @@
 
 extern crate alloc;
 
+use alloc::boxed::Box;
 use alloc::collections::VecDeque;
 use alloc::vec;
 use alloc::vec::Vec;
@@ -27,20 +28,34 @@ use core::ops::Range;
 
 use bytemuck::{AnyBitPattern, NoUninit};
 use derivative::Derivative;
-use event::Key;
-use stores::{Applet, EventAction};
+use event::{Handler, Key};
+use stores::{Applet, AppletBinary, AppletId, Applets, EventAction, Status};
 use wasefire_applet_api::{self as api, Api, ArrayU32, Dispatch, Id, Signature};
-use wasefire_board_api::{self as board, Api as Board, Singleton, Support};
+#[cfg(feature = "profile")]
+use wasefire_board_api::debug::Api as _;
+use wasefire_board_api::protocol::Api as _;
+use wasefire_board_api::timer::{Api as _, Command};
+use wasefire_board_api::{
+    self as board, Api as Board, AppletExit, RestartPolicy, Singleton, Support,
+};
 use wasefire_interpreter::{
-    self as interpreter, Call, Error, InstId, Module, RunAnswer, RunResult, Store, Val,
+    self as interpreter, Call, CoreFrame, Error, InstId, Linker, Module, RunAnswer, RunResult,
+    Store, Val,
 };
-use wasefire_logger::{self as logger, *};
-use wasefire_store as store;
+use wasefire_logger::*;
+use {wasefire_protocol as protocol, wasefire_store as store};
 
 mod call;
 mod event;
+mod manifest;
+mod marker;
+mod namespace;
 #[cfg(feature = "debug")]
 mod perf;
+pub mod persist;
+mod platform;
+mod pool;
+mod signature;
 mod stores;
 
 #[derive(Derivative)]
@@ -73,18 +88,44 @@ impl<B: Board> Events<B> {
     }
 }
 
+/// Maximum number of applets running concurrently.
+pub const MAX_APPLETS: usize = 2;
+
+/// Number of instructions an applet may execute before other applets get a chance to run.
+const TIME_SLICE: u64 = 100_000;
+
 pub struct Scheduler<B: Board> {
     store: store::Store<B::Storage>,
+    /// Bounds the number of bytes stored by applets in their namespace.
+    usage: namespace::Usage,
+    applet_storage: Option<board::Applet<B>>,
     host_funcs: Vec<Api<Id>>,
-    applet: Applet<B>,
+    /// Links the host functions in the same order as `host_funcs`.
+    linker: &'static Linker<'static>,
+    applets: Applets<B>,
+    /// Memory pool for the linear memory of applets.
+    pool: pool::Pool,
     timers: Vec<Option<Timer>>,
+    /// Whether a protocol request may be pending.
+    protocol: bool,
+    /// Recent applet logs, to be returned by the protocol.
+    logs: VecDeque<u8>,
     #[cfg(feature = "debug")]
     perf: perf::Perf<B>,
+    #[cfg(feature = "debugger")]
+    debugger: Option<NewDebugger>,
 }
 
+/// Creates the debugger of an applet given its name (if any).
+#[cfg(feature = "debugger")]
+type NewDebugger = fn(&str) -> Option<Box<dyn interpreter::Debugger>>;
+
 #[derive(Clone)]
 struct Timer {
-    // TODO: Add AppletId.
+    applet: AppletId,
+
+    /// Whether the timer is used by the scheduler to restart the applet.
+    restart: bool,
 }
 
 impl<B: Board> core::fmt::Debug for Scheduler<B> {
@@ -165,12 +206,18 @@ impl<'a, B: Board, T: Signature> SchedulerCall<'a, B, T> {
                 self.scheduler().perf.record(perf::Slot::Applets);
                 self.erased.scheduler.process_answer(answer);
             }
-            Err(Trap) => logger::panic!("Applet trapped in host."),
+            Err(Trap) => {
+                let frames = self.call().backtrace();
+                error!("Host call trapped.");
+                print_backtrace(self.store(), &frames);
+                let scheduler = self.scheduler();
+                scheduler.crash(scheduler.applets.current_id(), AppletExit::HostTrap);
+            }
         }
     }
 
     fn applet(&mut self) -> &mut Applet<B> {
-        &mut self.erased.scheduler.applet
+        self.erased.scheduler.applets.current()
     }
 
     fn store(&mut self) -> &mut Store<'static> {
@@ -183,13 +230,39 @@ impl<'a, B: Board, T: Signature> SchedulerCall<'a, B, T> {
 }
 
 impl<B: Board> Scheduler<B> {
-    pub fn run(wasm: &'static [u8]) -> ! {
+    pub fn run() -> ! {
+        Self::new().main()
+    }
+
+    /// Same as [`Self::run()`] but with debuggers for applets.
+    ///
+    /// The function is called with the name of each applet when it starts and returns its
+    /// debugger (if any).
+    #[cfg(feature = "debugger")]
+    pub fn run_with_debugger(debugger: NewDebugger) -> ! {
         let mut scheduler = Self::new();
-        debug!("Loading applet.");
-        scheduler.load(wasm);
+        scheduler.debugger = Some(debugger);
+        scheduler.main()
+    }
+
+    fn main(mut self) -> ! {
+        let scheduler = &mut self;
+        scheduler.load_persisted();
         loop {
             scheduler.flush_events();
-            scheduler.process_applet();
+            if core::mem::replace(&mut scheduler.protocol, false) {
+                platform::process(scheduler);
+            }
+            if let Some(id) = scheduler.applets.next_restart() {
+                scheduler.restart(id);
+            }
+            match scheduler.applets.next_ready() {
+                Some(id) => {
+                    scheduler.applets.set_current(id);
+                    scheduler.process_applet();
+                }
+                None => scheduler.wait_event(),
+            }
         }
     }
 
@@ -198,75 +271,355 @@ impl<B: Board> Scheduler<B> {
         Api::<Id>::iter(&mut host_funcs, |x| x);
         host_funcs.sort_by_key(|x| x.descriptor().name);
         assert!(host_funcs.windows(2).all(|x| x[0].descriptor().name != x[1].descriptor().name));
-        let mut applet = Applet::default();
-        let store = applet.store_mut();
+        let mut linker = Linker::default();
         for f in &host_funcs {
             let d = f.descriptor();
-            store.link_func("env", d.name, d.params, d.results).unwrap();
+            linker.link_func("env", d.name, d.params, d.results).unwrap();
+        }
+        // The scheduler is never dropped, so the linker lives forever.
+        let linker = Box::leak(Box::new(linker));
+        if board::Protocol::<B>::SUPPORT {
+            board::Protocol::<B>::enable().unwrap();
         }
         Self {
             store: store::Store::new(board::Storage::<B>::take().unwrap()).ok().unwrap(),
+            usage: namespace::Usage::default(),
+            applet_storage: board::Applet::<B>::take(),
+            applets: Applets::new(linker),
+            // SAFETY: The scheduler is created at most once, because `run()` never returns.
+            pool: pool::Pool::new(unsafe { B::applet_memory() }),
             host_funcs,
-            applet,
+            linker,
             timers: vec![None; board::Timer::<B>::SUPPORT],
+            protocol: false,
+            logs: VecDeque::new(),
             #[cfg(feature = "debug")]
             perf: perf::Perf::default(),
+            #[cfg(feature = "debugger")]
+            debugger: None,
+        }
+    }
+
+    /// Loads the applets from the applet storage (if any).
+    fn load_persisted(&mut self) {
+        for slot in 0 .. MAX_APPLETS {
+            let storage = match &self.applet_storage {
+                Some(x) => x,
+                None => return warn!("No applet storage."),
+            };
+            let persist::Applet { name, wasm, marker } = match persist::read(storage, slot) {
+                Ok(Some(x)) => x,
+                Ok(None) => continue,
+                Err(e) => {
+                    error!("Failed to read applet storage: {}", Debug2Format(&e));
+                    continue;
+                }
+            };
+            debug!("Loading applet {} in slot {}.", name.as_str(), slot);
+            let side_table = marker.and_then(|x| marker::open::<B>(&self.store, &wasm, &x));
+            if side_table.is_none() {
+                debug!("Applet {} has no valid marker.", name.as_str());
+            }
+            let mut binary = AppletBinary::new(name, wasm);
+            binary.side_table = side_table;
+            if self.load(AppletId(slot), binary).is_err() {
+                error!("Failed to load invalid applet.");
+            }
         }
     }
 
-    fn load(&mut self, wasm: &'static [u8]) {
-        #[repr(align(16))]
-        struct Memory([u8; 0x10000]);
-        static mut MEMORY: Memory = Memory([0; 0x10000]);
-        #[cfg(not(feature = "unsafe-skip-validation"))]
-        let module = Module::new(wasm).unwrap();
-        // SAFETY: The module is valid by the feature invariant.
-        #[cfg(feature = "unsafe-skip-validation")]
-        let module = unsafe { Module::new_unchecked(wasm) };
-        let store = self.applet.store_mut();
-        // SAFETY: This function is called once in `run()`.
-        let inst = store.instantiate(module, unsafe { &mut MEMORY.0 }).unwrap();
+    /// Unloads the applet of a slot (if any) and loads a new one.
+    ///
+    /// If the new applet is invalid (including when it imports functions outside its manifest or is
+    /// not signed by the board trust anchor), the slot is left untouched. If there is not enough
+    /// memory for the new applet or it fails to instantiate or initialize, the slot is left empty.
+    fn load(&mut self, id: AppletId, binary: AppletBinary) -> Result<(), protocol::Error> {
+        self.start(id, binary, 0)
+    }
+
+    /// Same as [`Self::load()`] but with the number of consecutive abnormal exits of the applet.
+    fn start(
+        &mut self, id: AppletId, mut binary: AppletBinary, crashes: u32,
+    ) -> Result<(), protocol::Error> {
+        // SAFETY: The module is dropped before the binary, because the binary is stored in the
+        // applet after its store.
+        let wasm = unsafe { binary.wasm() };
+        if !signature::check::<B>(wasm) {
+            error!("Applet {} is not signed by the trust anchor.", binary.name.as_str());
+            B::applet_rejected(&binary.name);
+            return Err(protocol::Error::InvalidSignature);
+        }
+        let module = match &binary.side_table {
+            // SAFETY: The side-table is only set for valid modules and comes from their
+            // serialization.
+            Some(side_table) => unsafe { Module::new_with_side_table(wasm, side_table) }
+                .map_err(|_| protocol::Error::InvalidApplet)?,
+            None => {
+                #[cfg(not(feature = "unsafe-skip-validation"))]
+                let module = Module::new(wasm).map_err(|_| protocol::Error::InvalidApplet)?;
+                // SAFETY: The module is valid by the feature invariant.
+                #[cfg(feature = "unsafe-skip-validation")]
+                let module = unsafe { Module::new_unchecked(wasm) };
+                binary.side_table = Some(module.serialize_side_table().into_boxed_slice());
+                module
+            }
+        };
+        if !manifest::check(&module, &self.host_funcs) {
+            warn!("Applet {} uses functions outside its manifest.", binary.name.as_str());
+            return Err(protocol::Error::InvalidApplet);
+        }
+        let senders = manifest::senders(&module);
+        self.unload(id);
+        let Some(memory) = self.pool.alloc(id, &module) else {
+            warn!("Not enough memory for applet {}.", binary.name.as_str());
+            return Err(protocol::Error::NoSpace);
+        };
+        #[cfg(feature = "debugger")]
+        let debugger = self.debugger.and_then(|x| x(&binary.name));
+        self.applets.set_current(id);
+        let applet = self.applets.current();
+        applet.binary = Some(binary);
+        applet.crashes = crashes;
+        applet.senders = senders;
+        let store = applet.store_mut();
+        let inst = match store.instantiate(module, memory) {
+            Ok(x) => x,
+            Err(e) => {
+                warn!("Failed to instantiate applet: {}", Debug2Format(&e));
+                self.discard(id);
+                return Err(protocol::Error::InvalidApplet);
+            }
+        };
+        #[cfg(feature = "debugger")]
+        store.set_debugger(debugger);
+        #[cfg(feature = "profile")]
+        store.set_profile(true);
         #[cfg(feature = "debug")]
         self.perf.record(perf::Slot::Platform);
-        match store.invoke(inst, "init", vec![]) {
-            Ok(RunResult::Done(x)) => assert!(x.is_empty()),
-            Ok(RunResult::Host { .. }) => logger::panic!("init called into host"),
-            Err(Error::NotFound) => (),
-            Err(e) => core::panic!("{e:?}"),
-        }
+        // The store is not metered before main.
+        store.set_fuel(None);
+        let trapped = match store.invoke(inst, "init", vec![]) {
+            Ok(RunResult::Done(x)) => {
+                assert!(x.is_empty());
+                false
+            }
+            Ok(RunResult::Host { .. }) => {
+                warn!("init called into host");
+                true
+            }
+            Ok(RunResult::OutOfFuel) => unreachable!(),
+            Err(Error::NotFound) => false,
+            Err(Error::Trap(_)) => true,
+            Err(e) => {
+                warn!("Failed to initialize applet: {}", Debug2Format(&e));
+                self.discard(id);
+                return Err(protocol::Error::InvalidApplet);
+            }
+        };
         #[cfg(feature = "debug")]
         self.perf.record(perf::Slot::Applets);
-        self.call(inst, "main", &[]);
+        match trapped {
+            true => self.trap(id),
+            false => {
+                self.applets.current().store_mut().set_fuel(Some(TIME_SLICE));
+                self.call(inst, "main", &[]);
+            }
+        }
+        Ok(())
+    }
+
+    /// Empties the slot of an applet that failed to start.
+    fn discard(&mut self, id: AppletId) {
+        self.applets.reset(id, self.linker);
+        self.pool.free(id);
+    }
+
+    /// Logs the core dump of an applet that trapped and crashes it.
+    fn trap(&mut self, id: AppletId) {
+        let store = self.applets.get_mut(id).store_mut();
+        if let Some(dump) = store.core_dump() {
+            error!("Applet trapped: {}", Display2Format(&dump.reason));
+            print_backtrace(store, &dump.frames);
+        }
+        self.crash(id, AppletExit::Trap);
+    }
+
+    /// Cleans up an applet after it exited abnormally and applies the restart policy.
+    ///
+    /// The applet stays installed but its handlers, timers, and hash contexts are dropped.
+    fn crash(&mut self, id: AppletId, exit: AppletExit) {
+        let applet = self.applets.get_mut(id);
+        let binary = applet.binary.take().unwrap();
+        let crashes = applet.crashes.saturating_add(1);
+        error!("Applet {} exited with {}.", binary.name.as_str(), Debug2Format(&exit));
+        B::applet_exited(&binary.name, exit);
+        #[cfg(feature = "profile")]
+        self.report_profile(id, &binary.name);
+        self.unload(id);
+        let status = match B::restart_policy() {
+            RestartPolicy::Never => Status::Crashed,
+            RestartPolicy::Immediately => Status::Restarting,
+            RestartPolicy::Backoff { initial_ms, max_ms } => {
+                let factor = 1usize.checked_shl(crashes - 1).unwrap_or(usize::MAX);
+                let delay = initial_ms.saturating_mul(factor);
+                match self.arm_restart(id, core::cmp::min(delay, max_ms)) {
+                    Some(()) => Status::Crashed,
+                    None => {
+                        warn!("No timer to delay restart.");
+                        Status::Restarting
+                    }
+                }
+            }
+        };
+        let applet = self.applets.get_mut(id);
+        applet.binary = Some(binary);
+        applet.crashes = crashes;
+        applet.status = status;
+    }
+
+    /// Arms a timer to restart an applet after a delay.
+    fn arm_restart(&mut self, id: AppletId, duration_ms: usize) -> Option<()> {
+        let timer = self.timers.iter().position(|x| x.is_none())?;
+        let command = Command { periodic: false, duration_ms };
+        board::Timer::<B>::arm(board::Id::new(timer).unwrap(), &command).ok()?;
+        self.timers[timer] = Some(Timer { applet: id, restart: true });
+        Some(())
+    }
+
+    /// Restarts an applet after it exited abnormally.
+    fn restart(&mut self, id: AppletId) {
+        let applet = self.applets.get_mut(id);
+        let crashes = applet.crashes;
+        let binary = applet.binary.take().unwrap();
+        info!("Restarting applet {}.", binary.name.as_str());
+        if self.start(id, binary, crashes).is_err() {
+            error!("Failed to restart invalid applet.");
+        }
+    }
+
+    /// Unloads the applet of a slot (if any).
+    ///
+    /// The board events and timers of the applet are disabled.
+    fn unload(&mut self, id: AppletId) {
+        #[cfg(feature = "profile")]
+        if let Some(binary) = &self.applets.get(id).binary {
+            self.report_profile(id, &binary.name);
+        }
+        for handler in self.applets.get(id).handlers() {
+            if handler.key.disable().is_err() {
+                warn!("Failed to disable event of unloaded applet.");
+            }
+        }
+        for (i, timer) in self.timers.iter_mut().enumerate() {
+            let restart = match timer {
+                Some(x) if x.applet == id => x.restart,
+                _ => continue,
+            };
+            if restart && board::Timer::<B>::disarm(board::Id::new(i).unwrap()).is_err() {
+                warn!("Failed to disarm restart timer of unloaded applet.");
+            }
+            *timer = None;
+        }
+        self.applets.reset(id, self.linker);
+        self.pool.free(id);
+    }
+
+    /// Reports the profile of an applet to the board in folded-stack format (if any).
+    #[cfg(feature = "profile")]
+    fn report_profile(&self, id: AppletId, name: &str) {
+        use core::fmt::Write;
+        let store = self.applets.get(id).store();
+        let Some(profile) = store.profile() else { return };
+        let mut stacks = alloc::string::String::new();
+        for sample in profile {
+            stacks.push_str(name);
+            for &(inst, func) in &sample.stack {
+                match store.func_name(inst, func) {
+                    Some(name) => write!(stacks, ";{:#}", rustc_demangle::demangle(name)),
+                    None => write!(stacks, ";function {func}"),
+                }
+                .unwrap();
+            }
+            writeln!(stacks, " {}", sample.count).unwrap();
+        }
+        board::Debug::<B>::profile(&stacks);
+    }
+
+    fn wait_event(&mut self) {
+        #[cfg(feature = "debug")]
+        self.perf.record(perf::Slot::Platform);
+        let event = B::wait_event();
+        #[cfg(feature = "debug")]
+        self.perf.record(perf::Slot::Waiting);
+        self.push_event(event);
     }
 
     fn flush_events(&mut self) {
         while let Some(event) = B::try_event() {
-            self.applet.push(event);
+            self.push_event(event);
         }
     }
 
-    /// Returns whether execution should resume.
-    fn process_event(&mut self) -> bool {
-        let event = loop {
-            match self.applet.pop() {
-                EventAction::Handle(event) => break event,
-                EventAction::Wait => {
-                    #[cfg(feature = "debug")]
-                    self.perf.record(perf::Slot::Platform);
-                    let event = B::wait_event();
-                    #[cfg(feature = "debug")]
-                    self.perf.record(perf::Slot::Waiting);
-                    self.applet.push(event);
+    fn push_event(&mut self, event: board::Event<B>) {
+        if let board::Event::Timer(board::timer::Event { timer }) = &event {
+            if let Some(Timer { applet, restart: true }) = self.timers[**timer] {
+                // The timer must be disarmed before it can be armed again.
+                if board::Timer::<B>::disarm(*timer).is_err() {
+                    warn!("Failed to disarm restart timer.");
                 }
-                EventAction::Reply => return true,
+                self.timers[**timer] = None;
+                self.applets.get_mut(applet).status = Status::Restarting;
+                return;
             }
-        };
-        event::process(self, event);
+        }
+        match event {
+            board::Event::Protocol(_) => self.protocol = true,
+            event => match self.applets.owner(Key::from(&event)) {
+                Some(id) => self.applets.get_mut(id).push(event.into()),
+                // This can happen after an event is disabled and the event queue of the board is
+                // flushed.
+                None => trace!("Discarding {}", Debug2Format(&event)),
+            },
+        }
+    }
+
+    /// Appends a line of the current applet to the applet logs.
+    fn log(&mut self, line: &str) {
+        const MAX_LOGS: usize = 1024;
+        if let Some(binary) = &self.applets.current().binary {
+            self.logs.extend(binary.name.as_bytes().iter().chain(b": "));
+        }
+        self.logs.extend(line.as_bytes().iter().chain(b"\n"));
+        let excess = self.logs.len().saturating_sub(MAX_LOGS);
+        self.logs.drain(.. excess);
+    }
+
+    /// Returns whether execution of the current applet should resume.
+    ///
+    /// If the applet is waiting for an event, it is not ready until an event is pushed for it, and
+    /// the scheduler executes other applets meanwhile.
+    fn process_event(&mut self) -> bool {
+        match self.applets.current().pop() {
+            EventAction::Handle(event) => event::process(self, event),
+            EventAction::Wait => (),
+            EventAction::Reply => return true,
+        }
         false
     }
 
     fn process_applet(&mut self) {
-        let call = match self.applet.store_mut().last_call() {
+        let store = self.applets.current().store_mut();
+        store.set_fuel(Some(TIME_SLICE));
+        if store.is_out_of_fuel() {
+            #[cfg(feature = "debug")]
+            self.perf.record(perf::Slot::Platform);
+            let answer = store.resume().map(|x| x.forget());
+            #[cfg(feature = "debug")]
+            self.perf.record(perf::Slot::Applets);
+            self.process_answer(answer);
+            return;
+        }
+        let call = match store.last_call() {
             Some(x) => x,
             None => {
                 self.process_event();
@@ -283,8 +636,22 @@ impl<B: Board> Scheduler<B> {
         call::process(call);
     }
 
+    /// Registers a handler for the current applet.
+    ///
+    /// Board events are routed to the applet that registered them. Only one applet may register a
+    /// given event.
+    fn enable_event(&mut self, handler: Handler<B>) -> Result<(), Trap> {
+        if let Some(id) = self.applets.owner(handler.key) {
+            if id != self.applets.current_id() {
+                warn!("Tried to register the event of another applet");
+                return Err(Trap);
+            }
+        }
+        self.applets.current().enable(handler)
+    }
+
     fn disable_event(&mut self, key: Key<B>) -> Result<(), Trap> {
-        self.applet.disable(key)?;
+        self.applets.current().disable(key)?;
         self.flush_events();
         Ok(())
     }
@@ -294,7 +661,8 @@ impl<B: Board> Scheduler<B> {
         let args = args.iter().map(|&x| Val::I32(x)).collect();
         #[cfg(feature = "debug")]
         self.perf.record(perf::Slot::Platform);
-        let answer = self.applet.store_mut().invoke(inst, name, args).map(|x| x.forget());
+        let answer =
+            self.applets.current().store_mut().invoke(inst, name, args).map(|x| x.forget());
         #[cfg(feature = "debug")]
         self.perf.record(perf::Slot::Applets);
         self.process_answer(answer);
@@ -305,11 +673,29 @@ impl<B: Board> Scheduler<B> {
             Ok(RunAnswer::Done(x)) => {
                 debug!("Thread is done.");
                 debug_assert!(x.is_empty());
-                self.applet.done();
+                self.applets.current().done();
             }
             Ok(RunAnswer::Host) => (),
-            Err(Error::Trap) => logger::panic!("Applet trapped in wasm."),
-            Err(e) => core::panic!("{e:?}"),
+            // The applet is resumed when it is scheduled again.
+            Ok(RunAnswer::OutOfFuel) => trace!("Applet ran out of fuel."),
+            Err(Error::Trap(_)) => self.trap(self.applets.current_id()),
+            Err(e) => {
+                warn!("Failed to run applet: {}", Debug2Format(&e));
+                self.crash(self.applets.current_id(), AppletExit::Trap);
+            }
+        }
+    }
+}
+
+/// Logs the call stack of an applet, symbolized with the name section of its module (if any).
+fn print_backtrace(store: &Store, frames: &[CoreFrame]) {
+    for (i, frame) in frames.iter().enumerate() {
+        match store.func_name(frame.inst, frame.func) {
+            Some(name) => {
+                let name = alloc::format!("{:#}", rustc_demangle::demangle(name));
+                error!("  #{} {} at {:#x}", i, name.as_str(), frame.offset);
+            }
+            None => error!("  #{} function {} at {:#x}", i, frame.func, frame.offset),
         }
     }
 }
@@ -426,6 +812,9 @@ impl<'a> Memory<'a> {
         let store = unsafe { &mut *self.store };
         let args = vec![Val::I32(size), Val::I32(align)];
         let inst = store.last_call().unwrap().inst();
+        // The allocation is not metered because it runs within a host call and can't be suspended.
+        let fuel = store.fuel();
+        store.set_fuel(None);
         let result: Result<Val, Error> = try {
             // TODO: We should ideally account this to the applet performance time.
             match store.invoke(inst, "alloc", args)? {
@@ -433,6 +822,7 @@ impl<'a> Memory<'a> {
                 _ => Err(Error::Invalid)?,
             }
         };
+        store.set_fuel(fuel);
         match result {
             Ok(Val::I32(x)) => x,
             _ => 0,
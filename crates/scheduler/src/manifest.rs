@@ -0,0 +1,137 @@
+// Copyright 2023 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Manifests of applets.
+//!
+//! An applet may declare the parts of the API it uses in a custom section named [`SECTION`]. The
+//! section contains one path per line, like `store`, `usb::serial`, or `syscall`. A path grants
+//! the functions it names and the functions of the modules it names, including submodules. The
+//! scheduler refuses to load an applet importing a function that its manifest doesn't grant.
+//! Applets without manifest may use the whole API.
+//!
+//! An applet may also declare the applets allowed to send it messages in a custom section named
+//! [`SENDERS`], with one applet name per line. Applets without this section don't accept messages.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use wasefire_applet_api::{Api, Id};
+use wasefire_interpreter::{ImportDesc, Module};
+use wasefire_logger as log;
+
+/// Name of the custom section of the manifest.
+pub const SECTION: &str = "wasefire-capabilities";
+
+/// Name of the custom section of the allowed senders.
+pub const SENDERS: &str = "wasefire-ipc";
+
+/// Returns whether the manifest of a module grants all its imports.
+///
+/// The host functions must be sorted by name.
+pub fn check(module: &Module, host_funcs: &[Api<Id>]) -> bool {
+    let Some(manifest) = module.custom_section(SECTION) else { return true };
+    let Ok(manifest) = core::str::from_utf8(manifest) else {
+        log::warn!("Manifest is not UTF-8.");
+        return false;
+    };
+    let granted: Vec<_> = manifest.lines().map(|x| x.trim()).filter(|x| !x.is_empty()).collect();
+    let mut path = Vec::new();
+    for import in module.imports() {
+        if import.module != "env" || !matches!(import.desc, ImportDesc::Func(_)) {
+            continue;
+        }
+        let Ok(index) = host_funcs.binary_search_by_key(&import.name, |x| x.descriptor().name)
+        else {
+            log::warn!("Applet imports unknown function {}.", import.name);
+            return false;
+        };
+        path.clear();
+        host_funcs[index].path(&mut path);
+        if !granted.iter().any(|x| grants(x, &path)) {
+            log::warn!("Manifest doesn't grant {}.", import.name);
+            return false;
+        }
+    }
+    true
+}
+
+/// Returns the names of the applets allowed to send messages to a module.
+pub fn senders(module: &Module) -> Vec<String> {
+    let Some(senders) = module.custom_section(SENDERS) else { return Vec::new() };
+    let Ok(senders) = core::str::from_utf8(senders) else {
+        log::warn!("Allowed senders are not UTF-8.");
+        return Vec::new();
+    };
+    senders.lines().map(|x| x.trim()).filter(|x| !x.is_empty()).map(|x| x.to_string()).collect()
+}
+
+/// Returns whether a path of the manifest grants a function given its path.
+fn grants(granted: &str, path: &[&str]) -> bool {
+    let granted = granted.split("::");
+    granted.clone().count() <= path.len() && granted.zip(path).all(|(x, y)| x == *y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a module importing `led::count` with an optional manifest.
+    fn binary(manifest: Option<&str>) -> Vec<u8> {
+        let mut wasm = b"\0asm\x01\0\0\0\x01\x04\x01\x60\0\0".to_vec();
+        wasm.extend_from_slice(b"\x02\x0a\x01\x03env\x02lc\0\0");
+        if let Some(manifest) = manifest {
+            custom_section(&mut wasm, SECTION, manifest);
+        }
+        wasm
+    }
+
+    fn custom_section(wasm: &mut Vec<u8>, name: &str, content: &str) {
+        wasm.extend_from_slice(&[0, (1 + name.len() + content.len()) as u8]);
+        wasm.push(name.len() as u8);
+        wasm.extend_from_slice(name.as_bytes());
+        wasm.extend_from_slice(content.as_bytes());
+    }
+
+    #[test]
+    fn check_imports() {
+        let mut host_funcs = Vec::new();
+        Api::<Id>::iter(&mut host_funcs, |x| x);
+        host_funcs.sort_by_key(|x| x.descriptor().name);
+        let check = |manifest| check(&Module::new(&binary(manifest)).unwrap(), &host_funcs);
+        assert!(check(None));
+        assert!(check(Some("store\nled\n")));
+        assert!(check(Some("led::count")));
+        assert!(!check(Some("")));
+        assert!(!check(Some("store\nusb::serial")));
+    }
+
+    #[test]
+    fn grants_prefix() {
+        assert!(grants("usb", &["usb", "serial", "write"]));
+        assert!(grants("usb::serial", &["usb", "serial", "write"]));
+        assert!(grants("usb::serial::write", &["usb", "serial", "write"]));
+        assert!(!grants("usb::serial::write::x", &["usb", "serial", "write"]));
+        assert!(!grants("store", &["usb", "serial", "write"]));
+        assert!(!grants("us", &["usb", "serial", "write"]));
+    }
+
+    #[test]
+    fn parse_senders() {
+        let senders = |wasm: &[u8]| senders(&Module::new(wasm).unwrap());
+        let mut wasm = binary(None);
+        assert!(senders(&wasm).is_empty());
+        custom_section(&mut wasm, SENDERS, "ctap\n\n pin \n");
+        assert_eq!(senders(&wasm), ["ctap", "pin"]);
+    }
+}
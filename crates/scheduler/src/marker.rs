@@ -0,0 +1,90 @@
+// Copyright 2023 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Markers of validated applets.
+//!
+//! When an applet is installed, the scheduler validates it and persists a marker next to it. The
+//! marker is the side-table of the module followed by an HMAC-SHA256 of the module and its
+//! side-table. At boot, the scheduler checks the HMAC instead of validating the module again.
+//!
+//! The HMAC key is generated randomly the first time it is needed and stored in the scheduler store
+//! at [`KEY`]. It never leaves the device. Boards without HMAC-SHA256 or random number generator
+//! support don't write markers and validate applets at every boot.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use digest::Mac;
+use wasefire_board_api::rng::Api as _;
+use wasefire_board_api::{self as board, Api as Board, Support};
+use {wasefire_logger as log, wasefire_store as store};
+
+/// Store key of the HMAC key.
+///
+/// This key is reserved and can't be accessed by applets.
+pub const KEY: usize = 4095;
+
+const TAG_LEN: usize = 32;
+
+/// Returns the marker of a valid module given its side-table.
+///
+/// Returns `None` if markers are not supported.
+pub fn seal<B: Board>(
+    store: &mut store::Store<B::Storage>, wasm: &[u8], side_table: &[u8],
+) -> Option<Vec<u8>> {
+    let tag = hmac::<B>(&key::<B>(store)?, wasm, side_table).finalize().into_bytes();
+    let mut result = side_table.to_vec();
+    result.extend_from_slice(&tag);
+    Some(result)
+}
+
+/// Returns the side-table of a module if its marker is authentic.
+///
+/// Returns `None` if markers are not supported, even if the store has a key from a previous build.
+pub fn open<B: Board>(
+    store: &store::Store<B::Storage>, wasm: &[u8], marker: &[u8],
+) -> Option<Box<[u8]>> {
+    if !board::crypto::HmacSha256::<B>::SUPPORT {
+        return None;
+    }
+    let key = store.find(KEY).ok()??;
+    let side_table = marker.get(.. marker.len().checked_sub(TAG_LEN)?)?;
+    let tag = &marker[side_table.len() ..];
+    hmac::<B>(&key, wasm, side_table).verify_slice(tag).ok()?;
+    Some(side_table.into())
+}
+
+fn key<B: Board>(store: &mut store::Store<B::Storage>) -> Option<Vec<u8>> {
+    if !board::crypto::HmacSha256::<B>::SUPPORT {
+        return None;
+    }
+    if let Some(key) = store.find(KEY).ok()? {
+        return Some(key);
+    }
+    let mut key = [0; 32];
+    board::Rng::<B>::fill_bytes(&mut key).ok()?;
+    if let Err(e) = store.insert(KEY, &key) {
+        log::warn!("Failed to store validation key: {}", log::Debug2Format(&e));
+        return None;
+    }
+    Some(key.to_vec())
+}
+
+fn hmac<B: Board>(key: &[u8], wasm: &[u8], side_table: &[u8]) -> board::crypto::HmacSha256<B> {
+    let mut hmac = board::crypto::HmacSha256::<B>::new_from_slice(key).unwrap();
+    hmac.update(&(wasm.len() as u32).to_le_bytes());
+    hmac.update(wasm);
+    hmac.update(side_table);
+    hmac
+}
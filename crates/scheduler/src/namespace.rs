@@ -0,0 +1,195 @@
+// Copyright 2023 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Store namespaces of applets.
+//!
+//! Each applet slot owns a disjoint range of [`KEYS`] keys of the scheduler store. Applets address
+//! their range with keys starting from zero. The range of the first slot starts at store key zero,
+//! such that the entries of an applet installed before namespaces remain accessible to the applet
+//! of the first slot.
+//!
+//! The board may bound the number of bytes stored by each applet with a quota. Uninstalling an
+//! applet wipes its namespace.
+
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use wasefire_store::{Storage, Store, StoreError, StoreResult, StoreUpdate};
+
+use crate::stores::AppletId;
+use crate::{marker, MAX_APPLETS};
+
+/// Number of keys of a namespace.
+///
+/// The keys after the last namespace are reserved by the scheduler.
+pub const KEYS: usize = marker::KEY / MAX_APPLETS;
+
+/// Returns the store key of an applet key.
+pub fn key(id: AppletId, key: usize) -> Result<usize, StoreError> {
+    if KEYS <= key {
+        return Err(StoreError::InvalidArgument);
+    }
+    Ok(id.0 * KEYS + key)
+}
+
+/// Returns the store keys of a range of applet keys.
+pub fn keys(id: AppletId, keys: Range<usize>) -> Result<Range<usize>, StoreError> {
+    if keys.start > keys.end || KEYS < keys.end {
+        return Err(StoreError::InvalidArgument);
+    }
+    let base = id.0 * KEYS;
+    Ok(base + keys.start .. base + keys.end)
+}
+
+/// Upper bounds of the number of bytes stored by each applet.
+///
+/// The bounds grow with each write and are only computed exactly by scanning the store when a write
+/// would exceed the quota. Writes that fit the quota thus don't scan the store.
+#[derive(Default)]
+pub struct Usage([Option<usize>; MAX_APPLETS]);
+
+impl Usage {
+    /// Checks that an applet may write a value of `len` bytes to some store keys.
+    ///
+    /// The entries of those keys are overwritten, so they don't count towards the quota.
+    pub fn check<S: Storage>(
+        &mut self, store: &Store<S>, id: AppletId, keys: &Range<usize>, len: usize,
+        quota: Option<usize>,
+    ) -> Result<(), StoreError> {
+        let Some(quota) = quota else { return Ok(()) };
+        let bound = &mut self.0[id.0];
+        if let Some(used) = bound {
+            if len <= quota.saturating_sub(*used) {
+                *used += len;
+                return Ok(());
+            }
+        }
+        let namespace = self::keys(id, 0 .. KEYS)?;
+        let mut used = len;
+        for handle in store.iter()? {
+            let handle = handle?;
+            let key = handle.get_key();
+            if namespace.contains(&key) && !keys.contains(&key) {
+                used += handle.get_length(store)?;
+            }
+        }
+        match used <= quota {
+            true => {
+                *bound = Some(used);
+                Ok(())
+            }
+            false => Err(StoreError::NoCapacity),
+        }
+    }
+}
+
+/// Removes all the entries of an applet.
+pub fn wipe<S: Storage>(store: &mut Store<S>, id: AppletId) -> StoreResult<()> {
+    let namespace = keys(id, 0 .. KEYS)?;
+    let mut updates = Vec::new();
+    for handle in store.iter()? {
+        let key = handle?.get_key();
+        if namespace.contains(&key) {
+            updates.push(StoreUpdate::Remove { key });
+        }
+    }
+    for updates in updates.chunks(store.max_updates()) {
+        store.transaction::<&[u8]>(updates)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use wasefire_store::{BufferOptions, BufferStorage};
+
+    use super::*;
+
+    fn store() -> Store<BufferStorage> {
+        let options = BufferOptions {
+            word_size: 4,
+            page_size: 4096,
+            max_word_writes: 2,
+            max_page_erases: 10000,
+            strict_mode: true,
+        };
+        let storage = BufferStorage::new(vec![0xff; 4 * 4096].into_boxed_slice(), options);
+        Store::new(storage).ok().unwrap()
+    }
+
+    #[test]
+    fn disjoint_keys() {
+        assert_eq!(key(AppletId(0), 5), Ok(5));
+        assert_eq!(key(AppletId(1), 5), Ok(KEYS + 5));
+        assert_eq!(keys(AppletId(1), 2 .. 4), Ok(KEYS + 2 .. KEYS + 4));
+        assert_eq!(key(AppletId(0), KEYS), Err(StoreError::InvalidArgument));
+        assert_eq!(key(AppletId(1), usize::MAX), Err(StoreError::InvalidArgument));
+        assert_eq!(keys(AppletId(0), Range { start: 4, end: 2 }), Err(StoreError::InvalidArgument));
+        assert_eq!(keys(AppletId(0), 0 .. KEYS + 1), Err(StoreError::InvalidArgument));
+        assert!(key(AppletId(MAX_APPLETS - 1), KEYS - 1).unwrap() < marker::KEY);
+    }
+
+    #[test]
+    fn quota_and_wipe() {
+        let mut store = store();
+        let mut usage = Usage::default();
+        let (a, b) = (AppletId(0), AppletId(1));
+        store.insert(key(a, 0).unwrap(), &[0; 10]).unwrap();
+        store.insert(key(a, 1).unwrap(), &[0; 10]).unwrap();
+        store.insert(key(b, 0).unwrap(), &[0; 10]).unwrap();
+        let mut check = |store: &Store<_>, id, key: usize, len| {
+            usage.check(store, id, &keys(id, key .. key + 1).unwrap(), len, Some(25))
+        };
+        assert_eq!(check(&store, a, 2, 5), Ok(()));
+        assert_eq!(check(&store, a, 2, 6), Err(StoreError::NoCapacity));
+        // Overwritten entries don't count.
+        assert_eq!(check(&store, a, 1, 15), Ok(()));
+        assert_eq!(check(&store, b, 1, 15), Ok(()));
+        wipe(&mut store, a).unwrap();
+        assert_eq!(store.find(key(a, 0).unwrap()), Ok(None));
+        assert_eq!(store.find(key(a, 1).unwrap()), Ok(None));
+        assert_eq!(store.find(key(b, 0).unwrap()), Ok(Some(vec![0; 10])));
+    }
+
+    #[test]
+    fn usage_bound() {
+        let mut store = store();
+        let mut usage = Usage::default();
+        let a = AppletId(0);
+        let keys = keys(a, 0 .. 1).unwrap();
+        assert_eq!(usage.check(&store, a, &keys, 10, Some(25)), Ok(()));
+        assert_eq!(usage.0[0], Some(10));
+        store.insert(key(a, 0).unwrap(), &[0; 10]).unwrap();
+        // The bound grows without scanning while writes fit the quota.
+        assert_eq!(usage.check(&store, a, &keys, 10, Some(25)), Ok(()));
+        assert_eq!(usage.0[0], Some(20));
+        // The bound is computed exactly when a write doesn't fit.
+        assert_eq!(usage.check(&store, a, &keys, 10, Some(25)), Ok(()));
+        assert_eq!(usage.0[0], Some(10));
+    }
+
+    #[test]
+    fn wipe_many() {
+        let mut store = store();
+        let a = AppletId(0);
+        let count = store.max_updates() + 2;
+        for i in 0 .. count {
+            store.insert(key(a, i).unwrap(), &[0; 4]).unwrap();
+        }
+        wipe(&mut store, a).unwrap();
+        assert_eq!(store.iter().unwrap().count(), 0);
+    }
+}
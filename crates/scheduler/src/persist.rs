@@ -0,0 +1,209 @@
+// Copyright 2023 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Persistence of the applets in the board applet storage.
+//!
+//! The applet storage is a dedicated flash region (or file). It is split in [`MAX_APPLETS`] slots
+//! of the same number of pages, one for each applet. Each slot has the following layout:
+//!
+//! - The length of the module as a little-endian 32-bits integer. The value `0xffffffff` (erased
+//!   flash) means that no applet is installed.
+//! - The length of the name as a little-endian 32-bits integer.
+//! - The name of the applet in UTF-8.
+//! - The module.
+//! - The length of the marker as a little-endian 32-bits integer. The value `0xffffffff` means that
+//!   the applet has no marker.
+//! - The marker (see [`crate::marker`]).
+//!
+//! The layout is contiguous across the pages of the slot, and padded with `0xff` to the next word.
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use wasefire_store::{Storage, StorageError, StorageIndex, StorageResult};
+
+use crate::MAX_APPLETS;
+
+const HEADER_LEN: usize = 8;
+
+/// Applet persisted in a slot.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Applet {
+    pub name: String,
+    pub wasm: Box<[u8]>,
+    pub marker: Option<Box<[u8]>>,
+}
+
+/// Reads the applet of a slot, if any.
+pub fn read<S: Storage>(storage: &S, slot: usize) -> StorageResult<Option<Applet>> {
+    let header = read_range(storage, slot, 0, HEADER_LEN)?;
+    let wasm_len = u32::from_le_bytes(header[.. 4].try_into().unwrap());
+    if wasm_len == 0xffffffff {
+        return Ok(None);
+    }
+    let wasm_len = wasm_len as usize;
+    let name_len = u32::from_le_bytes(header[4 ..].try_into().unwrap()) as usize;
+    let total = HEADER_LEN.checked_add(name_len).and_then(|x| x.checked_add(wasm_len));
+    let total = match total.and_then(|x| x.checked_add(4)) {
+        Some(x) if x <= capacity(storage) => x,
+        _ => return Err(StorageError::OutOfBounds),
+    };
+    let name = read_range(storage, slot, HEADER_LEN, name_len)?;
+    let name = String::from_utf8(name).map_err(|_| StorageError::CustomError)?;
+    let wasm = read_range(storage, slot, HEADER_LEN + name_len, wasm_len)?.into_boxed_slice();
+    let marker_len = read_range(storage, slot, total - 4, 4)?;
+    let marker_len = u32::from_le_bytes(marker_len[..].try_into().unwrap());
+    let marker = match marker_len {
+        0xffffffff => None,
+        len if len as usize <= capacity(storage) - total => {
+            Some(read_range(storage, slot, total, len as usize)?.into_boxed_slice())
+        }
+        _ => return Err(StorageError::OutOfBounds),
+    };
+    Ok(Some(Applet { name, wasm, marker }))
+}
+
+/// Writes an applet to a slot, replacing the previous one (if any).
+pub fn write<S: Storage>(
+    storage: &mut S, slot: usize, name: &str, wasm: &[u8], marker: Option<&[u8]>,
+) -> StorageResult<()> {
+    let first_page = first_page(storage, slot)?;
+    let marker_len = marker.map_or(0, |x| x.len());
+    let mut data = Vec::with_capacity(HEADER_LEN + name.len() + wasm.len() + 4 + marker_len);
+    data.extend_from_slice(&(wasm.len() as u32).to_le_bytes());
+    data.extend_from_slice(&(name.len() as u32).to_le_bytes());
+    data.extend_from_slice(name.as_bytes());
+    data.extend_from_slice(wasm);
+    match marker {
+        None => data.extend_from_slice(&0xffffffffu32.to_le_bytes()),
+        Some(marker) => {
+            data.extend_from_slice(&(marker.len() as u32).to_le_bytes());
+            data.extend_from_slice(marker);
+        }
+    }
+    let word_size = storage.word_size();
+    data.resize(data.len().next_multiple_of(word_size), 0xff);
+    if capacity(storage) < data.len() {
+        return Err(StorageError::OutOfBounds);
+    }
+    let page_size = storage.page_size();
+    for (page, chunk) in data.chunks(page_size).enumerate() {
+        let page = first_page + page;
+        storage.erase_page(page)?;
+        storage.write_slice(StorageIndex { page, byte: 0 }, chunk)?;
+    }
+    Ok(())
+}
+
+/// Erases the applet of a slot (if any).
+pub fn erase<S: Storage>(storage: &mut S, slot: usize) -> StorageResult<()> {
+    // Only the header needs to be erased, because it's the only part that is read unconditionally.
+    storage.erase_page(first_page(storage, slot)?)
+}
+
+/// Returns the number of pages of a slot.
+fn slot_pages<S: Storage>(storage: &S) -> usize {
+    storage.num_pages() / MAX_APPLETS
+}
+
+fn first_page<S: Storage>(storage: &S, slot: usize) -> StorageResult<usize> {
+    if MAX_APPLETS <= slot || slot_pages(storage) == 0 {
+        return Err(StorageError::OutOfBounds);
+    }
+    Ok(slot * slot_pages(storage))
+}
+
+/// Returns the number of bytes of a slot.
+fn capacity<S: Storage>(storage: &S) -> usize {
+    slot_pages(storage) * storage.page_size()
+}
+
+fn read_range<S: Storage>(
+    storage: &S, slot: usize, offset: usize, length: usize,
+) -> StorageResult<Vec<u8>> {
+    let first_page = first_page(storage, slot)?;
+    let page_size = storage.page_size();
+    let mut result = vec![0; length];
+    let mut done = 0;
+    while done < length {
+        let page = first_page + (offset + done) / page_size;
+        let byte = (offset + done) % page_size;
+        let len = core::cmp::min(length - done, page_size - byte);
+        let slice = storage.read_slice(StorageIndex { page, byte }, len)?;
+        result[done .. done + len].copy_from_slice(&slice);
+        done += len;
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use wasefire_store::{BufferOptions, BufferStorage};
+
+    use super::*;
+
+    fn new_storage() -> BufferStorage {
+        let options = BufferOptions {
+            word_size: 4,
+            page_size: 64,
+            max_word_writes: 2,
+            max_page_erases: 10000,
+            strict_mode: true,
+        };
+        BufferStorage::new(vec![0xff; 4 * MAX_APPLETS * 64].into_boxed_slice(), options)
+    }
+
+    fn applet(name: &str, wasm: &[u8], marker: Option<&[u8]>) -> Applet {
+        Applet { name: name.into(), wasm: wasm.into(), marker: marker.map(|x| x.into()) }
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut storage = new_storage();
+        assert_eq!(read(&storage, 0), Ok(None));
+        let wasm: Vec<u8> = (0 .. 150).collect();
+        write(&mut storage, 0, "hello", &wasm, None).unwrap();
+        assert_eq!(read(&storage, 0), Ok(Some(applet("hello", &wasm, None))));
+        write(&mut storage, 0, "world", b"\0asm", Some(b"marker")).unwrap();
+        assert_eq!(read(&storage, 0), Ok(Some(applet("world", b"\0asm", Some(b"marker")))));
+        erase(&mut storage, 0).unwrap();
+        assert_eq!(read(&storage, 0), Ok(None));
+    }
+
+    #[test]
+    fn independent_slots() {
+        let mut storage = new_storage();
+        let wasm: Vec<u8> = (0 .. 200).collect();
+        for slot in 0 .. MAX_APPLETS {
+            write(&mut storage, slot, "hello", &wasm[slot ..], None).unwrap();
+        }
+        erase(&mut storage, 0).unwrap();
+        assert_eq!(read(&storage, 0), Ok(None));
+        for slot in 1 .. MAX_APPLETS {
+            assert_eq!(read(&storage, slot), Ok(Some(applet("hello", &wasm[slot ..], None))));
+        }
+        assert_eq!(read(&storage, MAX_APPLETS), Err(StorageError::OutOfBounds));
+    }
+
+    #[test]
+    fn too_large() {
+        let mut storage = new_storage();
+        let result = write(&mut storage, 0, "hello", &[0; 4 * 64], None);
+        assert_eq!(result, Err(StorageError::OutOfBounds));
+        let result = write(&mut storage, 0, "hello", &[0; 200], Some(&[0; 40]));
+        assert_eq!(result, Err(StorageError::OutOfBounds));
+    }
+}
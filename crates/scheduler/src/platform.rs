@@ -0,0 +1,105 @@
+// Copyright 2023 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use wasefire_board_api::protocol::Api as _;
+use wasefire_board_api::{self as board, Api as Board};
+use wasefire_logger as log;
+use wasefire_protocol::{AppletInfo, Error, Request, Response};
+
+use crate::stores::AppletBinary;
+use crate::{marker, namespace, persist, Scheduler};
+
+/// Processes all pending protocol requests.
+pub fn process<B: Board>(scheduler: &mut Scheduler<B>) {
+    loop {
+        let request = match board::Protocol::<B>::read() {
+            Ok(Some(x)) => x,
+            Ok(None) => break,
+            Err(_) => {
+                log::warn!("Failed to read protocol request.");
+                break;
+            }
+        };
+        let response = match Request::deserialize(&request) {
+            Ok(request) => process_request(scheduler, request),
+            Err(error) => Response::Error(error).serialize(),
+        };
+        if board::Protocol::<B>::write(&response).is_err() {
+            log::warn!("Failed to write protocol response.");
+        }
+    }
+}
+
+fn process_request<B: Board>(scheduler: &mut Scheduler<B>, request: Request) -> Vec<u8> {
+    log::debug!("Processing {}", log::Debug2Format(&request));
+    let response = match request {
+        Request::Install { name, wasm } => {
+            let binary = AppletBinary::new(name.into(), Box::from(wasm));
+            let id = scheduler.applets.slot(name).ok_or(Error::NoSpace);
+            id.and_then(|id| {
+                if scheduler.applet_storage.is_none() {
+                    return Err(Error::Unsupported);
+                }
+                // A new applet starts with an empty namespace, even if a previous uninstall was
+                // interrupted.
+                if scheduler.applets.find(name).is_none() {
+                    namespace::wipe(&mut scheduler.store, id).map_err(|_| Error::Internal)?;
+                }
+                scheduler.load(id, binary)?;
+                let binary = scheduler.applets.get(id).binary.as_ref().unwrap();
+                let side_table = binary.side_table.as_ref().unwrap();
+                let marker = marker::seal::<B>(&mut scheduler.store, wasm, side_table);
+                let storage = scheduler.applet_storage.as_mut().unwrap();
+                if persist::write(storage, id.0, name, wasm, marker.as_deref()).is_err() {
+                    // The applet must not run if it doesn't survive a reboot.
+                    scheduler.unload(id);
+                    return Err(Error::Internal);
+                }
+                Ok(Response::Ok)
+            })
+        }
+        Request::List => {
+            let mut applets = Vec::new();
+            for (_, applet) in scheduler.applets.iter() {
+                let binary = applet.binary.as_ref().unwrap();
+                // SAFETY: The result is only used while the binary is alive.
+                let size = unsafe { binary.wasm() }.len() as u32;
+                applets.push(AppletInfo { name: &binary.name, size });
+            }
+            return Response::List(applets).serialize();
+        }
+        Request::Uninstall { name } => match scheduler.applets.find(name) {
+            Some(id) => {
+                scheduler.unload(id);
+                match scheduler.applet_storage.as_mut().map(|x| persist::erase(x, id.0)) {
+                    None => Err(Error::Unsupported),
+                    Some(Err(_)) => Err(Error::Internal),
+                    Some(Ok(())) => match namespace::wipe(&mut scheduler.store, id) {
+                        Ok(()) => Ok(Response::Ok),
+                        Err(_) => Err(Error::Internal),
+                    },
+                }
+            }
+            None => Err(Error::NotFound),
+        },
+        Request::Logs => {
+            let logs = scheduler.logs.drain(..).collect::<Vec<_>>();
+            return Response::Logs(&logs).serialize();
+        }
+    };
+    response.unwrap_or_else(Response::Error).serialize()
+}
@@ -0,0 +1,154 @@
+// Copyright 2023 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Memory pool for the linear memory of applets.
+//!
+//! The board provides the pool and a quota. When an applet starts, its slot allocates as much
+//! memory as the memory limits of the applet permit, up to the quota. Growing the memory of the
+//! applet is backed by this allocation. The allocation is released when the applet is unloaded.
+
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use wasefire_board_api::AppletMemory;
+use wasefire_interpreter::{Module, MEMORY_ALIGN};
+
+use crate::stores::AppletId;
+use crate::MAX_APPLETS;
+
+pub struct Pool {
+    base: *mut u8,
+    len: usize,
+    quota: usize,
+    slots: [Option<Range<usize>>; MAX_APPLETS],
+}
+
+impl Pool {
+    pub fn new(memory: AppletMemory) -> Self {
+        let AppletMemory { pool, quota } = memory;
+        assert_eq!(pool.as_ptr() as usize % MEMORY_ALIGN, 0);
+        Pool { base: pool.as_mut_ptr(), len: pool.len(), quota, slots: Default::default() }
+    }
+
+    /// Allocates the linear memory of an applet slot for a module.
+    ///
+    /// Returns `None` if the module needs more than the quota or the pool doesn't have enough
+    /// memory left. The slot must not have an allocation.
+    pub fn alloc(&mut self, id: AppletId, module: &Module) -> Option<&'static mut [u8]> {
+        assert!(self.slots[id.0].is_none());
+        let (min, max) = limits(module);
+        let max = core::cmp::min(max, self.quota) & !(MEMORY_ALIGN - 1);
+        if max < min {
+            return None;
+        }
+        // Take the first gap large enough for the minimum.
+        let mut used: Vec<_> = self.slots.iter().flatten().cloned().collect();
+        used.sort_by_key(|x| x.start);
+        used.push(self.len .. self.len);
+        let mut start = 0;
+        for range in used {
+            let len = core::cmp::min(range.start - start, max);
+            if min <= len {
+                self.slots[id.0] = Some(start .. start + len);
+                // SAFETY: The range is within the pool and disjoint from other allocations. The
+                // pool is never dropped.
+                return Some(unsafe { core::slice::from_raw_parts_mut(self.base.add(start), len) });
+            }
+            start = range.end;
+        }
+        None
+    }
+
+    /// Releases the linear memory of an applet slot (if any).
+    ///
+    /// The applet using the memory must be dropped.
+    pub fn free(&mut self, id: AppletId) {
+        self.slots[id.0] = None;
+    }
+}
+
+/// Returns the minimum and maximum number of bytes of the linear memory of a module.
+///
+/// All memories but the last get their minimum size (see `Store::instantiate()`).
+fn limits(module: &Module) -> (usize, usize) {
+    let bytes = |pages: u32| (pages as usize).saturating_mul(0x10000);
+    let limits = module.mem_limits();
+    let min = limits.iter().fold(0usize, |x, y| x.saturating_add(bytes(y.min)));
+    let max = match limits.last() {
+        None => 0,
+        Some(last) => (min - bytes(last.min)).saturating_add(bytes(last.max)),
+    };
+    (min, max)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::boxed::Box;
+
+    use super::*;
+
+    /// Returns a module with a memory of `min` pages and optionally `max` pages.
+    fn binary(min: u8, max: Option<u8>) -> Vec<u8> {
+        let mut wasm = b"\0asm\x01\0\0\0\x05".to_vec();
+        match max {
+            None => wasm.extend_from_slice(&[3, 1, 0, min]),
+            Some(max) => wasm.extend_from_slice(&[4, 1, 1, min, max]),
+        }
+        wasm
+    }
+
+    fn pool(pages: usize, quota: usize) -> Pool {
+        #[repr(align(16))]
+        struct Page([u8; 0x10000]);
+        let pool: Vec<_> = (0 .. pages).map(|_| Page([0; 0x10000])).collect();
+        let pool = Box::leak(pool.into_boxed_slice());
+        let len = pages * 0x10000;
+        // SAFETY: The pages are contiguous and leaked.
+        let pool = unsafe { core::slice::from_raw_parts_mut(pool.as_mut_ptr() as *mut u8, len) };
+        Pool::new(AppletMemory { pool, quota: quota * 0x10000 })
+    }
+
+    #[test]
+    fn quota() {
+        let mut pool = pool(4, 2);
+        let wasm = binary(1, None);
+        let module = Module::new(&wasm).unwrap();
+        assert_eq!(pool.alloc(AppletId(0), &module).unwrap().len(), 0x20000);
+        let wasm = binary(3, Some(3));
+        let module = Module::new(&wasm).unwrap();
+        assert!(pool.alloc(AppletId(1), &module).is_none());
+    }
+
+    #[test]
+    fn reuse() {
+        let mut pool = pool(3, 2);
+        let wasm = binary(1, Some(1));
+        let small = Module::new(&wasm).unwrap();
+        let wasm = binary(2, Some(2));
+        let large = Module::new(&wasm).unwrap();
+        assert_eq!(pool.alloc(AppletId(0), &small).unwrap().len(), 0x10000);
+        assert_eq!(pool.alloc(AppletId(1), &large).unwrap().len(), 0x20000);
+        pool.free(AppletId(0));
+        assert!(pool.alloc(AppletId(0), &large).is_none());
+        pool.free(AppletId(1));
+        assert_eq!(pool.alloc(AppletId(0), &large).unwrap().len(), 0x20000);
+    }
+
+    #[test]
+    fn no_memory() {
+        let mut pool = pool(0, 2);
+        let module = Module::new(b"\0asm\x01\0\0\0").unwrap();
+        assert_eq!(pool.alloc(AppletId(0), &module).unwrap().len(), 0);
+    }
+}
@@ -0,0 +1,119 @@
+// Copyright 2023 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Signatures of applets.
+//!
+//! When the board has a trust anchor, applets must end with a custom section named [`SECTION`]
+//! holding an ECDSA P-256 signature by the trust anchor. The signed message is the SHA-256 of the
+//! module up to this section. The section is exactly [`LEN`] bytes: the section header followed by
+//! the `r` and `s` components of the signature, each encoded in big-endian.
+//!
+//! The signature is verified before the module is parsed, such that unsigned bytes are never
+//! interpreted.
+
+use digest::Digest;
+use typenum::U32;
+use wasefire_board_api::crypto::{ecc, Hash};
+use wasefire_board_api::{self as board, Api as Board, Support, TrustAnchor};
+use wasefire_logger as log;
+
+/// Name of the custom section of the signature.
+pub const SECTION: &str = "wasefire-signature";
+
+/// Length of the signature section (including its header).
+pub const LEN: usize = HEADER.len() + SECTION.len() + 64;
+
+/// Header of the signature section: id, size, and name length.
+const HEADER: [u8; 3] = [0, (1 + SECTION.len() + 64) as u8, SECTION.len() as u8];
+
+/// Returns whether a module is signed by the trust anchor of the board (if any).
+pub fn check<B: Board>(wasm: &[u8]) -> bool {
+    let Some(anchor) = B::applet_trust_anchor() else { return true };
+    if !board::crypto::P256::<B>::SUPPORT || !board::crypto::Sha256::<B>::SUPPORT {
+        log::warn!("Board doesn't support P-256 signatures.");
+        return false;
+    }
+    verify::<board::crypto::P256<B>, board::crypto::Sha256<B>>(&anchor, wasm)
+}
+
+fn verify<P: ecc::Api<U32>, H: Hash<OutputSize = U32>>(anchor: &TrustAnchor, wasm: &[u8]) -> bool {
+    let Some(signed) = wasm.len().checked_sub(LEN).map(|x| &wasm[.. x]) else {
+        log::warn!("Applet is too short to be signed.");
+        return false;
+    };
+    let section = &wasm[signed.len() ..];
+    let (header, section) = section.split_at(HEADER.len());
+    let (name, signature) = section.split_at(SECTION.len());
+    if header != HEADER || name != SECTION.as_bytes() {
+        log::warn!("Applet is not signed.");
+        return false;
+    }
+    let (r, s) = signature.split_at(32);
+    let m = H::digest(signed);
+    let (x, y) = (&anchor.x.into(), &anchor.y.into());
+    match P::ecdsa_verify(&m, x, y, r.into(), s.into()) {
+        Ok(true) => true,
+        _ => {
+            log::warn!("Applet signature is invalid.");
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use ecc::Api as _;
+
+    use super::*;
+
+    type P256 = <board::Unsupported as board::crypto::Api>::P256;
+    type Sha256 = <board::Unsupported as board::crypto::Api>::Sha256;
+
+    const KEY: [u8; 32] = [0x42; 32];
+
+    fn anchor() -> TrustAnchor {
+        let (mut x, mut y) = Default::default();
+        P256::base_point_mul(&KEY.into(), &mut x, &mut y).unwrap();
+        TrustAnchor { x: x.into(), y: y.into() }
+    }
+
+    fn sign(wasm: &[u8]) -> Vec<u8> {
+        let (mut r, mut s) = Default::default();
+        P256::ecdsa_sign(&KEY.into(), &Sha256::digest(wasm), &mut r, &mut s).unwrap();
+        let mut result = wasm.to_vec();
+        result.extend_from_slice(&HEADER);
+        result.extend_from_slice(SECTION.as_bytes());
+        result.extend_from_slice(&r);
+        result.extend_from_slice(&s);
+        assert_eq!(result.len(), wasm.len() + LEN);
+        result
+    }
+
+    #[test]
+    fn verify_signature() {
+        let verify = |wasm: &[u8]| verify::<P256, Sha256>(&anchor(), wasm);
+        let wasm = b"\0asm\x01\0\0\0";
+        let mut signed = sign(wasm);
+        assert!(verify(&signed));
+        assert!(!verify(wasm));
+        assert!(!verify(&signed[1 ..]));
+        signed[4] ^= 1;
+        assert!(!verify(&signed));
+        signed[4] ^= 1;
+        *signed.last_mut().unwrap() ^= 1;
+        assert!(!verify(&signed));
+    }
+}
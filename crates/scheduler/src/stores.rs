@@ -12,14 +12,103 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use alloc::boxed::Box;
 use alloc::collections::{BTreeSet, VecDeque};
+use alloc::string::String;
+use alloc::vec::Vec;
 
-use wasefire_board_api::{self as board, Api as Board, Event};
-use wasefire_interpreter::Store;
+use wasefire_board_api::{self as board, Api as Board};
+use wasefire_interpreter::{Linker, Store};
 use wasefire_logger as log;
 
-use crate::event::{Handler, Key};
-use crate::{Memory, Trap};
+use crate::event::{Event, Handler, Key};
+use crate::{Memory, Trap, MAX_APPLETS};
+
+/// Identifies an applet slot.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct AppletId(pub usize);
+
+/// Applet slots of the scheduler.
+pub struct Applets<B: Board> {
+    slots: Vec<Applet<B>>,
+
+    /// The applet being executed (or the last one).
+    current: AppletId,
+}
+
+impl<B: Board> Applets<B> {
+    pub fn new(linker: &'static Linker<'static>) -> Self {
+        let slots = (0 .. MAX_APPLETS).map(|_| Applet::new(linker)).collect();
+        Applets { slots, current: AppletId(0) }
+    }
+
+    pub fn current_id(&self) -> AppletId {
+        self.current
+    }
+
+    pub fn set_current(&mut self, id: AppletId) {
+        self.current = id;
+    }
+
+    pub fn current(&mut self) -> &mut Applet<B> {
+        &mut self.slots[self.current.0]
+    }
+
+    pub fn get(&self, id: AppletId) -> &Applet<B> {
+        &self.slots[id.0]
+    }
+
+    pub fn get_mut(&mut self, id: AppletId) -> &mut Applet<B> {
+        &mut self.slots[id.0]
+    }
+
+    /// Replaces an applet with an empty one.
+    pub fn reset(&mut self, id: AppletId, linker: &'static Linker<'static>) {
+        // The previous applet (and thus its memory) must be dropped before the new one is created.
+        self.slots[id.0] = Applet::default();
+        self.slots[id.0] = Applet::new(linker);
+    }
+
+    /// Returns the loaded applets.
+    pub fn iter(&self) -> impl Iterator<Item = (AppletId, &Applet<B>)> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter(|(_, x)| x.binary.is_some())
+            .map(|(i, x)| (AppletId(i), x))
+    }
+
+    /// Returns the loaded applet with a given name.
+    pub fn find(&self, name: &str) -> Option<AppletId> {
+        self.iter().find(|(_, x)| x.binary.as_ref().unwrap().name == name).map(|(id, _)| id)
+    }
+
+    /// Returns the slot to install an applet with a given name.
+    ///
+    /// This is the slot of the applet with the same name if any, otherwise a free slot.
+    pub fn slot(&self, name: &str) -> Option<AppletId> {
+        self.find(name).or_else(|| self.slots.iter().position(|x| x.binary.is_none()).map(AppletId))
+    }
+
+    /// Returns the applet with a handler for a given key.
+    pub fn owner(&self, key: Key<B>) -> Option<AppletId> {
+        self.iter().find(|(_, x)| x.get(key).is_some()).map(|(id, _)| id)
+    }
+
+    /// Returns an applet waiting to be restarted, if any.
+    pub fn next_restart(&self) -> Option<AppletId> {
+        self.iter().find(|(_, x)| x.status == Status::Restarting).map(|(id, _)| id)
+    }
+
+    /// Returns the next applet ready to execute, in round-robin order.
+    pub fn next_ready(&self) -> Option<AppletId> {
+        let n = self.slots.len();
+        (1 ..= n)
+            .map(|i| (self.current.0 + i) % n)
+            .find(|&i| self.slots[i].is_ready())
+            .map(AppletId)
+    }
+}
 
 pub struct Applet<B: Board> {
     pub store: AppletStore,
@@ -30,9 +119,29 @@ pub struct Applet<B: Board> {
     /// Whether we returned from a callback.
     done: bool,
 
+    /// Whether the applet is waiting for an event.
+    waiting: bool,
+
     handlers: BTreeSet<Handler<B>>,
 
     pub hashes: AppletHashes<B>,
+
+    /// Messages sent to the applet and not yet received.
+    pub mailbox: VecDeque<Message>,
+
+    /// Names of the applets allowed to send messages to the applet.
+    pub senders: Vec<String>,
+
+    /// Whether the applet is running or exited abnormally.
+    pub status: Status,
+
+    /// Number of consecutive abnormal exits.
+    pub crashes: u32,
+
+    /// The module binary, if an applet is loaded.
+    ///
+    /// This field must be dropped after the store, because the store borrows it.
+    pub binary: Option<AppletBinary>,
 }
 
 // We have to implement manually because derive is not able to find the correct bounds.
@@ -42,12 +151,75 @@ impl<B: Board> Default for Applet<B> {
             store: Default::default(),
             events: Default::default(),
             done: Default::default(),
+            waiting: Default::default(),
             handlers: Default::default(),
             hashes: Default::default(),
+            mailbox: Default::default(),
+            senders: Default::default(),
+            status: Default::default(),
+            crashes: Default::default(),
+            binary: None,
         }
     }
 }
 
+/// Execution status of an applet.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Status {
+    /// The applet is running (possibly waiting for events).
+    #[default]
+    Running,
+
+    /// The applet exited abnormally and is not running.
+    ///
+    /// It may be waiting for its restart delay to elapse.
+    Crashed,
+
+    /// The applet exited abnormally and should be restarted.
+    Restarting,
+}
+
+/// Module binary of an applet.
+pub struct AppletBinary {
+    pub name: String,
+    // Invariant: Comes from `Box::into_raw()`.
+    wasm: *mut [u8],
+    /// The serialized side-table of the module, if known to be valid.
+    ///
+    /// This is set once the module is validated, such that restarts don't validate it again.
+    pub side_table: Option<Box<[u8]>>,
+}
+
+impl AppletBinary {
+    pub fn new(name: String, wasm: Box<[u8]>) -> Self {
+        AppletBinary { name, wasm: Box::into_raw(wasm), side_table: None }
+    }
+
+    /// Returns the module binary.
+    ///
+    /// # Safety
+    ///
+    /// The result must not be used after `self` is dropped.
+    pub unsafe fn wasm(&self) -> &'static [u8] {
+        unsafe { &*self.wasm }
+    }
+}
+
+impl Drop for AppletBinary {
+    fn drop(&mut self) {
+        // SAFETY: This comes from `Box::into_raw()` by invariant.
+        drop(unsafe { Box::from_raw(self.wasm) });
+    }
+}
+
+/// Message in the mailbox of an applet.
+pub struct Message {
+    /// Name of the sending applet.
+    pub sender: String,
+
+    pub data: Box<[u8]>,
+}
+
 #[derive(Debug, Default)]
 pub struct AppletStore(Store<'static>);
 
@@ -91,6 +263,15 @@ impl<B: Board> AppletHashes<B> {
 }
 
 impl<B: Board> Applet<B> {
+    fn new(linker: &'static Linker<'static>) -> Self {
+        Applet { store: AppletStore(Store::new(linker)), ..Applet::default() }
+    }
+
+    #[cfg(feature = "profile")]
+    pub fn store(&self) -> &Store<'static> {
+        &self.store.0
+    }
+
     pub fn store_mut(&mut self) -> &mut Store<'static> {
         &mut self.store.0
     }
@@ -101,11 +282,8 @@ impl<B: Board> Applet<B> {
 
     pub fn push(&mut self, event: Event<B>) {
         const MAX_EVENTS: usize = 5;
-        if !self.handlers.contains(&Key::from(&event)) {
-            // This can happen after an event is disabled and the event queue of the board is
-            // flushed.
-            log::trace!("Discarding {}", log::Debug2Format(&event));
-        } else if self.events.contains(&event) {
+        self.waiting = false;
+        if self.events.contains(&event) {
             log::trace!("Merging {}", log::Debug2Format(&event));
         } else if self.events.len() < MAX_EVENTS {
             log::debug!("Pushing {}", log::Debug2Format(&event));
@@ -122,10 +300,18 @@ impl<B: Board> Applet<B> {
         }
         match self.events.pop_front() {
             Some(event) => EventAction::Handle(event),
-            None => EventAction::Wait,
+            None => {
+                self.waiting = true;
+                EventAction::Wait
+            }
         }
     }
 
+    /// Returns whether the applet is running and not waiting for an event.
+    pub fn is_ready(&self) -> bool {
+        self.binary.is_some() && self.status == Status::Running && !self.waiting
+    }
+
     pub fn done(&mut self) {
         self.done = true;
     }
@@ -151,6 +337,10 @@ impl<B: Board> Applet<B> {
         }
     }
 
+    pub fn handlers(&self) -> impl Iterator<Item = &Handler<B>> {
+        self.handlers.iter()
+    }
+
     pub fn get(&self, key: Key<B>) -> Option<&Handler<B>> {
         self.handlers.get(&key)
     }
@@ -17,6 +17,8 @@ set -ex
 
 cargo check --features=std
 cargo check --features=std,log
+cargo check --features=std,profile
+cargo check --features=std,debugger
 cargo check --target=thumbv7em-none-eabi
 cargo check --target=thumbv7em-none-eabi --features=defmt
 cargo fmt -- --check
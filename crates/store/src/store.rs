@@ -447,6 +447,11 @@ impl<S: Storage> Store<S> {
         self.format.max_value_len() as usize
     }
 
+    /// Returns the maximum number of updates per transaction.
+    pub fn max_updates(&self) -> usize {
+        self.format.max_updates() as usize
+    }
+
     /// Returns the length of the value of an entry given its handle.
     fn get_length(&self, handle: &StoreHandle) -> StoreResult<usize> {
         self.check_handle(handle)?;
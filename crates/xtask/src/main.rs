@@ -17,19 +17,23 @@ use std::cmp::Reverse;
 use std::collections::BinaryHeap;
 use std::fmt::Display;
 use std::num::ParseIntError;
+use std::ops::Range;
 use std::os::unix::prelude::CommandExt;
 use std::path::Path;
 use std::process::{Command, Output};
 use std::str::FromStr;
 
-use anyhow::{bail, ensure, Context, Result};
+use anyhow::{anyhow, bail, ensure, Context, Result};
 use clap::Parser;
 use lazy_static::lazy_static;
+use object::{Object, ObjectSymbol};
 use probe_rs::config::TargetSelector;
 use probe_rs::{flashing, Permissions, Session};
 use rustc_demangle::demangle;
 use sha2::{Digest, Sha256};
 use strum::{Display, EnumString};
+use wasefire_scheduler::{persist, MAX_APPLETS};
+use wasefire_store::{BufferOptions, BufferStorage, Storage, StorageIndex};
 
 mod fs;
 
@@ -459,12 +463,14 @@ impl RunnerOptions {
         }
         cargo.env("RUSTFLAGS", rustflags.join(" "));
         cargo.current_dir(format!("crates/runner-{}", self.name));
-        fs::touch("target/wasefire/applet.wasm")?;
         if run && self.name == "host" {
-            let path = Path::new("target/wasefire/storage.bin");
-            if self.erase_flash && path.exists() {
-                fs::remove_file(path)?;
+            for path in ["target/wasefire/storage.bin", "target/wasefire/applet.bin"] {
+                let path = Path::new(path);
+                if self.erase_flash && path.exists() {
+                    fs::remove_file(path)?;
+                }
             }
+            cargo.args(["--", "../../target/wasefire/applet.wasm"]);
             replace_command(cargo);
         } else {
             execute_command(&mut cargo)?;
@@ -530,14 +536,23 @@ impl RunnerOptions {
             "host" => unreachable!(),
             _ => unimplemented!(),
         };
+        let mut session = Session::auto_attach(
+            TargetSelector::Unspecified(chip.to_string()),
+            Permissions::default(),
+        )?;
         if self.erase_flash {
-            let mut session = Session::auto_attach(
-                TargetSelector::Unspecified(chip.to_string()),
-                Permissions::default(),
-            )?;
             println!("Erasing the flash of {}", session.target().name);
             flashing::erase_all(&mut session, None)?;
         }
+        let region = applet_region(&fs::read(&elf)?)?;
+        let slot_len = (region.end - region.start) as usize / MAX_APPLETS;
+        let image = "target/wasefire/applet.bin";
+        fs::write(image, applet_image("target/wasefire/applet.wasm", slot_len)?)?;
+        let base_address = Some(region.start);
+        let format = flashing::Format::Bin(flashing::BinOptions { base_address, skip: 0 });
+        println!("Flashing the applet to {}", session.target().name);
+        flashing::download_file(&mut session, image, format)?;
+        drop(session);
         if self.gdb {
             println!("Use the following 2 commands in different terminals:");
             println!("JLinkGDBServer -device {chip} -if swd -speed 4000 -port 2331");
@@ -620,6 +635,42 @@ fn wrap_command() -> Result<Command> {
     Ok(Command::new(fs::canonicalize("./scripts/wrapper.sh")?))
 }
 
+/// Returns the address range of the applet storage of a runner.
+fn applet_region(elf: &[u8]) -> Result<Range<u64>> {
+    let elf = object::File::parse(elf)?;
+    let symbol = |name: &str| -> Result<u64> {
+        Ok(elf.symbol_by_name(name).with_context(|| format!("missing {name}"))?.address())
+    };
+    Ok(symbol("__sapplet")? .. symbol("__eapplet")?)
+}
+
+/// Returns the image of the first slot of the applet storage with an applet.
+///
+/// The image is built with `wasefire_scheduler::persist` such that it has the same layout as when
+/// the applet is installed by the platform.
+fn applet_image(wasm: &str, slot_len: usize) -> Result<Vec<u8>> {
+    const PAGE_SIZE: usize = 4096;
+    let name = Path::new(wasm).file_stem().and_then(|x| x.to_str()).context("invalid name")?;
+    let wasm = fs::read(wasm)?;
+    let options = BufferOptions {
+        word_size: 4,
+        page_size: PAGE_SIZE,
+        max_word_writes: 2,
+        max_page_erases: 10000,
+        strict_mode: true,
+    };
+    let storage = vec![0xff; slot_len * MAX_APPLETS].into_boxed_slice();
+    let mut storage = BufferStorage::new(storage, options);
+    persist::write(&mut storage, 0, name, &wasm, None).map_err(|e| anyhow!("{e:?}"))?;
+    let mut image = Vec::with_capacity(slot_len);
+    for page in 0 .. slot_len / PAGE_SIZE {
+        let index = StorageIndex { page, byte: 0 };
+        let page = storage.read_slice(index, PAGE_SIZE).map_err(|e| anyhow!("{e:?}"))?;
+        image.extend_from_slice(&page);
+    }
+    Ok(image)
+}
+
 /// Copies a file if its destination .hash changed.
 ///
 /// Returns whether the copy took place.
@@ -647,6 +647,90 @@
   ): void
 // END OF MODULE debug
 
+// START OF MODULE ipc
+// Message passing between applets.
+//
+// Applets are addressed by name. An applet only receives messages from the applets it
+// allows in its `wasefire-ipc` custom section (one applet name per line).
+  // Describes errors passing messages.
+  enum ipc_Error {
+    // A function pre-condition was broken.
+    InvalidArgument = 0,
+
+    // The receiving applet is not loaded.
+    NotFound = 1,
+
+    // The receiving applet doesn't allow messages from the sending applet.
+    NotAllowed = 2,
+
+    // The mailbox of the receiving applet is full.
+    NoCapacity = 3,
+  }
+
+  // Sends a message to an applet.
+  //
+  // The message is queued in the mailbox of the receiving applet, which is notified if
+  // it registered a callback.
+  @external("env", "is")
+  export declare function ipc_send(
+    // Name of the receiving applet.
+    applet_ptr: usize,
+
+    // Length of the name of the receiving applet.
+    applet_len: usize,
+
+    // Address of the message.
+    ptr: usize,
+
+    // Length of the message.
+    //
+    // The platform may limit the length of messages.
+    len: usize,
+  // Zero for success. Otherwise complement of error number.
+  ): isize
+
+  // Receives the oldest message from the mailbox, if any.
+  @external("env", "ir")
+  export declare function ipc_receive(
+    // Where to write the name of the sending applet, if a message is received.
+    //
+    // The (inner) pointer will be allocated by the callee and must be freed by the
+    // caller. It is thus owned by the caller when the function returns.
+    applet_ptr: usize,
+
+    // Where to write the length of the name of the sending applet.
+    applet_len: usize,
+
+    // Where to write the message, if a message is received.
+    //
+    // The (inner) pointer will be allocated by the callee and must be freed by the
+    // caller. It is thus owned by the caller when the function returns.
+    ptr: usize,
+
+    // Where to write the length of the message.
+    len: usize,
+  // One if received. Zero if the mailbox is empty. Otherwise complement of error
+  // number.
+  ): isize
+
+  // Registers a callback when the mailbox is not empty.
+  //
+  // It is possible that the callback is spuriously called.
+  @external("env", "ie")
+  export declare function ipc_register(
+    // Function pointer of the closure to call on messages.
+    handler_func: usize,
+
+    // Opaque data of the closure to call on messages.
+    handler_data: usize,
+  ): void
+
+  // Unregisters the callback.
+  @external("env", "id")
+  export declare function ipc_unregister(
+  ): void
+// END OF MODULE ipc
+
 // START OF MODULE led
 // LED operations.
   // Returns how many LEDs are on the device.
@@ -743,12 +827,14 @@
 
   // Inserts an entry in the store.
   //
-  // If an entry for that key was already present, it is overwritten.
+  // If an entry for that key was already present, it is overwritten. The platform may
+  // limit the total size of the entries of an applet.
   @external("env", "si")
   export declare function store_insert(
     // Key of the entry.
     //
-    // This must be smaller than 4096.
+    // Keys are private to the applet. This must be smaller than a platform limit (the
+    // platform shares the store between applets and reserves some keys).
     key: usize,
 
     // Value of the entry.
@@ -19,6 +19,7 @@
 
 #![no_std]
 wasefire::applet!();
+wasefire::capabilities!("clock", "debug", "led", "scheduling");
 
 use core::time::Duration;
 
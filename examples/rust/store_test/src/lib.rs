@@ -78,17 +78,20 @@ fn test_fragment() {
     debug::assert(store::fragment::find(0 .. 2).unwrap().is_none());
 }
 
-const INSERTED: &[usize] = &[0, 1, 2, 3, 100, 500, 1000, 2000];
+const INSERTED: &[usize] = &[0, 1, 2, 3, 100, 500, 1000];
 const REMOVED: &[usize] = &[2, 500];
 
+/// Last key of the applet namespace.
+const LAST: usize = 2046;
+
 fn reverse(key: usize) -> usize {
-    4095 - key
+    LAST - key
 }
 
 fn value(mut key: usize) -> Vec<u8> {
-    let reversed = key & 0x800 != 0;
+    let reversed = key > LAST / 2;
     if reversed {
-        key = 4095 - key;
+        key = reverse(key);
     }
     let val = key as u8;
     let mut len = val as usize;